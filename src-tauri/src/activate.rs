@@ -48,6 +48,7 @@ struct SecureStorage {
     license_key: Option<String>,
     instance_id: Option<String>,
     selected_cloak_model: Option<String>,
+    local_llm_base_url: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -61,6 +62,7 @@ pub struct StorageResult {
     license_key: Option<String>,
     instance_id: Option<String>,
     selected_cloak_model: Option<String>,
+    local_llm_base_url: Option<String>,
 }
 
 #[tauri::command]
@@ -80,6 +82,7 @@ pub async fn secure_storage_save(app: AppHandle, items: Vec<StorageItem>) -> Res
             "cloak_license_key" => storage.license_key = Some(item.value),
             "cloak_instance_id" => storage.instance_id = Some(item.value),
             "selected_cloak_model" => storage.selected_cloak_model = Some(item.value),
+            "local_llm_base_url" => storage.local_llm_base_url = Some(item.value),
             _ => return Err(format!("Invalid storage key: {}", item.key)),
         }
     }
@@ -102,6 +105,7 @@ pub async fn secure_storage_get(app: AppHandle) -> Result<StorageResult, String>
             license_key: None,
             instance_id: None,
             selected_cloak_model: None,
+            local_llm_base_url: None,
         });
     }
 
@@ -115,6 +119,7 @@ pub async fn secure_storage_get(app: AppHandle) -> Result<StorageResult, String>
         license_key: storage.license_key,
         instance_id: storage.instance_id,
         selected_cloak_model: storage.selected_cloak_model,
+        local_llm_base_url: storage.local_llm_base_url,
     })
 }
 
@@ -137,6 +142,7 @@ pub async fn secure_storage_remove(app: AppHandle, keys: Vec<String>) -> Result<
             "cloak_license_key" => storage.license_key = None,
             "cloak_instance_id" => storage.instance_id = None,
             "selected_cloak_model" => storage.selected_cloak_model = None,
+            "local_llm_base_url" => storage.local_llm_base_url = None,
             _ => return Err(format!("Invalid storage key: {}", key)),
         }
     }
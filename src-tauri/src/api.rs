@@ -1,22 +1,31 @@
+use crate::knowledge;
+use crate::providers;
+use crate::speaker;
+use crate::stt;
+use crate::stt::codec::AudioEncoding;
+use crate::tools;
 use base64::{engine::general_purpose, Engine as _};
+use futures_util::future::{AbortHandle, Abortable};
 use futures_util::StreamExt;
 use reqwest::multipart::{Form, Part};
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_machine_uid::MachineUidExt;
 
 /// Returns true if running in dev mode (API_ACCESS_KEY set, no real backend).
-fn is_dev_mode() -> bool {
+pub(crate) fn is_dev_mode() -> bool {
     env::var("API_ACCESS_KEY")
         .map(|k| !k.trim().is_empty())
         .unwrap_or(false)
 }
 
-fn get_app_endpoint() -> Result<String, String> {
+pub(crate) fn get_app_endpoint() -> Result<String, String> {
     if let Ok(endpoint) = env::var("APP_ENDPOINT") {
         let trimmed = endpoint.trim();
         if !trimmed.is_empty() {
@@ -40,7 +49,7 @@ fn get_app_endpoint() -> Result<String, String> {
     Err("APP_ENDPOINT environment variable not set. Please ensure it's set during the build process.".to_string())
 }
 
-fn get_api_access_key() -> Result<String, String> {
+pub(crate) fn get_api_access_key() -> Result<String, String> {
     if let Ok(key) = env::var("API_ACCESS_KEY") {
         return Ok(key.trim().to_string());
     }
@@ -69,6 +78,20 @@ struct SecureStorage {
     license_key: Option<String>,
     instance_id: Option<String>,
     selected_cloak_model: Option<String>,
+    local_llm_base_url: Option<String>,
+}
+
+fn read_secure_storage(app: &AppHandle) -> Option<SecureStorage> {
+    let storage_path = get_secure_storage_path(app).ok()?;
+    let content = fs::read_to_string(&storage_path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// Selected model, readable without a license so local models work offline
+fn get_selected_model(app: &AppHandle) -> Option<Model> {
+    read_secure_storage(app)?
+        .selected_cloak_model
+        .and_then(|json_str| serde_json::from_str(&json_str).ok())
 }
 
 pub async fn get_stored_credentials(
@@ -117,6 +140,40 @@ pub struct ChatRequest {
     history: Option<String>,
 }
 
+// Opt-in features for a single chat stream
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatStreamOptions {
+    // Let the model call the built-in tools
+    #[serde(default)]
+    enable_tools: bool,
+    // Inject cited knowledge base excerpts relevant to the user message
+    #[serde(default)]
+    use_knowledge: bool,
+}
+
+// Running chat streams keyed by stream ID, so they can be cancelled. An ID is
+// reserved before the request is sent, so it can be cancelled while waiting
+// for the provider to respond
+#[derive(Default)]
+pub struct ChatStreamState {
+    streams: Mutex<HashMap<String, Arc<AbortHandle>>>,
+}
+
+impl ChatStreamState {
+    // Frees `stream_id` if it still belongs to `handle`, not to a newer stream
+    fn release(&self, stream_id: &str, handle: &Arc<AbortHandle>) {
+        if let Ok(mut streams) = self.streams.lock() {
+            if streams
+                .get(stream_id)
+                .is_some_and(|current| Arc::ptr_eq(current, handle))
+            {
+                streams.remove(stream_id);
+            }
+        };
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ChatResponse {
     success: bool,
@@ -217,6 +274,12 @@ async fn get_api_config_with_fallback(
     provider: Option<String>,
     model: Option<String>,
 ) -> Result<ApiResponseConfig, String> {
+    // Local models never go through the Cloak API
+    if provider.as_deref() == Some(LOCAL_PROVIDER) {
+        let model = model.ok_or_else(|| "No local model selected.".to_string())?;
+        return Ok(local_api_config(&get_local_llm_base_url(app), model));
+    }
+
     match fetch_api_response_config(app, provider.clone(), model.clone()).await {
         Ok(config) => Ok(config),
         Err(e) => {
@@ -235,7 +298,7 @@ async fn get_api_config_with_fallback(
                                 default_model
                             };
                             return Ok(ApiResponseConfig {
-                                url: "https://generativelanguage.googleapis.com/v1beta/openai/chat/completions".to_string(),
+                                url: "https://generativelanguage.googleapis.com/v1beta/models".to_string(),
                                 user_token: key.clone(),
                                 model: chat_model,
                                 body: "".to_string(),
@@ -346,23 +409,89 @@ pub async fn transcribe_audio(
     app: AppHandle,
     audio_base64: String,
 ) -> Result<AudioResponse, String> {
+    let audio_bytes = decode_audio_base64(&audio_base64)?;
+    transcribe_bytes(&app, &audio_bytes).await
+}
+
+// Transcribes a `speech-detected` segment straight from the segment store
+#[tauri::command]
+pub async fn transcribe_segment(
+    app: AppHandle,
+    segment_id: String,
+) -> Result<AudioResponse, String> {
+    let audio_bytes = app
+        .state::<speaker::SegmentStore>()
+        .get(&segment_id)
+        .ok_or_else(|| format!("Speech segment {} is no longer available", segment_id))?;
+    transcribe_bytes(&app, &audio_bytes).await
+}
+
+/// Transcription text of a stored segment, for capture pipelines running in Rust.
+pub(crate) async fn transcribe_segment_text(
+    app: &AppHandle,
+    segment_id: &str,
+) -> Result<String, String> {
+    let audio_bytes = app
+        .state::<speaker::SegmentStore>()
+        .get(segment_id)
+        .ok_or_else(|| format!("Speech segment {} is no longer available", segment_id))?;
+    transcribe_audio_text(app, &audio_bytes).await
+}
+
+/// Transcription text of encoded audio, trying the same providers as
+/// `transcribe_audio`.
+pub(crate) async fn transcribe_audio_text(
+    app: &AppHandle,
+    audio_bytes: &[u8],
+) -> Result<String, String> {
+    let response = transcribe_bytes(app, audio_bytes).await?;
+    match response.transcription {
+        Some(text) if response.success => Ok(text),
+        _ => Err(response
+            .error
+            .unwrap_or_else(|| "Transcription failed".to_string())),
+    }
+}
+
+async fn transcribe_bytes(app: &AppHandle, audio_bytes: &[u8]) -> Result<AudioResponse, String> {
+    // On-device Whisper first; the primary and fallback endpoints follow
+    let local_config = stt::get_local_config(app);
+    if local_config.enabled {
+        match stt::transcribe_locally(app, audio_bytes).await {
+            Ok(transcription) => {
+                return Ok(AudioResponse {
+                    success: true,
+                    transcription: Some(transcription),
+                    error: None,
+                });
+            }
+            Err(e) if local_config.local_only => {
+                return Err(format!("Local transcription failed: {}", e));
+            }
+            Err(e) => {
+                tracing::warn!(local_error = %e, "Local transcription failed, trying remote endpoints");
+            }
+        }
+    }
+
     let (provider, model) = if is_dev_mode() {
         (None, None)
     } else {
-        let (_, _, selected_model) = get_stored_credentials(&app).await?;
+        let (_, _, selected_model) = get_stored_credentials(app).await?;
+        // A local chat model has no audio endpoint; transcribe with the workspace default
+        let selected_model = selected_model.filter(|m| m.provider != LOCAL_PROVIDER);
         (
             selected_model.as_ref().map(|m| m.provider.clone()),
             selected_model.as_ref().map(|m| m.model.clone()),
         )
     };
 
-    let api_config = get_api_config_with_fallback(&app, provider.clone(), model.clone()).await?;
+    let api_config = get_api_config_with_fallback(app, provider.clone(), model.clone()).await?;
 
     let user_audio_config = api_config.user_audio.as_ref().ok_or_else(|| {
         "Audio transcription is not configured for this workspace.".to_string()
     })?;
 
-    let audio_bytes = decode_audio_base64(&audio_base64)?;
     let client = reqwest::Client::new();
     let error_provider = provider.clone();
     let error_model = model.clone();
@@ -372,7 +501,7 @@ pub async fn transcribe_audio(
         &user_audio_config.user_token,
         &user_audio_config.model,
         user_audio_config.headers.as_ref(),
-        &audio_bytes,
+        audio_bytes,
     )
     .await
     {
@@ -397,7 +526,7 @@ pub async fn transcribe_audio(
                     fallback_token,
                     fallback_model,
                     user_audio_config.headers.as_ref(),
-                    &audio_bytes,
+                    audio_bytes,
                 )
                 .await
                 {
@@ -568,9 +697,11 @@ async fn perform_user_audio_transcription(
     headers: Option<&Vec<UserAudioHeader>>,
     audio_bytes: &[u8],
 ) -> Result<String, String> {
+    // Providers pick a decoder from the file name and type, so they must match the bytes
+    let encoding = AudioEncoding::detect(audio_bytes).unwrap_or_default();
     let audio_part = Part::bytes(audio_bytes.to_vec())
-        .file_name("audio.wav")
-        .mime_str("audio/wav")
+        .file_name(encoding.file_name())
+        .mime_str(encoding.mime_type())
         .map_err(|e| format!("Failed to prepare audio payload: {}", e))?;
 
     let mut form = Form::new()
@@ -643,21 +774,214 @@ pub async fn chat_stream_response(
     system_prompt: Option<String>,
     image_base64: Option<serde_json::Value>,
     history: Option<String>,
+    stream_id: Option<String>,
+    options: Option<ChatStreamOptions>,
 ) -> Result<String, String> {
-    let (_, _, selected_model) = get_stored_credentials(&app).await.unwrap_or((
-        String::new(),
-        String::new(),
-        None,
-    ));
+    let options = options.unwrap_or_default();
+    let stream_id = stream_id
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let (request_abort, registration) = AbortHandle::new_pair();
+    let request_abort = Arc::new(request_abort);
+    {
+        let state = app.state::<ChatStreamState>();
+        let mut streams = state
+            .streams
+            .lock()
+            .map_err(|e| format!("Failed to acquire stream lock: {}", e))?;
+        if streams.contains_key(&stream_id) {
+            return Err(format!("Chat stream {} is already running", stream_id));
+        }
+        streams.insert(stream_id.clone(), request_abort.clone());
+    }
+
+    let request = prepare_chat_stream(
+        &app,
+        user_message,
+        system_prompt,
+        image_base64,
+        history,
+        options,
+    );
+    let PreparedChat {
+        mut session,
+        response,
+        sources,
+    } = match Abortable::new(request, registration).await {
+        Ok(Ok(prepared)) => prepared,
+        Ok(Err(e)) => {
+            app.state::<ChatStreamState>()
+                .release(&stream_id, &request_abort);
+            return Err(e);
+        }
+        // Cancelled before the provider responded; `chat_stream_cancelled` is already sent
+        Err(_) => return Ok(stream_id),
+    };
+
+    // Sent before any chunk so the UI can render citations while the answer streams
+    if !sources.is_empty() {
+        let _ = app.emit(
+            "chat_stream_sources",
+            serde_json::json!({
+                "stream_id": stream_id,
+                "sources": sources,
+            }),
+        );
+    }
+
+    // Stream the body in the background so the caller can cancel it by ID.
+    // The lock is held until the handle is stored, so a stream that finishes
+    // instantly cannot try to remove itself before it has been registered.
+    let state = app.state::<ChatStreamState>();
+    let mut streams = state
+        .streams
+        .lock()
+        .map_err(|e| format!("Failed to acquire stream lock: {}", e))?;
+    // Cancelled between the response arriving and taking the lock
+    if !streams
+        .get(&stream_id)
+        .is_some_and(|current| Arc::ptr_eq(current, &request_abort))
+    {
+        return Ok(stream_id);
+    }
+    let (stream_abort, registration) = AbortHandle::new_pair();
+    let stream_abort = Arc::new(stream_abort);
+
+    let task_app = app.clone();
+    let task_stream_id = stream_id.clone();
+    let task_abort = stream_abort.clone();
+    let task = async move {
+        let app = task_app;
+        let stream_id = task_stream_id;
+        let error_rules = session.api_config.errors.clone().unwrap_or_default();
+
+        let mut response = response;
+        let mut full_response = String::new();
+        let mut usage: Option<serde_json::Value> = None;
+        let mut tool_rounds = 0;
+
+        // Keep going while the model asks for tools, feeding results back each round
+        let outcome: Result<(), String> = loop {
+            let ChatTurn {
+                text,
+                tool_calls,
+                usage: turn_usage,
+            } = match read_chat_stream(&app, &stream_id, response, session.chat_provider)
+                .await
+            {
+                Ok(turn) => turn,
+                Err(e) => {
+                    let sources = vec![e.to_string()];
+                    tauri::async_runtime::spawn({
+                        let app = app.clone();
+                        let error_msg = e.to_string();
+                        let provider = session.provider.clone();
+                        let model = session.model.clone();
+                        async move {
+                            report_api_error(app, error_msg, "/api/chat".to_string(), model, provider)
+                                .await;
+                        }
+                    });
+                    break Err(map_api_error_message(&error_rules, &sources));
+                }
+            };
+
+            full_response.push_str(&text);
+            if let Some(turn_usage) = turn_usage {
+                providers::add_usage(&mut usage, turn_usage);
+            }
+            if tool_calls.is_empty() || tool_rounds >= MAX_TOOL_ROUNDS {
+                break Ok(());
+            }
+            tool_rounds += 1;
+
+            run_tool_calls(&app, &stream_id, &mut session.messages, text, &tool_calls).await;
+
+            response = match send_chat_request(&app, &session).await {
+                Ok(response) => response,
+                Err(e) => break Err(e),
+            };
+        };
+
+        match outcome {
+            Ok(()) => {
+                // Emit completion event
+                let _ = app.emit(
+                    "chat_stream_complete",
+                    serde_json::json!({
+                        "stream_id": stream_id,
+                        "response": full_response,
+                        "sources": sources,
+                    }),
+                );
+
+                if !full_response.is_empty()
+                    && session.provider.as_deref() != Some(LOCAL_PROVIDER)
+                {
+                    tauri::async_runtime::spawn({
+                        let activity_app = app.clone();
+                        let activity_model = session.api_config.model.clone();
+                        let activity_app_version = app.package_info().version.to_string();
+                        async move {
+                            let _ = user_activity(
+                                activity_app,
+                                usage,
+                                activity_model,
+                                activity_app_version,
+                            )
+                            .await;
+                        }
+                    });
+                }
+            }
+            Err(final_message) => {
+                let _ = app.emit(
+                    "chat_stream_error",
+                    serde_json::json!({
+                        "stream_id": stream_id,
+                        "error": final_message,
+                    }),
+                );
+            }
+        }
+
+        app.state::<ChatStreamState>()
+            .release(&stream_id, &task_abort);
+    };
+    tokio::spawn(Abortable::new(task, registration));
+
+    streams.insert(stream_id.clone(), stream_abort);
+
+    Ok(stream_id)
+}
+
+// A chat session whose first request has been answered
+struct PreparedChat {
+    session: ChatSession,
+    response: reqwest::Response,
+    // Knowledge excerpts injected into the prompt, for citations
+    sources: Vec<knowledge::KnowledgeSource>,
+}
+
+// Builds the chat session and sends the first request
+async fn prepare_chat_stream(
+    app: &AppHandle,
+    user_message: String,
+    system_prompt: Option<String>,
+    image_base64: Option<serde_json::Value>,
+    history: Option<String>,
+    options: ChatStreamOptions,
+) -> Result<PreparedChat, String> {
+    let selected_model = get_selected_model(app);
     let (provider, model) = selected_model.as_ref().map_or((None, None), |m| {
         (Some(m.provider.clone()), Some(m.model.clone()))
     });
 
     // Fetch API configuration with fallback
-    let api_config = get_api_config_with_fallback(&app, provider.clone(), model.clone()).await?;
+    let api_config = get_api_config_with_fallback(app, provider.clone(), model.clone()).await?;
 
     // Parse the body from API config to merge with our request
-    let mut extra_body: serde_json::Value = if !api_config.body.is_empty() {
+    let extra_body: serde_json::Value = if !api_config.body.is_empty() {
         serde_json::from_str(&api_config.body).unwrap_or_else(|_| serde_json::json!({}))
     } else {
         serde_json::json!({})
@@ -674,6 +998,29 @@ pub async fn chat_stream_response(
         }));
     }
 
+    // Add retrieved knowledge as its own system message, after the system prompt
+    let mut sources: Vec<knowledge::KnowledgeSource> = Vec::new();
+    if options.use_knowledge {
+        match knowledge::build_context(
+            app,
+            &user_message,
+            knowledge::DEFAULT_RAG_K,
+            knowledge::DEFAULT_RAG_TOKEN_BUDGET,
+        )
+        .await
+        {
+            Ok(Some(context)) => {
+                messages.push(serde_json::json!({
+                    "role": "system",
+                    "content": context.prompt
+                }));
+                sources = context.sources;
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Knowledge retrieval failed, answering without it: {}", e),
+        }
+    }
+
     // Add history if provided
     if let Some(history_str) = history {
         if let Ok(history_messages) = serde_json::from_str::<Vec<serde_json::Value>>(&history_str) {
@@ -723,101 +1070,121 @@ pub async fn chat_stream_response(
         "content": user_content
     }));
 
-    let is_anthropic = provider.as_deref() == Some("Anthropic");
-    let request_body: serde_json::Value = if is_anthropic {
-        // Anthropic: system is top-level; messages have content as array of blocks (text, image with base64)
-        let system_text = messages
-            .iter()
-            .find(|m| m.get("role").and_then(|r| r.as_str()) == Some("system"))
-            .and_then(|m| m.get("content").and_then(|c| c.as_str()))
-            .unwrap_or("")
-            .to_string();
-        let anthropic_messages: Vec<serde_json::Value> = messages
-            .iter()
-            .filter(|m| m.get("role").and_then(|r| r.as_str()) != Some("system"))
-            .map(|m| {
-                let role = m.get("role").and_then(|r| r.as_str()).unwrap_or("user");
-                let content = m.get("content").cloned().unwrap_or(serde_json::json!([]));
-                let blocks: Vec<serde_json::Value> = if let Some(arr) = content.as_array() {
-                    arr.iter()
-                        .map(|block| {
-                            if block.get("type").and_then(|t| t.as_str()) == Some("image_url") {
-                                let url = block
-                                    .get("image_url")
-                                    .and_then(|u| u.get("url"))
-                                    .and_then(|u| u.as_str())
-                                    .unwrap_or("");
-                                let (base64, media_type) = if let Some(b) = url.strip_prefix("data:image/png;base64,") {
-                                    (b, "image/png")
-                                } else if let Some(b) = url.strip_prefix("data:image/jpeg;base64,") {
-                                    (b, "image/jpeg")
-                                } else {
-                                    (url, "image/jpeg")
-                                };
-                                serde_json::json!({
-                                    "type": "image",
-                                    "source": {
-                                        "type": "base64",
-                                        "media_type": media_type,
-                                        "data": base64
-                                    }
-                                })
-                            } else {
-                                serde_json::json!({
-                                    "type": "text",
-                                    "text": block.get("text").and_then(|t| t.as_str()).unwrap_or("")
-                                })
-                            }
-                        })
-                        .collect()
-                } else if let Some(s) = content.as_str() {
-                    vec![serde_json::json!({ "type": "text", "text": s })]
-                } else {
-                    vec![]
-                };
-                serde_json::json!({ "role": role, "content": blocks })
-            })
-            .collect();
-        let max_tokens = extra_body.get("max_tokens").cloned().unwrap_or(serde_json::json!(8192));
-        serde_json::json!({
-            "model": api_config.model,
-            "max_tokens": max_tokens,
-            "system": system_text,
-            "messages": anthropic_messages,
-            "stream": true
-        })
+    let chat_provider = providers::resolve_provider(provider.as_deref(), &api_config.url);
+    let tool_specs = if options.enable_tools {
+        tools::definitions()
     } else {
-        let mut body = serde_json::json!({
-            "model": api_config.model,
-            "messages": messages,
-            "stream": true
-        });
-        if let Some(extra_obj) = extra_body.as_object_mut() {
-            if let Some(req_obj) = body.as_object_mut() {
-                for (key, value) in extra_obj.iter() {
-                    req_obj.insert(key.clone(), value.clone());
-                }
-            }
-        }
-        body
+        Vec::new()
+    };
+    let session = ChatSession {
+        provider,
+        model,
+        api_config,
+        chat_provider,
+        extra_body,
+        messages,
+        tools: tool_specs,
+    };
+
+    let response = send_chat_request(app, &session).await?;
+    Ok(PreparedChat {
+        session,
+        response,
+        sources,
+    })
+}
+
+// Upper bound on model -> tool -> model round trips within one chat stream
+const MAX_TOOL_ROUNDS: usize = 5;
+
+/// Runs one prompt against the selected model and returns the whole answer.
+/// Chunks are still emitted under `stream_id`, so a webview that knows the ID
+/// can show progress. No tools or knowledge retrieval.
+pub(crate) async fn complete_chat(
+    app: &AppHandle,
+    stream_id: &str,
+    system_prompt: &str,
+    user_message: &str,
+) -> Result<String, String> {
+    let selected_model = get_selected_model(app);
+    let (provider, model) = selected_model.as_ref().map_or((None, None), |m| {
+        (Some(m.provider.clone()), Some(m.model.clone()))
+    });
+    let api_config = get_api_config_with_fallback(app, provider.clone(), model.clone()).await?;
+    let extra_body: serde_json::Value = if !api_config.body.is_empty() {
+        serde_json::from_str(&api_config.body).unwrap_or_else(|_| serde_json::json!({}))
+    } else {
+        serde_json::json!({})
     };
 
+    let chat_provider = providers::resolve_provider(provider.as_deref(), &api_config.url);
+    let error_rules = api_config.errors.clone().unwrap_or_default();
+    let session = ChatSession {
+        provider,
+        model,
+        api_config,
+        chat_provider,
+        extra_body,
+        messages: vec![
+            serde_json::json!({ "role": "system", "content": system_prompt }),
+            serde_json::json!({
+                "role": "user",
+                "content": [{ "type": "text", "text": user_message }]
+            }),
+        ],
+        tools: Vec::new(),
+    };
+
+    let response = send_chat_request(app, &session).await?;
+    let turn = read_chat_stream(app, stream_id, response, chat_provider)
+        .await
+        .map_err(|e| map_api_error_message(&error_rules, &[e.to_string()]))?;
+    Ok(turn.text)
+}
+
+// Everything needed to send (and, after tool calls, re-send) a chat request
+struct ChatSession {
+    provider: Option<String>,
+    model: Option<String>,
+    api_config: ApiResponseConfig,
+    chat_provider: &'static dyn providers::ChatProvider,
+    extra_body: serde_json::Value,
+    messages: Vec<serde_json::Value>,
+    tools: Vec<providers::ToolSpec>,
+}
+
+// One streamed model response
+struct ChatTurn {
+    text: String,
+    tool_calls: Vec<providers::ToolCall>,
+    usage: Option<serde_json::Value>,
+}
+
+// Sends the session's messages and returns the streaming response, or the
+// mapped error message for failed requests.
+async fn send_chat_request(
+    app: &AppHandle,
+    session: &ChatSession,
+) -> Result<reqwest::Response, String> {
+    let api_config = &session.api_config;
+    let chat_provider = session.chat_provider;
+    let request_body = chat_provider.build_request_body(
+        &api_config.model,
+        &session.messages,
+        &session.extra_body,
+        &session.tools,
+    );
+
     let error_rules = api_config.errors.clone().unwrap_or_default();
     let client = reqwest::Client::new();
     let mut req_builder = client
-        .post(&api_config.url)
+        .post(chat_provider.stream_url(&api_config.url, &api_config.model))
         .header("Content-Type", "application/json")
         .json(&request_body);
-    if is_anthropic {
-        req_builder = req_builder.header("x-api-key", api_config.user_token.clone());
-        req_builder = req_builder.header("anthropic-version", "2023-06-01");
-    } else {
-        req_builder = req_builder.header("Authorization", format!("Bearer {}", api_config.user_token));
+    for (key, value) in chat_provider.auth_headers(&api_config.user_token) {
+        req_builder = req_builder.header(key, value);
     }
-    let response = match req_builder
-        .send()
-        .await
-    {
+    let response = match req_builder.send().await {
         Ok(resp) => resp,
         Err(e) => {
             let mut sources = vec![e.to_string()];
@@ -827,8 +1194,8 @@ pub async fn chat_stream_response(
             let final_message = map_api_error_message(&error_rules, &sources);
             tauri::async_runtime::spawn({
                 let app = app.clone();
-                let provider = provider.clone();
-                let model = model.clone();
+                let provider = session.provider.clone();
+                let model = session.model.clone();
                 let error_msg = e.to_string();
                 async move {
                     report_api_error(app, error_msg, "/api/chat".to_string(), model, provider)
@@ -862,8 +1229,8 @@ pub async fn chat_stream_response(
         let final_message = map_api_error_message(&error_rules, &sources);
         tauri::async_runtime::spawn({
             let app = app.clone();
-            let provider = provider.clone();
-            let model = model.clone();
+            let provider = session.provider.clone();
+            let model = session.model.clone();
             let error_msg = format!("{}: {}", status, error_text);
             async move {
                 report_api_error(app, error_msg, "/api/chat".to_string(), model, provider).await;
@@ -872,119 +1239,164 @@ pub async fn chat_stream_response(
         return Err(final_message);
     }
 
-    // Handle streaming response
-    let mut stream = response.bytes_stream();
-    let mut full_response = String::new();
-    let mut buffer = String::new();
-    let mut usage: Option<serde_json::Value> = None;
-    let mut stream_started = false;
+    Ok(response)
+}
 
-    while let Some(chunk) = stream.next().await {
-        match chunk {
-            Ok(bytes) => {
-                let chunk_str = String::from_utf8_lossy(&bytes);
-                buffer.push_str(&chunk_str);
+// Runs the tool calls of one turn and appends the assistant turn plus one
+// `tool` message per result. Tool failures are reported to the model as
+// results rather than ending the stream.
+async fn run_tool_calls(
+    app: &AppHandle,
+    stream_id: &str,
+    messages: &mut Vec<serde_json::Value>,
+    text: String,
+    tool_calls: &[providers::ToolCall],
+) {
+    let assistant_tool_calls: Vec<serde_json::Value> = tool_calls
+        .iter()
+        .map(|call| {
+            serde_json::json!({
+                "id": call.id,
+                "type": "function",
+                "function": { "name": call.name, "arguments": call.arguments }
+            })
+        })
+        .collect();
+    messages.push(serde_json::json!({
+        "role": "assistant",
+        "content": text,
+        "tool_calls": assistant_tool_calls
+    }));
 
-                // Process complete lines
-                let lines: Vec<&str> = buffer.split('\n').collect();
-                let incomplete_line = lines.last().unwrap_or(&"").to_string();
+    let mut images: Vec<String> = Vec::new();
+    for call in tool_calls {
+        let _ = app.emit(
+            "chat_tool_call",
+            serde_json::json!({
+                "stream_id": stream_id,
+                "id": call.id,
+                "name": call.name,
+                "arguments": call.arguments,
+            }),
+        );
+
+        let (content, is_error) = match tools::execute(app, call).await {
+            Ok(output) => {
+                images.extend(output.images);
+                (output.content, false)
+            }
+            Err(e) => (format!("Error: {}", e), true),
+        };
+
+        let _ = app.emit(
+            "chat_tool_result",
+            serde_json::json!({
+                "stream_id": stream_id,
+                "id": call.id,
+                "name": call.name,
+                "content": content,
+                "is_error": is_error,
+            }),
+        );
+        messages.push(serde_json::json!({
+            "role": "tool",
+            "tool_call_id": call.id,
+            "content": content
+        }));
+    }
 
-                for line in &lines[..lines.len() - 1] {
-                    // Process all but the last (potentially incomplete) line
-                    let trimmed_line = line.trim();
+    // Tool messages only carry text everywhere, so images follow as a user turn
+    if !images.is_empty() {
+        let mut content = vec![serde_json::json!({
+            "type": "text",
+            "text": "Images returned by the tool calls above."
+        })];
+        content.extend(images.iter().map(|image| {
+            serde_json::json!({
+                "type": "image_url",
+                "image_url": { "url": format!("data:image/png;base64,{}", image) }
+            })
+        }));
+        messages.push(serde_json::json!({ "role": "user", "content": content }));
+    }
+}
 
-                    if trimmed_line.starts_with("data: ") {
-                        let json_str = trimmed_line.strip_prefix("data: ").unwrap_or("");
+// Reads an SSE chat response to the end, emitting each text delta tagged with
+// the stream ID. Returns the text, any requested tool calls and reported usage.
+async fn read_chat_stream(
+    app: &AppHandle,
+    stream_id: &str,
+    response: reqwest::Response,
+    chat_provider: &dyn providers::ChatProvider,
+) -> Result<ChatTurn, reqwest::Error> {
+    let mut stream = response.bytes_stream();
+    let mut full_response = String::new();
+    let mut sse = providers::SseBuffer::default();
+    let mut usage: Option<serde_json::Value> = None;
+    let mut tool_calls = providers::ToolCallAccumulator::default();
 
-                        if json_str == "[DONE]" {
-                            break;
-                        }
+    'stream: while let Some(chunk) = stream.next().await {
+        let bytes = chunk?;
+        for data in sse.push(&bytes) {
+            if chat_provider.is_done(&data) {
+                break 'stream;
+            }
 
-                        if !json_str.is_empty() {
-                            if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(json_str)
-                            {
-                                if is_anthropic {
-                                    if parsed.get("type").and_then(|t| t.as_str()) == Some("content_block_delta") {
-                                        if let Some(delta) = parsed.get("delta") {
-                                            if let Some(content) = delta.get("text").and_then(|c| c.as_str()) {
-                                                full_response.push_str(content);
-                                                let _ = app.emit("chat_stream_chunk", content);
-                                                stream_started = true;
-                                            }
-                                        }
-                                    }
-                                } else {
-                                    if usage.is_none() {
-                                        if let Some(collected) = parsed.get("usage") {
-                                            if !collected.is_null() {
-                                                usage = Some(collected.clone());
-                                            }
-                                        }
-                                    }
-                                    if let Some(choices) =
-                                        parsed.get("choices").and_then(|c| c.as_array())
-                                    {
-                                        if let Some(first_choice) = choices.first() {
-                                            if let Some(delta) = first_choice.get("delta") {
-                                                if let Some(content) =
-                                                    delta.get("content").and_then(|c| c.as_str())
-                                                {
-                                                    full_response.push_str(content);
-                                                    let _ = app.emit("chat_stream_chunk", content);
-                                                    stream_started = true;
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+            let parsed = match serde_json::from_str::<serde_json::Value>(&data) {
+                Ok(parsed) => parsed,
+                Err(_) => continue,
+            };
 
-                // Update buffer with incomplete line
-                buffer = incomplete_line;
+            if let Some(collected) = chat_provider.extract_usage(&parsed) {
+                providers::merge_usage(&mut usage, collected);
             }
-            Err(e) => {
-                let sources = vec![e.to_string()];
-                let final_message = map_api_error_message(&error_rules, &sources);
-                tauri::async_runtime::spawn({
-                    let app = app.clone();
-                    let provider = provider.clone();
-                    let model = model.clone();
-                    let error_msg = e.to_string();
-                    async move {
-                        report_api_error(app, error_msg, "/api/chat".to_string(), model, provider)
-                            .await;
+
+            for delta in chat_provider.parse_deltas(&parsed) {
+                match delta {
+                    providers::StreamDelta::Text(content) => {
+                        full_response.push_str(&content);
+                        let _ = app.emit(
+                            "chat_stream_chunk",
+                            serde_json::json!({
+                                "stream_id": stream_id,
+                                "chunk": content,
+                            }),
+                        );
                     }
-                });
-                return Err(final_message);
+                    delta => tool_calls.push(&delta),
+                }
             }
         }
     }
 
-    // Emit completion event
-    let _ = app.emit("chat_stream_complete", &full_response);
+    Ok(ChatTurn {
+        text: full_response,
+        tool_calls: tool_calls.finish(),
+        usage,
+    })
+}
 
-    if stream_started && !full_response.is_empty() {
-        tauri::async_runtime::spawn({
-            let activity_app = app.clone();
-            let activity_model = api_config.model.clone();
-            let activity_app_version = app.package_info().version.to_string();
-            let captured_metrics = usage.clone();
-            async move {
-                let _ = user_activity(
-                    activity_app,
-                    captured_metrics,
-                    activity_model,
-                    activity_app_version,
-                )
-                .await;
-            }
-        });
+// Aborts a running chat stream. Dropping the task drops the underlying HTTP stream.
+#[tauri::command]
+pub fn cancel_chat_stream(app: AppHandle, stream_id: String) -> Result<bool, String> {
+    let state = app.state::<ChatStreamState>();
+    let task = state
+        .streams
+        .lock()
+        .map_err(|e| format!("Failed to acquire stream lock: {}", e))?
+        .remove(&stream_id);
+
+    match task {
+        Some(task) => {
+            task.abort();
+            let _ = app.emit(
+                "chat_stream_cancelled",
+                serde_json::json!({ "stream_id": stream_id }),
+            );
+            Ok(true)
+        }
+        None => Ok(false),
     }
-
-    Ok(full_response)
 }
 
 async fn user_activity(
@@ -1268,8 +1680,135 @@ fn anthropic_models() -> Vec<Model> {
         .collect()
 }
 
-async fn fetch_dev_mode_models() -> Result<Vec<Model>, String> {
-    let mut all = Vec::new();
+// Local models (Ollama, llama.cpp or any OpenAI-compatible server on localhost)
+const LOCAL_PROVIDER: &str = "Local";
+const DEFAULT_LOCAL_LLM_BASE_URL: &str = "http://localhost:11434";
+
+// Base URL of the local server: stored setting, then LOCAL_LLM_BASE_URL, then Ollama's default
+fn get_local_llm_base_url(app: &AppHandle) -> String {
+    let configured = read_secure_storage(app)
+        .and_then(|storage| storage.local_llm_base_url)
+        .or_else(|| env::var("LOCAL_LLM_BASE_URL").ok())
+        .or_else(|| option_env!("LOCAL_LLM_BASE_URL").map(String::from))
+        .filter(|url| !url.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_LOCAL_LLM_BASE_URL.to_string());
+
+    // Accept both "http://host:port" and "http://host:port/v1"
+    configured
+        .trim()
+        .trim_end_matches('/')
+        .trim_end_matches("/v1")
+        .to_string()
+}
+
+fn local_api_config(base_url: &str, model: String) -> ApiResponseConfig {
+    ApiResponseConfig {
+        url: format!("{}/v1/chat/completions", base_url),
+        user_token: "".to_string(),
+        model,
+        body: "".to_string(),
+        customer_id: None,
+        customer_email: None,
+        customer_name: None,
+        license_key: "".to_string(),
+        instance_id: "".to_string(),
+        user_audio: None,
+        errors: None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaTagsResponse {
+    #[serde(default)]
+    models: Vec<OllamaModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaModelEntry {
+    name: String,
+    details: Option<OllamaModelDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaModelDetails {
+    families: Option<Vec<String>>,
+}
+
+fn local_model(id: String, modality: &str) -> Model {
+    Model {
+        provider: LOCAL_PROVIDER.to_string(),
+        name: id.clone(),
+        id: id.clone(),
+        model: id.clone(),
+        description: format!("Local {}", id),
+        modality: modality.to_string(),
+        is_available: true,
+    }
+}
+
+// Lists models from Ollama's /api/tags, falling back to /v1/models for llama.cpp and friends
+async fn fetch_local_models(base_url: &str) -> Result<Vec<Model>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(3))
+        .build()
+        .map_err(|e| format!("Failed to create local client: {}", e))?;
+
+    let tags_response = client
+        .get(format!("{}/api/tags", base_url))
+        .send()
+        .await
+        .map_err(|e| format!("Local models request failed: {}", e))?;
+
+    if tags_response.status().is_success() {
+        let body: OllamaTagsResponse = tags_response
+            .json()
+            .await
+            .map_err(|e| format!("Local models parse error: {}", e))?;
+        let models = body
+            .models
+            .into_iter()
+            .map(|m| {
+                // Ollama marks vision models with the "clip" family
+                let has_vision = m
+                    .details
+                    .and_then(|d| d.families)
+                    .map(|families| families.iter().any(|f| f == "clip" || f == "mllama"))
+                    .unwrap_or(false);
+                local_model(m.name, if has_vision { "text,image" } else { "text" })
+            })
+            .collect();
+        return Ok(models);
+    }
+
+    let response = client
+        .get(format!("{}/v1/models", base_url))
+        .send()
+        .await
+        .map_err(|e| format!("Local models request failed: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("Local models API error ({}): {}", status, text));
+    }
+    let json: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Local models parse error: {}", e))?;
+    let models = json
+        .get("data")
+        .and_then(|d| d.as_array())
+        .map(|data| {
+            data.iter()
+                .filter_map(|m| m.get("id")?.as_str())
+                .map(|id| local_model(id.to_string(), "text"))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(models)
+}
+
+async fn fetch_dev_mode_models(local_models: Vec<Model>) -> Result<Vec<Model>, String> {
+    let mut all = local_models;
     if let Ok(openai) = fetch_openai_models().await {
         all.extend(openai);
     }
@@ -1298,15 +1837,39 @@ async fn fetch_dev_mode_models() -> Result<Vec<Model>, String> {
 // Models API Command
 #[tauri::command]
 pub async fn fetch_models(app: AppHandle) -> Result<Vec<Model>, String> {
+    // A local server that isn't running just contributes no models
+    let local_models = match fetch_local_models(&get_local_llm_base_url(&app)).await {
+        Ok(models) => models,
+        Err(e) => {
+            tracing::debug!("Local models unavailable: {}", e);
+            vec![]
+        }
+    };
+
     if is_dev_mode() {
-        return fetch_dev_mode_models().await;
+        return fetch_dev_mode_models(local_models).await;
     }
 
+    match fetch_cloak_models(&app).await {
+        Ok(mut models) => {
+            models.extend(local_models);
+            Ok(models)
+        }
+        // Offline: still offer whatever the local server has
+        Err(e) if !local_models.is_empty() => {
+            tracing::warn!("Cloak models unavailable, using local models only: {}", e);
+            Ok(local_models)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn fetch_cloak_models(app: &AppHandle) -> Result<Vec<Model>, String> {
     // Get environment variables
     let app_endpoint = get_app_endpoint()?;
     let api_access_key = get_api_access_key()?;
 
-    let (license_key, instance_id) = match get_stored_credentials(&app).await {
+    let (license_key, instance_id) = match get_stored_credentials(app).await {
         Ok((lk, id, _)) => (lk, id),
         Err(_) => ("".to_string(), "".to_string()),
     };
@@ -1525,7 +2088,7 @@ pub async fn check_license_status(app: AppHandle) -> Result<bool, String> {
 }
 
 #[tauri::command]
-pub fn get_env_config() -> Result<serde_json::Value, String> {
+pub fn get_env_config(app: AppHandle) -> Result<serde_json::Value, String> {
     let api_access_key = get_api_access_key().unwrap_or_default();
     let app_endpoint = get_app_endpoint().unwrap_or_default();
     let has_openai = env::var("OPENAI_API_KEY").map(|k| !k.trim().is_empty()).unwrap_or(false)
@@ -1540,6 +2103,7 @@ pub fn get_env_config() -> Result<serde_json::Value, String> {
         "has_openai_key": has_openai,
         "has_google_key": has_google,
         "has_anthropic_key": has_anthropic,
+        "local_llm_base_url": get_local_llm_base_url(&app),
     }))
 }
 
@@ -1617,85 +2181,3 @@ pub async fn get_activity(app: AppHandle) -> Result<serde_json::Value, String> {
         .await
         .map_err(|e| format!("Failed to parse activity response: {}", e))
 }
-
-// --- Knowledge / Embeddings API ---
-
-#[derive(Debug, Serialize, Deserialize)]
-struct EmbeddingRequest {
-    text: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct EmbeddingResponse {
-    embedding: Vec<f64>,
-}
-
-/// Generate embedding vector for text via Cloak API. Used for semantic search.
-#[tauri::command]
-pub async fn generate_embedding(app: AppHandle, text: String) -> Result<Vec<f64>, String> {
-    if text.trim().is_empty() {
-        return Err("Text cannot be empty".to_string());
-    }
-
-    if is_dev_mode() {
-        // Dev: return a dummy 384-dim vector so frontend can still run (e.g. cosine sim with zeros).
-        return Ok(vec![0.0; 384]);
-    }
-
-    let app_endpoint = get_app_endpoint()?;
-    let api_access_key = get_api_access_key()?;
-    let (license_key, instance_id) = match get_stored_credentials(&app).await {
-        Ok((lk, id, _)) => (lk, id),
-        Err(_) => {
-            return Err("No license. Activate Cloak to use embeddings.".to_string());
-        }
-    };
-    let machine_id = app
-        .machine_uid()
-        .get_machine_uid()
-        .ok()
-        .and_then(|uid| uid.id)
-        .unwrap_or_else(|| "".to_string());
-    let app_version = app.package_info().version.to_string();
-
-    let client = reqwest::Client::new();
-    let url = format!("{}/api/embeddings", app_endpoint.trim_end_matches('/'));
-
-    let body = EmbeddingRequest {
-        text: text.trim().to_string(),
-    };
-
-    let response = client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", api_access_key))
-        .header("license_key", &license_key)
-        .header("instance", &instance_id)
-        .header("machine_id", &machine_id)
-        .header("app_version", &app_version)
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| format!("Embedding request failed: {}", e))?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        if let Ok(err_json) = serde_json::from_str::<serde_json::Value>(&error_text) {
-            if let Some(msg) = err_json.get("error").or(err_json.get("message")).and_then(|v| v.as_str()) {
-                return Err(format!("Embedding API ({}): {}", status, msg));
-            }
-        }
-        return Err(format!("Embedding API ({}): {}", status, error_text));
-    }
-
-    let emb: EmbeddingResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse embedding response: {}", e))?;
-
-    Ok(emb.embedding)
-}
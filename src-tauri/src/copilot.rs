@@ -0,0 +1,408 @@
+// Cloak answer copilot: follows what the other party says (system audio, or
+// the "them" side of a meeting) and, once they have asked a question, streams
+// a suggested answer to the overlay without the user having to ask for one.
+//
+// Transcript lines are gathered into turns. A turn ends after `debounce_ms` of
+// silence, and is dropped if the user starts talking first, since they are
+// already answering. A finished turn is checked for a question with a cheap
+// heuristic or an LLM classifier, and suggestions are rate limited.
+//
+// Answers stream through `chat_stream_response`; `copilot-suggestion` is sent
+// first with the stream ID, so the overlay can follow the usual chunk events.
+use crate::api;
+use crate::meetings::segment_label;
+use crate::speaker::AudioSource;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Listener, Manager};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+const CONFIG_FILE_NAME: &str = "copilot_config.json";
+
+const ANSWER_INSTRUCTIONS: &str = "You are a live call copilot. The user is on a call (an interview, a sales call or a meeting) and the other person has just asked them a question. Suggest the answer the user can say out loud right now.
+
+Rules:
+- Answer in the first person, as the user
+- Lead with the direct answer, then at most 2-3 short supporting points
+- No preamble, no restating the question
+- Keep it short enough to read at a glance
+- Lines may start with a speaker label (\"Me\", \"Speaker 1\", \"Them\"); \"Me\" is the user";
+
+const CLASSIFIER_PROMPT: &str = "You watch a live call transcript for the user (\"Me\"). Decide whether the other person's last turn asks the user something they are expected to answer now: a direct question, or a request such as \"tell me about...\" or \"walk me through...\". Rhetorical questions, small talk fillers and questions to someone else do not count.
+
+Reply with the question alone, rewritten so it makes sense without the transcript, or with NONE if there is no question to answer.";
+
+// Interrogative openers; a turn starting with one of these is a question
+// even when the transcript lost the question mark
+const QUESTION_OPENERS: &[&str] = &[
+    "what", "why", "how", "when", "where", "who", "whom", "whose", "which", "can", "could",
+    "would", "will", "do", "does", "did", "is", "are", "was", "were", "should", "have", "has",
+];
+
+const QUESTION_PHRASES: &[&str] = &[
+    "tell me about",
+    "tell me more",
+    "walk me through",
+    "talk me through",
+    "can you explain",
+    "give me an example",
+    "i'd like to know",
+    "i'd love to hear",
+    "curious about",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuestionDetector {
+    /// Question marks, interrogative openers and request phrases
+    #[default]
+    Heuristic,
+    /// Asks the selected chat model; falls back to the heuristic on failure
+    Llm,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CopilotConfig {
+    pub enabled: bool,
+    pub detector: QuestionDetector,
+    /// Silence that ends the other party's turn
+    pub debounce_ms: u64,
+    /// Minimum time between two suggestions
+    pub min_interval_secs: u64,
+    /// Transcript lines sent along with the question
+    pub context_lines: usize,
+}
+
+impl Default for CopilotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            detector: QuestionDetector::Heuristic,
+            debounce_ms: 1200,
+            min_interval_secs: 15,
+            context_lines: 20,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CopilotState {
+    config: Mutex<Option<CopilotConfig>>,
+    // The overlay's system prompt or call context, set by the webview
+    context: Mutex<Option<String>>,
+    feed: Mutex<Option<mpsc::UnboundedSender<HeardLine>>>,
+}
+
+struct HeardLine {
+    source: Option<AudioSource>,
+    speaker: Option<u32>,
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct TranscriptEvent {
+    text: String,
+    source: Option<AudioSource>,
+    speaker: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CopilotSuggestion<'a> {
+    stream_id: &'a str,
+    question: &'a str,
+}
+
+fn config_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(app_data_dir.join(CONFIG_FILE_NAME))
+}
+
+/// Current copilot settings, read from disk on first use.
+pub fn get_config(app: &AppHandle) -> CopilotConfig {
+    let state = app.state::<CopilotState>();
+    let mut cached = state.config.lock().unwrap();
+    cached
+        .get_or_insert_with(|| {
+            config_path(app)
+                .ok()
+                .and_then(|path| fs::read_to_string(path).ok())
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_default()
+        })
+        .clone()
+}
+
+#[tauri::command]
+pub fn get_copilot_config(app: AppHandle) -> Result<CopilotConfig, String> {
+    Ok(get_config(&app))
+}
+
+#[tauri::command]
+pub fn set_copilot_config(app: AppHandle, config: CopilotConfig) -> Result<(), String> {
+    if config.debounce_ms < 200 {
+        return Err("Debounce must be at least 200 ms".to_string());
+    }
+    if config.context_lines == 0 {
+        return Err("At least one line of context is needed".to_string());
+    }
+
+    let content = serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize copilot config: {}", e))?;
+    fs::write(config_path(&app)?, content)
+        .map_err(|e| format!("Failed to write copilot config: {}", e))?;
+    *app.state::<CopilotState>().config.lock().unwrap() = Some(config);
+    Ok(())
+}
+
+/// Sets the system prompt or call context answers are written with; `None`
+/// uses the copilot's own instructions only.
+#[tauri::command]
+pub fn set_copilot_context(app: AppHandle, context: Option<String>) -> Result<(), String> {
+    let context = context.filter(|context| !context.trim().is_empty());
+    *app.state::<CopilotState>().context.lock().unwrap() = context;
+    Ok(())
+}
+
+/// Feeds a line the webview transcribed itself (no Rust STT for it).
+#[tauri::command]
+pub fn copilot_observe_transcript(
+    app: AppHandle,
+    source: Option<AudioSource>,
+    speaker: Option<u32>,
+    text: String,
+) -> Result<(), String> {
+    observe(&app, source, speaker, &text);
+    Ok(())
+}
+
+/// Starts the copilot loop. Streaming transcripts are picked up on their own;
+/// other transcribed lines come in through `observe`.
+pub fn spawn(app: AppHandle) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    *app.state::<CopilotState>().feed.lock().unwrap() = Some(sender);
+
+    let listener_app = app.clone();
+    app.listen("transcript-final", move |event| {
+        if let Ok(transcript) = serde_json::from_str::<TranscriptEvent>(event.payload()) {
+            observe(
+                &listener_app,
+                transcript.source,
+                transcript.speaker,
+                &transcript.text,
+            );
+        }
+    });
+
+    tauri::async_runtime::spawn(run_loop(app, receiver));
+}
+
+/// Hands a transcribed line to the copilot. `None` as the source is the
+/// system audio of a plain capture, i.e. the other party.
+pub fn observe(app: &AppHandle, source: Option<AudioSource>, speaker: Option<u32>, text: &str) {
+    let text = text.trim();
+    if text.is_empty() || !get_config(app).enabled {
+        return;
+    }
+    let state = app.state::<CopilotState>();
+    let feed = state.feed.lock().unwrap();
+    if let Some(feed) = feed.as_ref() {
+        let _ = feed.send(HeardLine {
+            source,
+            speaker,
+            text: text.to_string(),
+        });
+    }
+}
+
+async fn run_loop(app: AppHandle, mut receiver: mpsc::UnboundedReceiver<HeardLine>) {
+    let mut history: VecDeque<String> = VecDeque::new();
+    // The other party's lines since their turn started
+    let mut turn: Vec<String> = Vec::new();
+    let mut last_suggestion: Option<Instant> = None;
+    let mut last_stream: Option<String> = None;
+
+    loop {
+        let config = get_config(&app);
+        let line = if turn.is_empty() {
+            match receiver.recv().await {
+                Some(line) => Some(line),
+                None => break,
+            }
+        } else {
+            let debounce = Duration::from_millis(config.debounce_ms);
+            match tokio::time::timeout(debounce, receiver.recv()).await {
+                Ok(Some(line)) => Some(line),
+                Ok(None) => break,
+                // Silence: the turn is over
+                Err(_) => None,
+            }
+        };
+
+        if let Some(line) = line {
+            let label = match line.source {
+                Some(source) => segment_label(source, line.speaker),
+                None => "Them".to_string(),
+            };
+            history.push_back(format!("{}: {}", label, line.text));
+            while history.len() > config.context_lines {
+                history.pop_front();
+            }
+            if line.source == Some(AudioSource::Me) {
+                // The user is already answering
+                turn.clear();
+            } else {
+                turn.push(line.text);
+            }
+            continue;
+        }
+
+        let turn_text = turn.join(" ");
+        turn.clear();
+        if !config.enabled {
+            continue;
+        }
+        let min_interval = Duration::from_secs(config.min_interval_secs);
+        if last_suggestion.is_some_and(|at| at.elapsed() < min_interval) {
+            continue;
+        }
+
+        let question = match config.detector {
+            QuestionDetector::Heuristic => detect_question(&turn_text),
+            QuestionDetector::Llm => match classify_turn(&app, &history).await {
+                Ok(question) => question,
+                Err(e) => {
+                    warn!("Copilot question classifier failed: {}", e);
+                    detect_question(&turn_text)
+                }
+            },
+        };
+        let Some(question) = question else {
+            continue;
+        };
+
+        // A newer question replaces an answer that is still streaming
+        if let Some(stream_id) = last_stream.take() {
+            let _ = api::cancel_chat_stream(app.clone(), stream_id);
+        }
+        last_suggestion = Some(Instant::now());
+        last_stream = Some(suggest_answer(&app, &question, &history).await);
+    }
+}
+
+// Looks at the last sentences of the turn, where the question usually is
+fn detect_question(turn: &str) -> Option<String> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    for (index, c) in turn.char_indices() {
+        if matches!(c, '.' | '?' | '!') {
+            sentences.push(turn[start..=index].trim());
+            start = index + c.len_utf8();
+        }
+    }
+    sentences.push(turn[start..].trim());
+
+    sentences
+        .into_iter()
+        .rev()
+        .filter(|sentence| !sentence.is_empty())
+        .take(2)
+        .find(|sentence| is_question(sentence))
+        .map(str::to_string)
+}
+
+fn is_question(sentence: &str) -> bool {
+    if sentence.ends_with('?') {
+        return true;
+    }
+    let lower = sentence.to_lowercase();
+    if QUESTION_PHRASES.iter().any(|phrase| lower.contains(phrase)) {
+        return true;
+    }
+    let mut words = lower.split_whitespace();
+    let opener = words
+        .next()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()));
+    // "So" and "And" often lead into the question itself
+    let opener = match opener {
+        Some("so" | "and" | "okay" | "ok") => words.next(),
+        opener => opener,
+    };
+    // Short fragments like "How nice." are not questions
+    opener.is_some_and(|opener| QUESTION_OPENERS.contains(&opener))
+        && lower.split_whitespace().count() >= 4
+}
+
+async fn classify_turn(
+    app: &AppHandle,
+    history: &VecDeque<String>,
+) -> Result<Option<String>, String> {
+    let transcript = history.iter().cloned().collect::<Vec<_>>().join("\n");
+    let reply = api::complete_chat(
+        app,
+        &format!("copilot-classify-{}", uuid::Uuid::new_v4()),
+        CLASSIFIER_PROMPT,
+        &transcript,
+    )
+    .await?;
+    let reply = reply.trim().trim_matches('"').trim();
+    if reply.is_empty() || reply.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    Ok(Some(reply.to_string()))
+}
+
+// Streams an answer and returns its stream ID
+async fn suggest_answer(app: &AppHandle, question: &str, history: &VecDeque<String>) -> String {
+    let stream_id = format!("copilot-{}", uuid::Uuid::new_v4());
+    let _ = app.emit(
+        "copilot-suggestion",
+        CopilotSuggestion {
+            stream_id: &stream_id,
+            question,
+        },
+    );
+
+    let context = app.state::<CopilotState>().context.lock().unwrap().clone();
+    let system_prompt = match context {
+        Some(context) => format!("{}\n\n{}", context, ANSWER_INSTRUCTIONS),
+        None => ANSWER_INSTRUCTIONS.to_string(),
+    };
+    let transcript = history.iter().cloned().collect::<Vec<_>>().join("\n");
+    let user_message = format!(
+        "Recent transcript:\n{}\n\nQuestion to answer: {}",
+        transcript, question
+    );
+
+    if let Err(e) = api::chat_stream_response(
+        app.clone(),
+        user_message,
+        Some(system_prompt),
+        None,
+        None,
+        Some(stream_id.clone()),
+        None,
+    )
+    .await
+    {
+        // Failed before streaming; end the suggestion the usual way
+        let _ = app.emit(
+            "chat_stream_error",
+            serde_json::json!({
+                "stream_id": stream_id,
+                "error": e,
+            }),
+        );
+    }
+    stream_id
+}
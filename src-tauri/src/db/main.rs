@@ -31,5 +31,33 @@ pub fn migrations() -> Vec<Migration> {
             sql: include_str!("migrations/meetings.sql"),
             kind: MigrationKind::Up,
         },
+        // Migration 5: Background knowledge indexer bookkeeping
+        Migration {
+            version: 5,
+            description: "create_knowledge_index_state_table",
+            sql: include_str!("migrations/knowledge-index.sql"),
+            kind: MigrationKind::Up,
+        },
+        // Migration 6: Speaker-attributed meeting transcript segments
+        Migration {
+            version: 6,
+            description: "create_meeting_segments_table",
+            sql: include_str!("migrations/meeting-segments.sql"),
+            kind: MigrationKind::Up,
+        },
+        // Migration 7: Per-meeting audio archive
+        Migration {
+            version: 7,
+            description: "create_meeting_audio_archive",
+            sql: include_str!("migrations/meeting-audio.sql"),
+            kind: MigrationKind::Up,
+        },
+        // Migration 8: Structured meeting notes alongside the markdown summary
+        Migration {
+            version: 8,
+            description: "add_meeting_summary_json",
+            sql: include_str!("migrations/meeting-summary.sql"),
+            kind: MigrationKind::Up,
+        },
     ]
 }
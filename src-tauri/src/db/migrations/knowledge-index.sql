@@ -0,0 +1,10 @@
+-- Which version of each knowledge item is embedded, written by the background indexer.
+-- Items whose updated_at or embedding model differ from this row get re-indexed.
+CREATE TABLE IF NOT EXISTS knowledge_index_state (
+  item_id TEXT PRIMARY KEY,
+  item_updated_at INTEGER NOT NULL,
+  embedding_model TEXT NOT NULL,
+  chunk_count INTEGER NOT NULL,
+  indexed_at INTEGER NOT NULL,
+  FOREIGN KEY (item_id) REFERENCES knowledge_items(id) ON DELETE CASCADE
+);
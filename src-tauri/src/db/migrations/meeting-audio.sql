@@ -0,0 +1,20 @@
+-- Archived meeting audio: one compressed file per utterance under
+-- `<app data>/meetings/<meeting id>/`. `audio_path` is that directory, and is
+-- cleared when the retention policy deletes it.
+ALTER TABLE meetings ADD COLUMN audio_path TEXT;
+
+CREATE TABLE IF NOT EXISTS meeting_audio_chunks (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  meeting_id TEXT NOT NULL,
+  source TEXT NOT NULL CHECK(source IN ('me', 'them')),
+  speaker INTEGER,
+  file_name TEXT NOT NULL,
+  -- Start of the utterance, relative to the start of the recording
+  offset_ms INTEGER NOT NULL,
+  duration_ms INTEGER NOT NULL,
+  size_bytes INTEGER NOT NULL,
+  created_at INTEGER NOT NULL,
+  FOREIGN KEY (meeting_id) REFERENCES meetings(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_meeting_audio_chunks_meeting ON meeting_audio_chunks(meeting_id, offset_ms);
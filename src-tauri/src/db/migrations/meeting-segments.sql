@@ -0,0 +1,14 @@
+-- Speaker-attributed transcript lines. `source` is the capture side ('me' for the
+-- microphone, 'them' for system audio); `speaker` numbers the remote voices told
+-- apart by diarization and is NULL when unknown.
+CREATE TABLE IF NOT EXISTS meeting_segments (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  meeting_id TEXT NOT NULL,
+  source TEXT NOT NULL CHECK(source IN ('me', 'them')),
+  speaker INTEGER,
+  text TEXT NOT NULL,
+  created_at INTEGER NOT NULL,
+  FOREIGN KEY (meeting_id) REFERENCES meetings(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_meeting_segments_meeting ON meeting_segments(meeting_id, id);
@@ -0,0 +1,3 @@
+-- Structured meeting notes (summary, decisions, action items, open questions)
+-- as JSON; `summary` keeps the markdown rendering shown in the UI.
+ALTER TABLE meetings ADD COLUMN summary_json TEXT;
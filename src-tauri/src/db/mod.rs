@@ -1,3 +1,5 @@
 mod main;
+mod pool;
 
 pub use main::*;
+pub use pool::*;
@@ -0,0 +1,46 @@
+// Rust-side connection to the database the webview opens through tauri-plugin-sql
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::OnceCell;
+
+/// File name behind the plugin's `sqlite:cloak.db` connection string
+pub const DB_FILE_NAME: &str = "cloak.db";
+
+#[derive(Default)]
+pub struct DbState {
+    pool: OnceCell<SqlitePool>,
+}
+
+/// Returns the shared pool, connecting on first use.
+///
+/// The plugin resolves `sqlite:` paths against the app config dir, so we do the
+/// same to end up on the same file. Migrations stay owned by the plugin.
+pub async fn get_pool(app: &AppHandle) -> Result<SqlitePool, String> {
+    let state = app.state::<DbState>();
+    state
+        .pool
+        .get_or_try_init(|| async {
+            let config_dir = app
+                .path()
+                .app_config_dir()
+                .map_err(|e| format!("Failed to get app config directory: {}", e))?;
+            std::fs::create_dir_all(&config_dir)
+                .map_err(|e| format!("Failed to create app config directory: {}", e))?;
+
+            let options = SqliteConnectOptions::new()
+                .filename(config_dir.join(DB_FILE_NAME))
+                .create_if_missing(true)
+                .foreign_keys(true)
+                // The webview holds its own connection; wait instead of failing on locks
+                .busy_timeout(Duration::from_secs(5));
+
+            SqlitePoolOptions::new()
+                .max_connections(4)
+                .connect_with(options)
+                .await
+                .map_err(|e| format!("Failed to open database: {}", e))
+        })
+        .await
+        .cloned()
+}
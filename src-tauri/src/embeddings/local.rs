@@ -0,0 +1,192 @@
+// Local sentence-transformer (BERT family, e.g. all-MiniLM-L6-v2) run with candle on the CPU
+use candle_core::{Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+use futures_util::StreamExt;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+use tokio::io::AsyncWriteExt;
+
+/// Directory name of the model, under `models/` in the resource or app data dir
+pub const LOCAL_MODEL_NAME: &str = "all-MiniLM-L6-v2";
+
+const MODEL_URL: &str =
+    "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main";
+// The weights go last: `find_model_dir` treats their presence as a complete model
+const MODEL_FILES: [&str; 3] = ["config.json", "tokenizer.json", "model.safetensors"];
+
+// Sentence-transformers were trained on short passages; longer input is truncated
+const MAX_SEQUENCE_LENGTH: usize = 256;
+
+// Texts embedded per forward pass, to bound memory on long batches
+const BATCH_SIZE: usize = 16;
+
+pub struct LocalEmbedder {
+    model: BertModel,
+    tokenizer: Tokenizer,
+    device: Device,
+}
+
+/// Finds the model files. `LOCAL_EMBEDDING_MODEL_DIR` wins, then the app data
+/// dir (where `download_model` puts it), then the resource dir for packagers
+/// that ship the model.
+pub fn find_model_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    if let Ok(dir) = std::env::var("LOCAL_EMBEDDING_MODEL_DIR") {
+        if !dir.trim().is_empty() {
+            candidates.push(PathBuf::from(dir.trim()));
+        }
+    }
+    if let Ok(dir) = app.path().app_data_dir() {
+        candidates.push(dir.join("models").join(LOCAL_MODEL_NAME));
+    }
+    if let Ok(dir) = app.path().resource_dir() {
+        candidates.push(dir.join("models").join(LOCAL_MODEL_NAME));
+    }
+
+    candidates
+        .into_iter()
+        .find(|dir| dir.join("model.safetensors").is_file())
+        .ok_or_else(|| {
+            format!(
+                "Local embedding model {} is not downloaded yet",
+                LOCAL_MODEL_NAME
+            )
+        })
+}
+
+/// Downloads the model from Hugging Face into the app data dir (about 90 MB).
+/// Files are written under a temporary name and renamed once complete, so an
+/// interrupted download is resumed file by file and never loaded half-written.
+pub async fn download_model(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("models")
+        .join(LOCAL_MODEL_NAME);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create model directory: {}", e))?;
+
+    let client = reqwest::Client::new();
+    for file in MODEL_FILES {
+        let target = dir.join(file);
+        if target.is_file() {
+            continue;
+        }
+
+        let response = client
+            .get(format!("{}/{}", MODEL_URL, file))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Failed to download {}: {}", file, e))?;
+        let partial = dir.join(format!("{}.part", file));
+        let mut output = tokio::fs::File::create(&partial)
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", file, e))?;
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.map_err(|e| format!("Failed to download {}: {}", file, e))?;
+            output
+                .write_all(&chunk)
+                .await
+                .map_err(|e| format!("Failed to write {}: {}", file, e))?;
+        }
+        output
+            .flush()
+            .await
+            .map_err(|e| format!("Failed to write {}: {}", file, e))?;
+        drop(output);
+        tokio::fs::rename(&partial, &target)
+            .await
+            .map_err(|e| format!("Failed to save {}: {}", file, e))?;
+    }
+    Ok(dir)
+}
+
+impl LocalEmbedder {
+    pub fn load(model_dir: &Path) -> Result<Self, String> {
+        let device = Device::Cpu;
+
+        let config = std::fs::read_to_string(model_dir.join("config.json"))
+            .map_err(|e| format!("Failed to read embedding model config: {}", e))?;
+        let config: Config = serde_json::from_str(&config)
+            .map_err(|e| format!("Failed to parse embedding model config: {}", e))?;
+
+        let mut tokenizer = Tokenizer::from_file(model_dir.join("tokenizer.json"))
+            .map_err(|e| format!("Failed to load embedding tokenizer: {}", e))?;
+        tokenizer.with_padding(Some(PaddingParams::default()));
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: MAX_SEQUENCE_LENGTH,
+                ..Default::default()
+            }))
+            .map_err(|e| format!("Failed to configure embedding tokenizer: {}", e))?;
+
+        // Safety: the weights file is only mapped for reading and is not modified while loaded
+        let vb = unsafe {
+            VarBuilder::from_mmaped_safetensors(
+                &[model_dir.join("model.safetensors")],
+                DTYPE,
+                &device,
+            )
+        }
+        .map_err(|e| format!("Failed to load embedding model weights: {}", e))?;
+        let model = BertModel::load(vb, &config)
+            .map_err(|e| format!("Failed to load embedding model: {}", e))?;
+
+        Ok(Self {
+            model,
+            tokenizer,
+            device,
+        })
+    }
+
+    /// Mean-pooled, L2-normalized sentence embeddings, so cosine similarity is a dot product.
+    pub fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(BATCH_SIZE) {
+            vectors.extend(
+                self.embed_batch(batch)
+                    .map_err(|e| format!("Failed to compute embeddings: {}", e))?,
+            );
+        }
+        Ok(vectors)
+    }
+
+    fn embed_batch(&self, texts: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(anyhow::Error::msg)?;
+
+        let token_ids = encodings
+            .iter()
+            .map(|e| Tensor::new(e.get_ids(), &self.device))
+            .collect::<candle_core::Result<Vec<_>>>()?;
+        let attention_mask = encodings
+            .iter()
+            .map(|e| Tensor::new(e.get_attention_mask(), &self.device))
+            .collect::<candle_core::Result<Vec<_>>>()?;
+        let token_ids = Tensor::stack(&token_ids, 0)?;
+        let attention_mask = Tensor::stack(&attention_mask, 0)?;
+        let token_type_ids = token_ids.zeros_like()?;
+
+        let hidden = self
+            .model
+            .forward(&token_ids, &token_type_ids, Some(&attention_mask))?;
+
+        // Average only over real tokens, not padding
+        let mask = attention_mask.to_dtype(DTYPE)?.unsqueeze(2)?;
+        let summed = hidden.broadcast_mul(&mask)?.sum(1)?;
+        let counts = mask.sum(1)?;
+        let pooled = summed.broadcast_div(&counts)?;
+        let norms = pooled.sqr()?.sum_keepdim(1)?.sqrt()?;
+        let normalized = pooled.broadcast_div(&norms)?;
+
+        Ok(normalized.to_vec2::<f32>()?)
+    }
+}
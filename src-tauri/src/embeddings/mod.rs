@@ -0,0 +1,184 @@
+// Cloak embedding backends: local model, Cloak API, or any OpenAI-compatible `/v1/embeddings`
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tokio::sync::OnceCell;
+
+mod local;
+mod remote;
+
+pub use local::LocalEmbedder;
+
+const CONFIG_FILE_NAME: &str = "embedding_config.json";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingBackend {
+    /// Sentence-transformer run on the CPU; nothing leaves the machine. The
+    /// model is downloaded on first use
+    #[default]
+    Local,
+    /// Cloak server (requires a license)
+    Cloak,
+    /// Any server exposing OpenAI's `/v1/embeddings` (OpenAI, Ollama, LM Studio, ...)
+    OpenAi,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbeddingConfig {
+    #[serde(default)]
+    pub backend: EmbeddingBackend,
+    /// Base URL for the OpenAI-compatible backend, with or without `/v1`
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Model name for the OpenAI-compatible backend
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+impl EmbeddingConfig {
+    /// Identifies the vector space; vectors from different models aren't comparable.
+    pub fn model_signature(&self) -> String {
+        match self.backend {
+            EmbeddingBackend::Local => format!("local:{}", local::LOCAL_MODEL_NAME),
+            EmbeddingBackend::Cloak => "cloak".to_string(),
+            EmbeddingBackend::OpenAi => format!(
+                "openai:{}",
+                self.model
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|model| !model.is_empty())
+                    .unwrap_or(remote::DEFAULT_OPENAI_MODEL)
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct EmbeddingState {
+    config: Mutex<Option<EmbeddingConfig>>,
+    // Loaded on first use; the model stays in memory for the app's lifetime
+    local: OnceCell<Arc<LocalEmbedder>>,
+    downloading: Arc<AtomicBool>,
+}
+
+fn get_config_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+
+    Ok(app_data_dir.join(CONFIG_FILE_NAME))
+}
+
+/// Current embedding configuration, read from disk on first use.
+pub fn get_config(app: &AppHandle) -> EmbeddingConfig {
+    let state = app.state::<EmbeddingState>();
+    let mut cached = state.config.lock().unwrap();
+    cached
+        .get_or_insert_with(|| {
+            get_config_path(app)
+                .ok()
+                .and_then(|path| fs::read_to_string(path).ok())
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_default()
+        })
+        .clone()
+}
+
+/// Embeds each text with the configured backend. Vectors are returned in input order.
+pub async fn embed(app: &AppHandle, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+    if texts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let config = get_config(app);
+    match config.backend {
+        EmbeddingBackend::Local => {
+            let embedder = get_local_embedder(app).await?;
+            let texts = texts.to_vec();
+            tauri::async_runtime::spawn_blocking(move || embedder.embed(&texts))
+                .await
+                .map_err(|e| format!("Embedding task panicked: {}", e))?
+        }
+        EmbeddingBackend::Cloak => {
+            let mut vectors = Vec::with_capacity(texts.len());
+            for text in texts {
+                vectors.push(remote::cloak_embedding(app, text).await?);
+            }
+            Ok(vectors)
+        }
+        EmbeddingBackend::OpenAi => remote::openai_embeddings(&config, texts).await,
+    }
+}
+
+async fn get_local_embedder(app: &AppHandle) -> Result<Arc<LocalEmbedder>, String> {
+    let state = app.state::<EmbeddingState>();
+    state
+        .local
+        .get_or_try_init(|| async {
+            let model_dir = local::find_model_dir(app).inspect_err(|_| start_download(app))?;
+            tauri::async_runtime::spawn_blocking(move || LocalEmbedder::load(&model_dir))
+                .await
+                .map_err(|e| format!("Embedding model load panicked: {}", e))?
+                .map(Arc::new)
+        })
+        .await
+        .cloned()
+}
+
+// Fetches the model in the background instead of holding up the caller; search
+// falls back to keywords and the indexer retries on its next pass
+fn start_download(app: &AppHandle) {
+    let downloading = app.state::<EmbeddingState>().downloading.clone();
+    if downloading.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match local::download_model(&app).await {
+            Ok(dir) => tracing::info!("Downloaded local embedding model to {}", dir.display()),
+            Err(e) => tracing::warn!("Failed to download local embedding model: {}", e),
+        }
+        downloading.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Generate embedding vector for text with the configured backend. Used for semantic search.
+#[tauri::command]
+pub async fn generate_embedding(app: AppHandle, text: String) -> Result<Vec<f32>, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Text cannot be empty".to_string());
+    }
+
+    embed(&app, &[text.to_string()])
+        .await?
+        .pop()
+        .ok_or_else(|| "Embedding backend returned no vector".to_string())
+}
+
+#[tauri::command]
+pub fn get_embedding_config(app: AppHandle) -> Result<EmbeddingConfig, String> {
+    Ok(get_config(&app))
+}
+
+#[tauri::command]
+pub fn set_embedding_config(app: AppHandle, config: EmbeddingConfig) -> Result<(), String> {
+    let path = get_config_path(&app)?;
+    let content = serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize embedding config: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write embedding config: {}", e))?;
+
+    let state = app.state::<EmbeddingState>();
+    *state.config.lock().unwrap() = Some(config);
+    Ok(())
+}
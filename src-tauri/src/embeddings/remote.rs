@@ -0,0 +1,170 @@
+// Embedding backends that run on a server
+use super::EmbeddingConfig;
+use crate::api::{get_api_access_key, get_app_endpoint, get_stored_credentials, is_dev_mode};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_machine_uid::MachineUidExt;
+
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com";
+pub(super) const DEFAULT_OPENAI_MODEL: &str = "text-embedding-3-small";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EmbeddingRequest {
+    text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EmbeddingResponse {
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiEmbeddingResponse {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+// Pulls the most specific message out of an error response body
+fn error_message(status: reqwest::StatusCode, error_text: &str) -> String {
+    if let Ok(err_json) = serde_json::from_str::<serde_json::Value>(error_text) {
+        let message = err_json
+            .get("error")
+            .and_then(|e| e.get("message").or(Some(e)))
+            .or(err_json.get("message"))
+            .and_then(|v| v.as_str());
+        if let Some(msg) = message {
+            return format!("Embedding API ({}): {}", status, msg);
+        }
+    }
+    format!("Embedding API ({}): {}", status, error_text)
+}
+
+/// Embeds one text with the Cloak server.
+pub async fn cloak_embedding(app: &AppHandle, text: &str) -> Result<Vec<f32>, String> {
+    if is_dev_mode() {
+        return Err(
+            "Cloak embeddings are unavailable in dev mode. Use the local or OpenAI-compatible backend."
+                .to_string(),
+        );
+    }
+
+    let app_endpoint = get_app_endpoint()?;
+    let api_access_key = get_api_access_key()?;
+    let (license_key, instance_id) = match get_stored_credentials(app).await {
+        Ok((lk, id, _)) => (lk, id),
+        Err(_) => {
+            return Err("No license. Activate Cloak to use embeddings.".to_string());
+        }
+    };
+    let machine_id = app
+        .machine_uid()
+        .get_machine_uid()
+        .ok()
+        .and_then(|uid| uid.id)
+        .unwrap_or_else(|| "".to_string());
+    let app_version = app.package_info().version.to_string();
+
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/embeddings", app_endpoint.trim_end_matches('/'));
+
+    let body = EmbeddingRequest {
+        text: text.trim().to_string(),
+    };
+
+    let response = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .header("Authorization", format!("Bearer {}", api_access_key))
+        .header("license_key", &license_key)
+        .header("instance", &instance_id)
+        .header("machine_id", &machine_id)
+        .header("app_version", &app_version)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Embedding request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(error_message(status, &error_text));
+    }
+
+    let emb: EmbeddingResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse embedding response: {}", e))?;
+
+    Ok(emb.embedding)
+}
+
+/// Embeds a batch of texts with an OpenAI-compatible `/v1/embeddings` endpoint.
+pub async fn openai_embeddings(
+    config: &EmbeddingConfig,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    let base_url = config
+        .base_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .unwrap_or(DEFAULT_OPENAI_BASE_URL)
+        .trim_end_matches('/')
+        .trim_end_matches("/v1");
+    let model = config
+        .model
+        .as_deref()
+        .map(str::trim)
+        .filter(|model| !model.is_empty())
+        .unwrap_or(DEFAULT_OPENAI_MODEL);
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(60))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let mut request = client
+        .post(format!("{}/v1/embeddings", base_url))
+        .json(&serde_json::json!({ "model": model, "input": texts }));
+    // Local servers run without an API key
+    if let Some(api_key) = config.api_key.as_deref().filter(|k| !k.trim().is_empty()) {
+        request = request.header("Authorization", format!("Bearer {}", api_key.trim()));
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Embedding request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(error_message(status, &error_text));
+    }
+
+    let mut parsed: OpenAiEmbeddingResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse embedding response: {}", e))?;
+    if parsed.data.len() != texts.len() {
+        return Err(format!(
+            "Embedding API returned {} vectors for {} inputs",
+            parsed.data.len(),
+            texts.len()
+        ));
+    }
+
+    parsed.data.sort_by_key(|d| d.index);
+    Ok(parsed.data.into_iter().map(|d| d.embedding).collect())
+}
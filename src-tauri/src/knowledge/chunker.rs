@@ -0,0 +1,109 @@
+// Token-aware overlapping chunking for knowledge item content
+//
+// Token counts are estimated rather than computed with a specific tokenizer,
+// since the embedding backend (and its tokenizer) is user-selectable.
+
+/// Target chunk size; fits the 256-token window of the local model
+pub const CHUNK_TOKENS: usize = 256;
+/// Tokens repeated from the end of the previous chunk, so context isn't cut mid-thought
+pub const OVERLAP_TOKENS: usize = 32;
+
+/// Rough token count: about 4 characters or 0.75 words per token for English text.
+pub fn estimate_tokens(text: &str) -> usize {
+    let chars = text.chars().count().div_ceil(4);
+    let words = (text.split_whitespace().count() * 4).div_ceil(3);
+    chars.max(words)
+}
+
+// Splits text into sentences, keeping paragraph breaks as boundaries
+fn sentences(text: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    for paragraph in text.split("\n\n") {
+        let mut start = 0;
+        let bytes = paragraph.as_bytes();
+        for (i, &b) in bytes.iter().enumerate() {
+            let at_boundary = matches!(b, b'.' | b'!' | b'?' | b'\n')
+                && bytes
+                    .get(i + 1)
+                    .is_none_or(|next| next.is_ascii_whitespace());
+            if at_boundary {
+                let sentence = paragraph[start..=i].trim();
+                if !sentence.is_empty() {
+                    out.push(sentence);
+                }
+                start = i + 1;
+            }
+        }
+        let rest = paragraph[start..].trim();
+        if !rest.is_empty() {
+            out.push(rest);
+        }
+    }
+    out
+}
+
+// Breaks a sentence that is longer than a whole chunk into word runs
+fn split_long(sentence: &str, max_tokens: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for word in sentence.split_whitespace() {
+        current.push(word);
+        if estimate_tokens(&current.join(" ")) >= max_tokens {
+            pieces.push(current.join(" "));
+            current.clear();
+        }
+    }
+    if !current.is_empty() {
+        pieces.push(current.join(" "));
+    }
+    pieces
+}
+
+/// Splits `text` into chunks of about `CHUNK_TOKENS` tokens on sentence
+/// boundaries, each starting with up to `OVERLAP_TOKENS` of the previous chunk.
+pub fn chunk_text(text: &str) -> Vec<String> {
+    let units: Vec<String> = sentences(text.trim())
+        .into_iter()
+        .flat_map(|s| {
+            if estimate_tokens(s) > CHUNK_TOKENS {
+                split_long(s, CHUNK_TOKENS - OVERLAP_TOKENS)
+            } else {
+                vec![s.to_string()]
+            }
+        })
+        .collect();
+
+    let mut chunks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut current_tokens = 0;
+    // Number of leading units in `current` carried over from the previous chunk
+    let mut carried = 0;
+
+    for unit in &units {
+        let unit_tokens = estimate_tokens(unit);
+        if current_tokens + unit_tokens > CHUNK_TOKENS && current.len() > carried {
+            chunks.push(current.join(" "));
+
+            // Carry the tail of this chunk into the next one
+            let mut overlap: Vec<&str> = Vec::new();
+            let mut overlap_tokens = 0;
+            for prev in current.iter().rev() {
+                let tokens = estimate_tokens(prev);
+                if overlap_tokens + tokens > OVERLAP_TOKENS {
+                    break;
+                }
+                overlap.insert(0, prev);
+                overlap_tokens += tokens;
+            }
+            carried = overlap.len();
+            current = overlap;
+            current_tokens = overlap_tokens;
+        }
+        current.push(unit);
+        current_tokens += unit_tokens;
+    }
+    if current.len() > carried {
+        chunks.push(current.join(" "));
+    }
+    chunks
+}
@@ -0,0 +1,402 @@
+// Background indexer: mirrors conversations, completed meetings and pages into
+// knowledge_items, then chunks and embeds every item whose content changed.
+//
+// Progress lives in `knowledge_index_state`, so an interrupted pass simply
+// resumes with the remaining items after a restart.
+use super::chunker::chunk_text;
+use super::KnowledgeState;
+use crate::{db, embeddings};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+// Give the SQL plugin time to run migrations before the first pass
+const STARTUP_DELAY: Duration = Duration::from_secs(10);
+const EMBED_BATCH_SIZE: usize = 32;
+// A backend that keeps failing (offline, no model) ends the pass early
+const MAX_CONSECUTIVE_FAILURES: usize = 3;
+
+#[derive(Debug, Clone, Serialize)]
+struct IndexProgress {
+    item_id: String,
+    title: String,
+    processed: usize,
+    total: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IndexSummary {
+    /// Knowledge items created or updated from their source
+    pub synced: usize,
+    /// Items whose chunks were (re-)embedded
+    pub indexed: usize,
+    pub failed: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexStatus {
+    pub total_items: i64,
+    pub indexed_items: i64,
+    pub running: bool,
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Starts the indexer loop. It runs a pass on startup, every `POLL_INTERVAL`,
+/// and whenever `reindex_knowledge` is called.
+pub fn spawn_indexer(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+        loop {
+            if let Err(e) = run_pass(&app).await {
+                tracing::warn!("Knowledge indexing failed: {}", e);
+            }
+
+            let state = app.state::<KnowledgeState>();
+            tokio::select! {
+                _ = state.wake.notified() => {}
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            }
+        }
+    });
+}
+
+async fn run_pass(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<KnowledgeState>();
+    state.indexing.store(true, Ordering::SeqCst);
+    let result = index_pending(app).await;
+    state.indexing.store(false, Ordering::SeqCst);
+
+    let summary = result?;
+    if summary.synced > 0 || summary.indexed > 0 || summary.failed > 0 {
+        let _ = app.emit("knowledge-index-complete", &summary);
+    }
+    Ok(())
+}
+
+async fn index_pending(app: &AppHandle) -> Result<IndexSummary, String> {
+    let pool = db::get_pool(app).await?;
+    let mut summary = IndexSummary {
+        synced: sync_sources(&pool).await?,
+        ..Default::default()
+    };
+
+    let model = embeddings::get_config(app).model_signature();
+    let pending: Vec<(String, String)> = sqlx::query_as(
+        "SELECT k.id, k.title FROM knowledge_items k
+         LEFT JOIN knowledge_index_state s ON s.item_id = k.id
+         WHERE s.item_id IS NULL OR s.item_updated_at != k.updated_at OR s.embedding_model != ?
+         ORDER BY k.updated_at DESC",
+    )
+    .bind(&model)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to find items to index: {}", e))?;
+
+    let total = pending.len();
+    let mut consecutive_failures = 0;
+    for (processed, (item_id, title)) in pending.into_iter().enumerate() {
+        let _ = app.emit(
+            "knowledge-index-progress",
+            IndexProgress {
+                item_id: item_id.clone(),
+                title,
+                processed,
+                total,
+            },
+        );
+
+        match index_item(app, &pool, &item_id, &model).await {
+            Ok(()) => {
+                summary.indexed += 1;
+                consecutive_failures = 0;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to index knowledge item {}: {}", item_id, e);
+                summary.failed += 1;
+                summary.error = Some(e);
+                consecutive_failures += 1;
+                if consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+                    break;
+                }
+            }
+        }
+    }
+
+    if summary.indexed > 0 {
+        let state = app.state::<KnowledgeState>();
+        state.index.lock().await.invalidate();
+    }
+    Ok(summary)
+}
+
+// Replaces an item's chunks and embeddings in one transaction
+async fn index_item(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    item_id: &str,
+    model: &str,
+) -> Result<(), String> {
+    let item: Option<(String, i64)> =
+        sqlx::query_as("SELECT content, updated_at FROM knowledge_items WHERE id = ?")
+            .bind(item_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load knowledge item: {}", e))?;
+    // Deleted since the pass started
+    let Some((content, updated_at)) = item else {
+        return Ok(());
+    };
+
+    let chunks = chunk_text(&content);
+    let mut vectors = Vec::with_capacity(chunks.len());
+    for batch in chunks.chunks(EMBED_BATCH_SIZE) {
+        vectors.extend(embeddings::embed(app, batch).await?);
+    }
+
+    let now = now_millis();
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    sqlx::query("DELETE FROM embeddings WHERE item_id = ?")
+        .bind(item_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete old embeddings: {}", e))?;
+
+    for (chunk_index, (chunk, vector)) in chunks.iter().zip(&vectors).enumerate() {
+        let embedding = serde_json::to_string(vector)
+            .map_err(|e| format!("Failed to serialize embedding: {}", e))?;
+        sqlx::query(
+            "INSERT INTO embeddings (id, item_id, chunk_index, chunk_text, embedding, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(format!("emb-{}-{}-{}", item_id, chunk_index, now))
+        .bind(item_id)
+        .bind(chunk_index as i64)
+        .bind(chunk)
+        .bind(embedding)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to insert embedding: {}", e))?;
+    }
+
+    sqlx::query(
+        "INSERT INTO knowledge_index_state (item_id, item_updated_at, embedding_model, chunk_count, indexed_at)
+         VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(item_id) DO UPDATE SET
+           item_updated_at = excluded.item_updated_at,
+           embedding_model = excluded.embedding_model,
+           chunk_count = excluded.chunk_count,
+           indexed_at = excluded.indexed_at",
+    )
+    .bind(item_id)
+    .bind(updated_at)
+    .bind(model)
+    .bind(chunks.len() as i64)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to record index state: {}", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit embeddings: {}", e))
+}
+
+struct SourceItem {
+    source_id: String,
+    item_type: &'static str,
+    title: String,
+    content: String,
+    created_at: i64,
+    updated_at: i64,
+    existing_item_id: Option<String>,
+}
+
+// Creates or refreshes knowledge items for new or changed sources.
+// Returns how many items were written.
+async fn sync_sources(pool: &SqlitePool) -> Result<usize, String> {
+    let mut sources: Vec<SourceItem> = Vec::new();
+
+    let conversations: Vec<(String, String, i64, i64, Option<String>)> = sqlx::query_as(
+        "SELECT c.id, c.title, c.created_at, c.updated_at, k.id FROM conversations c
+         LEFT JOIN knowledge_items k ON k.source_id = c.id AND k.type = 'conversation'
+         WHERE k.id IS NULL OR c.updated_at > k.updated_at",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load conversations: {}", e))?;
+    for (id, title, created_at, updated_at, existing_item_id) in conversations {
+        let messages: Vec<(String, String)> = sqlx::query_as(
+            "SELECT role, content FROM messages WHERE conversation_id = ? ORDER BY timestamp ASC",
+        )
+        .bind(&id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load messages: {}", e))?;
+        let content = messages
+            .iter()
+            .map(|(role, content)| format!("[{}]: {}", role, content))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        sources.push(SourceItem {
+            source_id: id,
+            item_type: "conversation",
+            title,
+            content,
+            created_at,
+            updated_at,
+            existing_item_id,
+        });
+    }
+
+    // Meetings are indexed once recording and processing are done
+    let meetings: Vec<(String, String, String, i64, i64, Option<String>)> = sqlx::query_as(
+        "SELECT m.id, m.title,
+           CASE WHEN TRIM(m.notes) = '' THEN m.transcript
+                ELSE m.transcript || char(10) || char(10) || 'Notes:' || char(10) || m.notes END,
+           m.created_at, m.updated_at, k.id
+         FROM meetings m
+         LEFT JOIN knowledge_items k ON k.source_id = m.id AND k.type = 'transcription'
+         WHERE m.status = 'completed' AND (k.id IS NULL OR m.updated_at > k.updated_at)",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load meetings: {}", e))?;
+    for (id, title, content, created_at, updated_at, existing_item_id) in meetings {
+        sources.push(SourceItem {
+            source_id: id,
+            item_type: "transcription",
+            title,
+            content,
+            created_at,
+            updated_at,
+            existing_item_id,
+        });
+    }
+
+    let pages: Vec<(String, String, String, i64, i64, Option<String>)> = sqlx::query_as(
+        "SELECT p.id, p.title, p.content, p.created_at, p.updated_at, k.id FROM pages p
+         LEFT JOIN knowledge_items k ON k.source_id = p.id AND k.type = 'page'
+         WHERE k.id IS NULL OR p.updated_at > k.updated_at",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load pages: {}", e))?;
+    for (id, title, content, created_at, updated_at, existing_item_id) in pages {
+        sources.push(SourceItem {
+            source_id: id,
+            item_type: "page",
+            title,
+            content,
+            created_at,
+            updated_at,
+            existing_item_id,
+        });
+    }
+
+    let mut written = 0;
+    for source in sources {
+        if source.content.trim().is_empty() {
+            continue;
+        }
+        let title = if source.title.trim().is_empty() {
+            let preview: String = source.content.chars().take(80).collect();
+            if source.content.chars().count() > 80 {
+                format!("{}…", preview)
+            } else {
+                preview
+            }
+        } else {
+            source.title
+        };
+
+        let result = match &source.existing_item_id {
+            Some(item_id) => sqlx::query(
+                "UPDATE knowledge_items SET title = ?, content = ?, updated_at = ? WHERE id = ?",
+            )
+            .bind(&title)
+            .bind(&source.content)
+            .bind(source.updated_at)
+            .bind(item_id)
+            .execute(pool)
+            .await,
+            None => {
+                let item_id = format!(
+                    "ki-{}-{}",
+                    now_millis(),
+                    &uuid::Uuid::new_v4().simple().to_string()[..9]
+                );
+                sqlx::query(
+                    "INSERT INTO knowledge_items (id, type, title, content, summary, source_id, created_at, updated_at)
+                     VALUES (?, ?, ?, ?, NULL, ?, ?, ?)",
+                )
+                .bind(item_id)
+                .bind(source.item_type)
+                .bind(&title)
+                .bind(&source.content)
+                .bind(&source.source_id)
+                .bind(source.created_at)
+                .bind(source.updated_at)
+                .execute(pool)
+                .await
+            }
+        };
+        result.map_err(|e| format!("Failed to save knowledge item: {}", e))?;
+        written += 1;
+    }
+
+    Ok(written)
+}
+
+/// Wakes the indexer now. With `force`, every item is re-chunked and re-embedded.
+#[tauri::command]
+pub async fn reindex_knowledge(app: AppHandle, force: Option<bool>) -> Result<(), String> {
+    if force.unwrap_or(false) {
+        let pool = db::get_pool(&app).await?;
+        sqlx::query("DELETE FROM knowledge_index_state")
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("Failed to reset index state: {}", e))?;
+    }
+
+    app.state::<KnowledgeState>().wake.notify_one();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_knowledge_index_status(app: AppHandle) -> Result<IndexStatus, String> {
+    let pool = db::get_pool(&app).await?;
+    let model = embeddings::get_config(&app).model_signature();
+    let (total_items, indexed_items): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*), COUNT(s.item_id) FROM knowledge_items k
+         LEFT JOIN knowledge_index_state s
+           ON s.item_id = k.id AND s.item_updated_at = k.updated_at AND s.embedding_model = ?",
+    )
+    .bind(&model)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| format!("Failed to read index status: {}", e))?;
+
+    Ok(IndexStatus {
+        total_items,
+        indexed_items,
+        running: app
+            .state::<KnowledgeState>()
+            .indexing
+            .load(Ordering::SeqCst),
+    })
+}
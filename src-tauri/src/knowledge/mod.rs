@@ -0,0 +1,22 @@
+// Cloak knowledge base: background indexing plus vector + keyword search over knowledge_items
+use std::sync::atomic::AtomicBool;
+use tokio::sync::{Mutex, Notify};
+
+mod chunker;
+mod indexer;
+mod rag;
+mod search;
+mod vector_index;
+
+pub use indexer::*;
+pub use rag::*;
+pub use search::*;
+use vector_index::VectorIndex;
+
+#[derive(Default)]
+pub struct KnowledgeState {
+    index: Mutex<VectorIndex>,
+    // Wakes the background indexer before its next scheduled pass
+    wake: Notify,
+    indexing: AtomicBool,
+}
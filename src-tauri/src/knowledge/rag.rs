@@ -0,0 +1,91 @@
+// Retrieval-augmented chat: packs the best knowledge chunks into a cited context block
+use super::chunker::estimate_tokens;
+use super::search::{search, SearchFilters};
+use serde::Serialize;
+use tauri::AppHandle;
+
+/// Chunks retrieved before packing; the token budget usually keeps fewer
+pub const DEFAULT_RAG_K: usize = 8;
+/// Upper bound on the injected context, leaving room for history and the answer
+pub const DEFAULT_RAG_TOKEN_BUDGET: usize = 1500;
+
+/// A knowledge item cited in the injected context as `[citation]`.
+#[derive(Debug, Clone, Serialize)]
+pub struct KnowledgeSource {
+    pub citation: usize,
+    pub item_id: String,
+    pub item_type: String,
+    pub title: String,
+    pub chunk_indexes: Vec<i64>,
+}
+
+/// Context to add to a chat request, and the items it cites.
+pub struct KnowledgeContext {
+    pub prompt: String,
+    pub sources: Vec<KnowledgeSource>,
+}
+
+/// Retrieves chunks relevant to `query` and formats them as a system prompt
+/// with one citation number per item. Returns None when nothing relevant fits.
+pub async fn build_context(
+    app: &AppHandle,
+    query: &str,
+    k: usize,
+    token_budget: usize,
+) -> Result<Option<KnowledgeContext>, String> {
+    let results = search(app, query, k, &SearchFilters::default()).await?;
+
+    let mut sources: Vec<KnowledgeSource> = Vec::new();
+    // (citation, text) in rank order
+    let mut excerpts: Vec<(usize, String)> = Vec::new();
+    let mut used_tokens = 0;
+
+    for result in results {
+        let tokens = estimate_tokens(&result.chunk_text) + estimate_tokens(&result.title) + 8;
+        // Skip chunks that don't fit; a later, shorter one still might
+        if used_tokens + tokens > token_budget {
+            continue;
+        }
+        used_tokens += tokens;
+
+        let citation = match sources.iter_mut().find(|s| s.item_id == result.item_id) {
+            Some(source) => {
+                source.chunk_indexes.extend(result.chunk_index);
+                source.citation
+            }
+            None => {
+                let citation = sources.len() + 1;
+                sources.push(KnowledgeSource {
+                    citation,
+                    item_id: result.item_id,
+                    item_type: result.item_type,
+                    title: result.title,
+                    chunk_indexes: result.chunk_index.into_iter().collect(),
+                });
+                citation
+            }
+        };
+        excerpts.push((citation, result.chunk_text));
+    }
+
+    if sources.is_empty() {
+        return Ok(None);
+    }
+
+    let mut prompt = String::from(
+        "The following excerpts from the user's notes, past conversations and meetings may help answer. \
+         Use them only if relevant, and cite them inline as [n] when you do.\n",
+    );
+    for source in &sources {
+        prompt.push_str(&format!(
+            "\n[{}] {} ({})\n",
+            source.citation, source.title, source.item_type
+        ));
+        for (_, text) in excerpts.iter().filter(|(c, _)| *c == source.citation) {
+            prompt.push_str(text.trim());
+            prompt.push('\n');
+        }
+    }
+
+    Ok(Some(KnowledgeContext { prompt, sources }))
+}
@@ -0,0 +1,327 @@
+// Hybrid search: cosine top-k over chunk embeddings fused with knowledge_fts BM25
+use super::vector_index::{dot, normalize};
+use super::KnowledgeState;
+use crate::{db, embeddings};
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use tauri::{AppHandle, Manager};
+
+const DEFAULT_K: usize = 10;
+const MAX_K: usize = 100;
+// Each ranking contributes this many candidates per requested result
+const CANDIDATE_FACTOR: usize = 4;
+const MIN_CANDIDATES: usize = 50;
+// Reciprocal rank fusion constant; 60 is the value from the original RRF paper
+const RRF_K: f64 = 60.0;
+
+/// Restricts a search to matching items. Empty lists don't filter.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFilters {
+    /// Tag IDs or names; items with any of them match
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Project IDs; items in any of them match
+    #[serde(default)]
+    pub projects: Vec<String>,
+    /// `conversation`, `transcription` and/or `page`
+    #[serde(default)]
+    pub item_types: Vec<String>,
+    /// Specific knowledge item IDs
+    #[serde(default)]
+    pub item_ids: Vec<String>,
+}
+
+impl SearchFilters {
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+            && self.projects.is_empty()
+            && self.item_types.is_empty()
+            && self.item_ids.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnowledgeSearchResult {
+    pub item_id: String,
+    pub item_type: String,
+    pub title: String,
+    pub summary: Option<String>,
+    /// None when the item matched by keyword but has no embedded chunks yet
+    pub chunk_index: Option<i64>,
+    /// The chunk text, or an FTS snippet when there is no chunk
+    pub chunk_text: String,
+    /// Fused rank score; only meaningful for ordering
+    pub score: f64,
+    pub vector_score: Option<f32>,
+    pub keyword_score: Option<f64>,
+    pub updated_at: i64,
+}
+
+// One fused candidate before its text and item metadata are loaded
+#[derive(Default)]
+struct Candidate {
+    item_id: String,
+    embedding_id: Option<String>,
+    chunk_index: Option<i64>,
+    snippet: Option<String>,
+    score: f64,
+    vector_score: Option<f32>,
+    keyword_score: Option<f64>,
+}
+
+/// Quotes each word so FTS5 operators and punctuation in user text can't break the query.
+pub fn fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|term| term.replace('"', ""))
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"", term))
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+fn rrf(rank: usize) -> f64 {
+    1.0 / (RRF_K + rank as f64 + 1.0)
+}
+
+// IDs of the items passing the filters, or None when there are no filters
+async fn filtered_item_ids(
+    pool: &SqlitePool,
+    filters: &SearchFilters,
+) -> Result<Option<HashSet<String>>, String> {
+    if filters.is_empty() {
+        return Ok(None);
+    }
+
+    let mut qb = QueryBuilder::<Sqlite>::new("SELECT k.id FROM knowledge_items k WHERE 1 = 1");
+    if !filters.item_types.is_empty() {
+        qb.push(" AND k.type IN (");
+        let mut list = qb.separated(", ");
+        for item_type in &filters.item_types {
+            list.push_bind(item_type);
+        }
+        qb.push(")");
+    }
+    if !filters.item_ids.is_empty() {
+        qb.push(" AND k.id IN (");
+        let mut list = qb.separated(", ");
+        for item_id in &filters.item_ids {
+            list.push_bind(item_id);
+        }
+        qb.push(")");
+    }
+    if !filters.tags.is_empty() {
+        qb.push(
+            " AND EXISTS (SELECT 1 FROM item_tags it JOIN tags t ON t.id = it.tag_id \
+             WHERE it.item_id = k.id AND (t.id IN (",
+        );
+        let mut list = qb.separated(", ");
+        for tag in &filters.tags {
+            list.push_bind(tag);
+        }
+        qb.push(") OR t.name IN (");
+        let mut list = qb.separated(", ");
+        for tag in &filters.tags {
+            list.push_bind(tag);
+        }
+        qb.push(")))");
+    }
+    if !filters.projects.is_empty() {
+        qb.push(
+            " AND EXISTS (SELECT 1 FROM project_items pi \
+             WHERE pi.item_id = k.id AND pi.project_id IN (",
+        );
+        let mut list = qb.separated(", ");
+        for project in &filters.projects {
+            list.push_bind(project);
+        }
+        qb.push("))");
+    }
+
+    let ids: Vec<(String,)> = qb
+        .build_query_as()
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to apply search filters: {}", e))?;
+    Ok(Some(ids.into_iter().map(|(id,)| id).collect()))
+}
+
+/// Ranks knowledge chunks for `query`, best first.
+///
+/// Vector and keyword rankings are combined with reciprocal rank fusion. If the
+/// query can't be embedded (e.g. no local model), keyword results are still returned.
+pub async fn search(
+    app: &AppHandle,
+    query: &str,
+    k: usize,
+    filters: &SearchFilters,
+) -> Result<Vec<KnowledgeSearchResult>, String> {
+    let query = query.trim();
+    if query.is_empty() || k == 0 {
+        return Ok(Vec::new());
+    }
+    let candidate_count = (k * CANDIDATE_FACTOR).max(MIN_CANDIDATES);
+
+    let pool = db::get_pool(app).await?;
+    let allowed = filtered_item_ids(&pool, filters).await?;
+    let is_allowed = |item_id: &str| allowed.as_ref().is_none_or(|ids| ids.contains(item_id));
+
+    let query_vector = match embeddings::embed(app, &[query.to_string()]).await {
+        Ok(mut vectors) => vectors.pop(),
+        Err(e) => {
+            tracing::warn!("Knowledge search without vectors: {}", e);
+            None
+        }
+    };
+
+    let mut candidates: HashMap<String, Candidate> = HashMap::new();
+    // Best-scoring chunk of each item, so keyword hits can point at a chunk
+    let mut best_chunks: HashMap<String, (String, i64, f32)> = HashMap::new();
+
+    if let Some(mut query_vector) = query_vector {
+        normalize(&mut query_vector);
+        let model = embeddings::get_config(app).model_signature();
+
+        let state = app.state::<KnowledgeState>();
+        let mut index = state.index.lock().await;
+        index.refresh(&pool).await?;
+
+        let mut scored: Vec<(usize, f32)> = index
+            .entries
+            .iter()
+            .enumerate()
+            // Vectors from another embedding model aren't comparable, even at the
+            // same dimension; items still waiting to be re-indexed sit out
+            .filter(|(_, e)| e.embedding_model == model && is_allowed(&e.item_id))
+            .map(|(i, e)| (i, dot(&query_vector, &e.vector)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        for &(i, score) in &scored {
+            let entry = &index.entries[i];
+            best_chunks
+                .entry(entry.item_id.clone())
+                .or_insert_with(|| (entry.embedding_id.clone(), entry.chunk_index, score));
+        }
+        for (rank, &(i, score)) in scored.iter().take(candidate_count).enumerate() {
+            let entry = &index.entries[i];
+            candidates.insert(
+                entry.embedding_id.clone(),
+                Candidate {
+                    item_id: entry.item_id.clone(),
+                    embedding_id: Some(entry.embedding_id.clone()),
+                    chunk_index: Some(entry.chunk_index),
+                    score: rrf(rank),
+                    vector_score: Some(score),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    let match_query = fts_query(query);
+    if !match_query.is_empty() {
+        let rows: Vec<(String, f64, String)> = sqlx::query_as(
+            "SELECT k.id, bm25(knowledge_fts), snippet(knowledge_fts, 1, '', '', '...', 48)
+             FROM knowledge_fts
+             JOIN knowledge_items k ON k.rowid = knowledge_fts.rowid
+             WHERE knowledge_fts MATCH ?
+             ORDER BY bm25(knowledge_fts)
+             LIMIT ?",
+        )
+        .bind(&match_query)
+        .bind(candidate_count as i64)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to search knowledge base: {}", e))?;
+
+        let keyword_hits = rows
+            .into_iter()
+            .filter(|(item_id, _, _)| is_allowed(item_id));
+        for (rank, (item_id, bm25, snippet)) in keyword_hits.enumerate() {
+            let (key, embedding_id, chunk_index, vector_score) = match best_chunks.get(&item_id) {
+                Some((embedding_id, chunk_index, score)) => (
+                    embedding_id.clone(),
+                    Some(embedding_id.clone()),
+                    Some(*chunk_index),
+                    Some(*score),
+                ),
+                None => (format!("fts:{}", item_id), None, None, None),
+            };
+            let candidate = candidates.entry(key).or_insert_with(|| Candidate {
+                item_id: item_id.clone(),
+                embedding_id,
+                chunk_index,
+                vector_score,
+                ..Default::default()
+            });
+            candidate.score += rrf(rank);
+            // SQLite's bm25() is negative; flip it so higher means more relevant
+            candidate.keyword_score = Some(-bm25);
+            candidate.snippet = Some(snippet);
+        }
+    }
+
+    let mut ranked: Vec<Candidate> = candidates.into_values().collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked.truncate(k);
+
+    let mut results = Vec::with_capacity(ranked.len());
+    for candidate in ranked {
+        let item: Option<(String, String, Option<String>, i64)> = sqlx::query_as(
+            "SELECT type, title, summary, updated_at FROM knowledge_items WHERE id = ?",
+        )
+        .bind(&candidate.item_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| format!("Failed to load knowledge item: {}", e))?;
+        // Deleted since the index was loaded
+        let Some((item_type, title, summary, updated_at)) = item else {
+            continue;
+        };
+
+        let chunk_text = match &candidate.embedding_id {
+            Some(embedding_id) => {
+                sqlx::query_scalar::<_, String>("SELECT chunk_text FROM embeddings WHERE id = ?")
+                    .bind(embedding_id)
+                    .fetch_optional(&pool)
+                    .await
+                    .map_err(|e| format!("Failed to load knowledge chunk: {}", e))?
+            }
+            None => None,
+        };
+        let Some(chunk_text) = chunk_text.or(candidate.snippet) else {
+            continue;
+        };
+
+        results.push(KnowledgeSearchResult {
+            item_id: candidate.item_id,
+            item_type,
+            title,
+            summary,
+            chunk_index: candidate.chunk_index,
+            chunk_text,
+            score: candidate.score,
+            vector_score: candidate.vector_score,
+            keyword_score: candidate.keyword_score,
+            updated_at,
+        });
+    }
+
+    Ok(results)
+}
+
+/// Search the knowledge base by meaning and keywords, optionally filtered by tag, project or type.
+#[tauri::command]
+pub async fn search_knowledge(
+    app: AppHandle,
+    query: String,
+    k: Option<usize>,
+    filters: Option<SearchFilters>,
+) -> Result<Vec<KnowledgeSearchResult>, String> {
+    let k = k.unwrap_or(DEFAULT_K).clamp(1, MAX_K);
+    search(&app, &query, k, &filters.unwrap_or_default()).await
+}
@@ -0,0 +1,82 @@
+// In-memory copy of the `embeddings` table, so a search is a scan over packed
+// f32 vectors instead of parsing every JSON row in the webview.
+use sqlx::SqlitePool;
+
+pub struct IndexEntry {
+    pub embedding_id: String,
+    pub item_id: String,
+    pub chunk_index: i64,
+    /// `model_signature()` of the backend that produced the vector
+    pub embedding_model: String,
+    /// L2-normalized, so cosine similarity is a dot product
+    pub vector: Vec<f32>,
+}
+
+// Cheap summary of the table used to notice writes from the webview or the indexer
+type Fingerprint = (i64, i64, i64);
+
+#[derive(Default)]
+pub struct VectorIndex {
+    fingerprint: Option<Fingerprint>,
+    pub entries: Vec<IndexEntry>,
+}
+
+pub fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+impl VectorIndex {
+    /// Reloads the index if the `embeddings` table changed since the last load.
+    pub async fn refresh(&mut self, pool: &SqlitePool) -> Result<(), String> {
+        let fingerprint: Fingerprint = sqlx::query_as(
+            "SELECT COUNT(*), COALESCE(MAX(rowid), 0), COALESCE(MAX(created_at), 0) FROM embeddings",
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to read embeddings: {}", e))?;
+        if self.fingerprint == Some(fingerprint) {
+            return Ok(());
+        }
+
+        let rows: Vec<(String, String, i64, String, String)> = sqlx::query_as(
+            "SELECT e.id, e.item_id, e.chunk_index, s.embedding_model, e.embedding
+             FROM embeddings e
+             JOIN knowledge_index_state s ON s.item_id = e.item_id",
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load embeddings: {}", e))?;
+
+        self.entries = rows
+            .into_iter()
+            .filter_map(|(embedding_id, item_id, chunk_index, model, embedding)| {
+                let mut vector: Vec<f32> = serde_json::from_str(&embedding).ok()?;
+                if vector.is_empty() {
+                    return None;
+                }
+                normalize(&mut vector);
+                Some(IndexEntry {
+                    embedding_id,
+                    item_id,
+                    chunk_index,
+                    embedding_model: model,
+                    vector,
+                })
+            })
+            .collect();
+        self.fingerprint = Some(fingerprint);
+        Ok(())
+    }
+
+    /// Forces a reload on the next search.
+    pub fn invalidate(&mut self) {
+        self.fingerprint = None;
+    }
+}
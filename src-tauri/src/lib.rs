@@ -2,17 +2,25 @@
 mod activate;
 mod api;
 mod capture;
+mod copilot;
 mod db;
+mod embeddings;
 mod google_oauth;
+mod knowledge;
+mod meetings;
+mod providers;
 mod shortcuts;
+mod stt;
+mod tools;
 mod window;
 use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Manager, WebviewWindow};
 use tauri_plugin_posthog::{init as posthog_init, PostHogConfig, PostHogOptions};
+use tokio::sync::mpsc::UnboundedSender;
 use tokio::task::JoinHandle;
 pub mod speaker;
 use capture::CaptureState;
-use speaker::VadConfig;
+use speaker::{DiarizationConfig, VadConfig};
 
 #[cfg(target_os = "macos")]
 #[allow(deprecated)]
@@ -22,7 +30,12 @@ use tauri_nspanel::{cocoa::appkit::NSWindowCollectionBehavior, panel_delegate, W
 pub struct AudioState {
     stream_task: Arc<Mutex<Option<JoinHandle<()>>>>,
     vad_config: Arc<Mutex<VadConfig>>,
+    diarization_config: Arc<Mutex<DiarizationConfig>>,
     is_capturing: Arc<Mutex<bool>>,
+    // Copy of every finished utterance, for the meeting audio archive
+    utterance_tap: Arc<Mutex<Option<UnboundedSender<speaker::CapturedUtterance>>>>,
+    // Set while a capture follows the default device
+    device_follower: Arc<Mutex<Option<speaker::DeviceFollower>>>,
 }
 
 #[tauri::command]
@@ -42,7 +55,16 @@ pub fn run() {
                 .build(),
         )
         .manage(AudioState::default())
+        .manage(speaker::SegmentStore::default())
+        .manage(meetings::MeetingRecorderState::default())
+        .manage(meetings::MeetingArchiveState::default())
         .manage(CaptureState::default())
+        .manage(api::ChatStreamState::default())
+        .manage(copilot::CopilotState::default())
+        .manage(db::DbState::default())
+        .manage(embeddings::EmbeddingState::default())
+        .manage(knowledge::KnowledgeState::default())
+        .manage(stt::SttState::default())
         .manage(shortcuts::WindowVisibility {
             is_hidden: Mutex::new(false),
         })
@@ -100,25 +122,59 @@ pub fn run() {
             activate::secure_storage_get,
             activate::secure_storage_remove,
             api::transcribe_audio,
+            api::transcribe_segment,
             api::chat_stream_response,
+            api::cancel_chat_stream,
             api::fetch_models,
             api::fetch_prompts,
             api::create_system_prompt,
             api::check_license_status,
             api::get_activity,
             api::get_env_config,
-            api::generate_embedding,
+            embeddings::generate_embedding,
+            embeddings::get_embedding_config,
+            embeddings::set_embedding_config,
+            knowledge::search_knowledge,
+            knowledge::reindex_knowledge,
+            knowledge::get_knowledge_index_status,
             speaker::start_system_audio_capture,
+            speaker::start_dual_audio_capture,
             speaker::stop_system_audio_capture,
             speaker::manual_stop_continuous,
             speaker::check_system_audio_access,
             speaker::request_system_audio_access,
             speaker::get_vad_config,
             speaker::update_vad_config,
+            speaker::analyze_vad_recording,
+            speaker::get_speech_segment,
+            speaker::release_speech_segment,
+            speaker::get_diarization_config,
+            speaker::update_diarization_config,
             speaker::get_capture_status,
             speaker::get_audio_sample_rate,
             speaker::get_input_devices,
             speaker::get_output_devices,
+            speaker::get_audio_applications,
+            stt::get_streaming_stt_config,
+            stt::set_streaming_stt_config,
+            stt::get_local_whisper_config,
+            stt::set_local_whisper_config,
+            meetings::append_meeting_transcript,
+            meetings::get_meeting_segments,
+            meetings::start_meeting,
+            meetings::stop_meeting,
+            meetings::get_active_meeting,
+            meetings::get_meeting_archive_config,
+            meetings::set_meeting_archive_config,
+            meetings::retranscribe_meeting,
+            meetings::delete_meeting_audio,
+            meetings::summarize_meeting,
+            meetings::get_meeting_notes,
+            meetings::draft_follow_up_email,
+            copilot::get_copilot_config,
+            copilot::set_copilot_config,
+            copilot::set_copilot_context,
+            copilot::copilot_observe_transcript,
             google_oauth::google_oauth_start,
         ])
         .setup(|app| {
@@ -127,6 +183,19 @@ pub fn run() {
             #[cfg(target_os = "macos")]
             init(app.app_handle());
             let app_handle = app.handle();
+            knowledge::spawn_indexer(app_handle.clone());
+            copilot::spawn(app_handle.clone());
+            speaker::watch_audio_devices(app_handle);
+            std::thread::spawn(speaker::remove_stale_capture_routes);
+            tauri::async_runtime::spawn({
+                let app_handle = app_handle.clone();
+                async move {
+                    meetings::recover_interrupted_meetings(app_handle.clone()).await;
+                    if let Err(e) = meetings::enforce_retention(&app_handle).await {
+                        eprintln!("Failed to apply meeting audio retention: {}", e);
+                    }
+                }
+            });
             if app_handle.get_webview_window("dashboard").is_none() {
                 if let Err(e) = window::create_dashboard_window(&app_handle) {
                     eprintln!("Failed to pre-create dashboard window on startup: {}", e);
@@ -0,0 +1,467 @@
+// Cloak meeting audio archive: while a meeting records, each utterance is kept
+// as a compressed file under `<app data>/meetings/<meeting id>/`, so a badly
+// transcribed call can be transcribed again later. Archives are pruned by age
+// and by total size.
+use super::{now_millis, parse_source, source_name, transcript_line};
+use crate::api;
+use crate::db;
+use crate::speaker::CapturedUtterance;
+use crate::stt::{self, codec, codec::AudioEncoding};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+const CONFIG_FILE_NAME: &str = "meeting_archive_config.json";
+const ARCHIVE_DIR: &str = "meetings";
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeetingArchiveConfig {
+    /// Keep the audio of recorded meetings
+    pub enabled: bool,
+    /// Archives older than this many days are deleted; `None` keeps them
+    pub retention_days: Option<u32>,
+    /// Oldest archives are deleted while all of them together exceed this
+    pub max_size_mb: Option<u64>,
+    pub encoding: AudioEncoding,
+}
+
+impl Default for MeetingArchiveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: Some(30),
+            max_size_mb: Some(2048),
+            encoding: AudioEncoding::Opus,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MeetingArchiveState {
+    config: Mutex<Option<MeetingArchiveConfig>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct RetranscribeProgress<'a> {
+    meeting_id: &'a str,
+    done: usize,
+    total: usize,
+}
+
+fn config_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(app_data_dir.join(CONFIG_FILE_NAME))
+}
+
+fn archive_root(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join(ARCHIVE_DIR))
+}
+
+/// Current archive settings, read from disk on first use.
+pub fn get_config(app: &AppHandle) -> MeetingArchiveConfig {
+    let state = app.state::<MeetingArchiveState>();
+    let mut cached = state.config.lock().unwrap();
+    cached
+        .get_or_insert_with(|| {
+            config_path(app)
+                .ok()
+                .and_then(|path| fs::read_to_string(path).ok())
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_default()
+        })
+        .clone()
+}
+
+#[tauri::command]
+pub fn get_meeting_archive_config(app: AppHandle) -> Result<MeetingArchiveConfig, String> {
+    Ok(get_config(&app))
+}
+
+#[tauri::command]
+pub async fn set_meeting_archive_config(
+    app: AppHandle,
+    config: MeetingArchiveConfig,
+) -> Result<(), String> {
+    if config.retention_days == Some(0) {
+        return Err("Retention must be at least one day".to_string());
+    }
+    if config.max_size_mb == Some(0) {
+        return Err("Maximum archive size must be at least 1 MB".to_string());
+    }
+
+    let content = serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize archive config: {}", e))?;
+    fs::write(config_path(&app)?, content)
+        .map_err(|e| format!("Failed to write archive config: {}", e))?;
+    *app.state::<MeetingArchiveState>().config.lock().unwrap() = Some(config);
+
+    // A tighter policy applies right away
+    enforce_retention(&app).await
+}
+
+/// Starts archiving the utterances of `meeting_id` when the archive is
+/// enabled. The returned task ends once `stop_archive` has been called and
+/// the capture has stopped.
+pub(super) async fn start_archive(
+    app: &AppHandle,
+    meeting_id: &str,
+    started_at: i64,
+) -> Result<Option<JoinHandle<()>>, String> {
+    let config = get_config(app);
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    let dir = archive_root(app)?.join(meeting_id);
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create meeting audio directory: {}", e))?;
+    let pool = db::get_pool(app).await?;
+    sqlx::query("UPDATE meetings SET audio_path = ? WHERE id = ?")
+        .bind(dir.to_string_lossy().to_string())
+        .bind(meeting_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to link meeting audio: {}", e))?;
+
+    let (sender, mut receiver) = mpsc::unbounded_channel::<CapturedUtterance>();
+    *app.state::<crate::AudioState>()
+        .utterance_tap
+        .lock()
+        .map_err(|e| format!("Failed to acquire utterance tap lock: {}", e))? = Some(sender);
+
+    let meeting_id = meeting_id.to_string();
+    Ok(Some(tauri::async_runtime::spawn(async move {
+        let mut sequence = 0;
+        while let Some(utterance) = receiver.recv().await {
+            sequence += 1;
+            if let Err(e) = write_chunk(
+                &pool,
+                &meeting_id,
+                &dir,
+                config.encoding,
+                started_at,
+                sequence,
+                utterance,
+            )
+            .await
+            {
+                warn!("Failed to archive meeting audio: {}", e);
+            }
+        }
+    })))
+}
+
+/// Stops copying utterances to the archive.
+pub(super) fn stop_archive(app: &AppHandle) {
+    if let Ok(mut tap) = app.state::<crate::AudioState>().utterance_tap.lock() {
+        *tap = None;
+    }
+}
+
+async fn write_chunk(
+    pool: &SqlitePool,
+    meeting_id: &str,
+    dir: &Path,
+    encoding: AudioEncoding,
+    started_at: i64,
+    sequence: u32,
+    utterance: CapturedUtterance,
+) -> Result<(), String> {
+    let Some(source) = utterance.source else {
+        return Ok(());
+    };
+    if utterance.samples.is_empty() || utterance.sample_rate == 0 {
+        return Ok(());
+    }
+
+    let now = now_millis();
+    let duration_ms = utterance.samples.len() as i64 * 1000 / utterance.sample_rate as i64;
+    let offset_ms = (now - started_at - duration_ms).max(0);
+    let bytes = tauri::async_runtime::spawn_blocking(move || {
+        codec::encode(encoding, utterance.sample_rate, &utterance.samples)
+    })
+    .await
+    .map_err(|e| format!("Audio encoding panicked: {}", e))??;
+
+    let file_name = match utterance.speaker {
+        Some(speaker) => format!(
+            "{:05}-{}-{}.{}",
+            sequence,
+            source_name(source),
+            speaker,
+            encoding.extension()
+        ),
+        None => format!(
+            "{:05}-{}.{}",
+            sequence,
+            source_name(source),
+            encoding.extension()
+        ),
+    };
+    tokio::fs::write(dir.join(&file_name), &bytes)
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+
+    sqlx::query(
+        "INSERT INTO meeting_audio_chunks
+         (meeting_id, source, speaker, file_name, offset_ms, duration_ms, size_bytes, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(meeting_id)
+    .bind(source_name(source))
+    .bind(utterance.speaker.map(i64::from))
+    .bind(&file_name)
+    .bind(offset_ms)
+    .bind(duration_ms)
+    .bind(bytes.len() as i64)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save meeting audio chunk: {}", e))?;
+    Ok(())
+}
+
+/// Transcribes a meeting again from its archived audio and replaces its
+/// transcript. `model_path` picks an on-device Whisper model; without it the
+/// usual transcription providers are used. Returns the number of lines.
+#[tauri::command]
+pub async fn retranscribe_meeting(
+    app: AppHandle,
+    meeting_id: String,
+    model_path: Option<String>,
+    language: Option<String>,
+) -> Result<usize, String> {
+    if super::recorder::is_recording(&app, &meeting_id) {
+        return Err("Stop the recording before transcribing it again".to_string());
+    }
+
+    let pool = db::get_pool(&app).await?;
+    let meeting: Option<(Option<String>, i64)> =
+        sqlx::query_as("SELECT audio_path, created_at FROM meetings WHERE id = ?")
+            .bind(&meeting_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| format!("Failed to load meeting: {}", e))?;
+    let (audio_path, created_at) =
+        meeting.ok_or_else(|| format!("Meeting not found: {}", meeting_id))?;
+    let dir = audio_path
+        .map(PathBuf::from)
+        .ok_or_else(|| "This meeting has no archived audio".to_string())?;
+
+    let chunks: Vec<(String, Option<i64>, String, i64)> = sqlx::query_as(
+        "SELECT source, speaker, file_name, offset_ms FROM meeting_audio_chunks
+         WHERE meeting_id = ? ORDER BY offset_ms, id",
+    )
+    .bind(&meeting_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to load meeting audio: {}", e))?;
+    if chunks.is_empty() {
+        return Err("This meeting has no archived audio".to_string());
+    }
+
+    let local_config = model_path
+        .filter(|path| !path.trim().is_empty())
+        .map(|path| stt::LocalWhisperConfig {
+            enabled: true,
+            model_path: Some(path),
+            language,
+            ..stt::get_local_config(&app)
+        });
+
+    // Everything is transcribed before the old transcript is touched
+    let total = chunks.len();
+    let mut lines = Vec::with_capacity(total);
+    for (done, (source, speaker, file_name, offset_ms)) in chunks.into_iter().enumerate() {
+        let audio = tokio::fs::read(dir.join(&file_name))
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", file_name, e))?;
+        let text = match &local_config {
+            Some(config) => stt::transcribe_locally_with(&app, config, &audio).await?,
+            None => api::transcribe_audio_text(&app, &audio).await?,
+        };
+        if !text.trim().is_empty() {
+            lines.push((source, speaker, text, created_at + offset_ms));
+        }
+        let _ = app.emit(
+            "meeting-retranscribe-progress",
+            RetranscribeProgress {
+                meeting_id: &meeting_id,
+                done: done + 1,
+                total,
+            },
+        );
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    sqlx::query("DELETE FROM meeting_segments WHERE meeting_id = ?")
+        .bind(&meeting_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to clear meeting segments: {}", e))?;
+
+    let mut transcript = Vec::with_capacity(lines.len());
+    for (source, speaker, text, spoken_at) in &lines {
+        let text = text.trim();
+        transcript.push(transcript_line(
+            parse_source(source),
+            speaker.map(|s| s as u32),
+            text,
+        ));
+        sqlx::query(
+            "INSERT INTO meeting_segments (meeting_id, source, speaker, text, created_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&meeting_id)
+        .bind(source)
+        .bind(speaker)
+        .bind(text)
+        .bind(spoken_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save meeting segment: {}", e))?;
+    }
+
+    sqlx::query("UPDATE meetings SET transcript = ?, updated_at = ? WHERE id = ?")
+        .bind(transcript.join("\n"))
+        .bind(now_millis())
+        .bind(&meeting_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save meeting transcript: {}", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save meeting transcript: {}", e))?;
+
+    Ok(lines.len())
+}
+
+/// Deletes a meeting's archived audio, keeping its transcript.
+#[tauri::command]
+pub async fn delete_meeting_audio(app: AppHandle, meeting_id: String) -> Result<(), String> {
+    if super::recorder::is_recording(&app, &meeting_id) {
+        return Err("Stop the recording before deleting its audio".to_string());
+    }
+    let pool = db::get_pool(&app).await?;
+    delete_archive(&pool, &meeting_id, &archive_root(&app)?.join(&meeting_id)).await
+}
+
+/// Applies the retention policy: drops archives past `retention_days`, then
+/// the oldest ones while the total exceeds `max_size_mb`, then directories no
+/// meeting links to any more.
+pub async fn enforce_retention(app: &AppHandle) -> Result<(), String> {
+    let config = get_config(app);
+    let root = archive_root(app)?;
+    let pool = db::get_pool(app).await?;
+    let recording = super::recorder::active_meeting_id(app);
+
+    let archived: Vec<(String, String, i64)> = sqlx::query_as(
+        "SELECT id, audio_path, created_at FROM meetings
+         WHERE audio_path IS NOT NULL ORDER BY created_at",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to load archived meetings: {}", e))?;
+
+    let cutoff = config
+        .retention_days
+        .map(|days| now_millis() - days as i64 * DAY_MILLIS);
+    let mut kept = Vec::new();
+    for (meeting_id, audio_path, created_at) in archived {
+        let path = PathBuf::from(audio_path);
+        if recording.as_deref() == Some(meeting_id.as_str()) {
+            continue;
+        }
+        if cutoff.is_some_and(|cutoff| created_at < cutoff) {
+            delete_archive(&pool, &meeting_id, &path).await?;
+        } else {
+            let size = dir_size(&path);
+            kept.push((meeting_id, path, size));
+        }
+    }
+
+    if let Some(max_size_mb) = config.max_size_mb {
+        let max_bytes = max_size_mb * 1024 * 1024;
+        let mut total: u64 = kept.iter().map(|(_, _, size)| size).sum();
+        let mut oldest = std::mem::take(&mut kept).into_iter();
+        while total > max_bytes {
+            let Some((meeting_id, path, size)) = oldest.next() else {
+                break;
+            };
+            delete_archive(&pool, &meeting_id, &path).await?;
+            total -= size;
+        }
+        kept.extend(oldest);
+    }
+
+    // Left behind by deleted meetings
+    let linked: HashSet<String> = kept
+        .into_iter()
+        .map(|(meeting_id, _, _)| meeting_id)
+        .chain(recording)
+        .collect();
+    if let Ok(entries) = fs::read_dir(&root) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.path().is_dir() && !linked.contains(&name) {
+                if let Err(e) = fs::remove_dir_all(entry.path()) {
+                    warn!("Failed to remove orphaned meeting audio {}: {}", name, e);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn delete_archive(pool: &SqlitePool, meeting_id: &str, path: &Path) -> Result<(), String> {
+    match fs::remove_dir_all(path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("Failed to delete meeting audio: {}", e)),
+    }
+    sqlx::query("DELETE FROM meeting_audio_chunks WHERE meeting_id = ?")
+        .bind(meeting_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete meeting audio chunks: {}", e))?;
+    sqlx::query("UPDATE meetings SET audio_path = NULL WHERE id = ?")
+        .bind(meeting_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to unlink meeting audio: {}", e))?;
+    Ok(())
+}
+
+fn dir_size(path: &Path) -> u64 {
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.metadata().ok())
+                .filter(|metadata| metadata.is_file())
+                .map(|metadata| metadata.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
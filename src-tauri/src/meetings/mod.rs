@@ -0,0 +1,167 @@
+// Cloak meeting transcripts written from Rust. Rows are created by the
+// frontend, or by the recorder for meetings it records itself
+use crate::db;
+use crate::speaker::{speaker_label, AudioSource};
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+mod archive;
+mod recorder;
+mod summary;
+
+pub use archive::{
+    delete_meeting_audio, enforce_retention, get_meeting_archive_config, retranscribe_meeting,
+    set_meeting_archive_config, MeetingArchiveState,
+};
+pub use recorder::{
+    get_active_meeting, recover_interrupted_meetings, start_meeting, stop_meeting,
+    MeetingRecorderState,
+};
+pub use summary::{draft_follow_up_email, get_meeting_notes, summarize_meeting};
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MeetingSegment {
+    pub id: i64,
+    pub source: String,
+    pub speaker: Option<i64>,
+    pub label: String,
+    pub text: String,
+    pub created_at: i64,
+}
+
+/// Who said a line: `Me`, `Speaker 2` for a diarized remote voice, or `Them`.
+pub fn segment_label(source: AudioSource, speaker: Option<u32>) -> String {
+    match (source, speaker) {
+        (AudioSource::Them, Some(speaker)) => speaker_label(speaker),
+        _ => source.label().to_string(),
+    }
+}
+
+/// One transcript line, e.g. `Speaker 1: sounds good`.
+pub fn transcript_line(source: AudioSource, speaker: Option<u32>, text: &str) -> String {
+    format!("{}: {}", segment_label(source, speaker), text.trim())
+}
+
+fn source_name(source: AudioSource) -> &'static str {
+    match source {
+        AudioSource::Me => "me",
+        AudioSource::Them => "them",
+    }
+}
+
+fn parse_source(name: &str) -> AudioSource {
+    if name == source_name(AudioSource::Me) {
+        AudioSource::Me
+    } else {
+        AudioSource::Them
+    }
+}
+
+/// Stores a speaker-attributed segment and appends its line to the meeting's
+/// plain-text transcript. Returns the line.
+#[tauri::command]
+pub async fn append_meeting_transcript(
+    app: AppHandle,
+    meeting_id: String,
+    source: AudioSource,
+    speaker: Option<u32>,
+    text: String,
+) -> Result<String, String> {
+    append_line(&app, &meeting_id, source, speaker, &text).await
+}
+
+async fn append_line(
+    app: &AppHandle,
+    meeting_id: &str,
+    source: AudioSource,
+    speaker: Option<u32>,
+    text: &str,
+) -> Result<String, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Transcript text is empty".to_string());
+    }
+    let line = transcript_line(source, speaker, text);
+    let now = now_millis();
+
+    let pool = db::get_pool(app).await?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let result = sqlx::query(
+        "UPDATE meetings
+         SET transcript = CASE WHEN transcript = '' THEN ?1 ELSE transcript || char(10) || ?1 END,
+             updated_at = ?2
+         WHERE id = ?3",
+    )
+    .bind(&line)
+    .bind(now)
+    .bind(meeting_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to append meeting transcript: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err(format!("Meeting not found: {}", meeting_id));
+    }
+
+    sqlx::query(
+        "INSERT INTO meeting_segments (meeting_id, source, speaker, text, created_at)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(meeting_id)
+    .bind(source_name(source))
+    .bind(speaker.map(i64::from))
+    .bind(text)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save meeting segment: {}", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save meeting transcript: {}", e))?;
+    Ok(line)
+}
+
+/// Speaker-attributed transcript of a meeting, oldest first.
+#[tauri::command]
+pub async fn get_meeting_segments(
+    app: AppHandle,
+    meeting_id: String,
+) -> Result<Vec<MeetingSegment>, String> {
+    let pool = db::get_pool(&app).await?;
+    let rows: Vec<(i64, String, Option<i64>, String, i64)> = sqlx::query_as(
+        "SELECT id, source, speaker, text, created_at FROM meeting_segments
+         WHERE meeting_id = ? ORDER BY id",
+    )
+    .bind(&meeting_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to load meeting segments: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, source, speaker, text, created_at)| {
+            let label = segment_label(parse_source(&source), speaker.map(|s| s as u32));
+            MeetingSegment {
+                id,
+                source,
+                speaker,
+                label,
+                text,
+                created_at,
+            }
+        })
+        .collect())
+}
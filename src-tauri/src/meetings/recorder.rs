@@ -0,0 +1,546 @@
+// Cloak meeting recorder: owns a dual capture for the length of a meeting,
+// writes each utterance to the meeting as soon as it is transcribed and
+// summarizes the transcript once recording stops. Everything runs in Rust, so
+// a webview reload neither stops a meeting nor loses its lines.
+//
+// Status is `recording` while capturing, `processing` from stop until the
+// summary has been attempted, then `completed` whether or not it succeeded.
+// Recording into an existing meeting moves it back to `recording`; if the
+// capture fails to start, its previous status is restored. A crash leaves the
+// row in `recording` or `processing` with every line written so far; on the
+// next launch both are summarized and completed.
+use super::archive;
+use super::summary;
+use super::{append_line, now_millis, parse_source, segment_label};
+use crate::api;
+use crate::copilot;
+use crate::db;
+use crate::speaker::{self, AudioSource, SegmentStore};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, EventId, Listener, Manager};
+use tokio::sync::{mpsc, OwnedMutexGuard};
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+// Streaming providers send their last finals after the capture has stopped
+const DRAIN_GRACE: Duration = Duration::from_secs(3);
+
+#[derive(Default)]
+pub struct MeetingRecorderState {
+    active: Mutex<Option<ActiveMeeting>>,
+    // Held while a stopped meeting still listens for late transcripts; events
+    // carry no meeting, so the next one waits rather than sharing them
+    draining: Arc<tokio::sync::Mutex<()>>,
+}
+
+struct ActiveMeeting {
+    meeting_id: String,
+    started_at: i64,
+    // Dropping the listeners closes the queue, which ends the worker
+    listeners: Vec<EventId>,
+    worker: JoinHandle<()>,
+    // Writes the archived audio, when the archive is enabled
+    archive: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveMeetingInfo {
+    pub meeting_id: String,
+    pub started_at: i64,
+}
+
+// Utterances of the capture, in the order they were spoken
+enum Utterance {
+    // `speech-detected` segment still to be transcribed
+    Segment {
+        id: String,
+        mime_type: String,
+        source: AudioSource,
+        speaker: Option<u32>,
+    },
+    // `transcript-final` of a streaming provider
+    Text {
+        text: String,
+        source: AudioSource,
+        speaker: Option<u32>,
+    },
+}
+
+#[derive(Deserialize)]
+struct SegmentEvent {
+    id: String,
+    mime_type: String,
+    source: Option<AudioSource>,
+    speaker: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct TranscriptEvent {
+    text: String,
+    source: Option<AudioSource>,
+    speaker: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MeetingLine {
+    meeting_id: String,
+    line: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MeetingStatus<'a> {
+    meeting_id: &'a str,
+    status: &'a str,
+}
+
+// Payload of `meeting-segment-untranscribed`: Rust has no working STT for the
+// segment, so it stays in the store for a webview with its own provider
+#[derive(Debug, Clone, Serialize)]
+struct UntranscribedSegment {
+    meeting_id: String,
+    segment_id: String,
+    mime_type: String,
+    source: AudioSource,
+    speaker: Option<u32>,
+    error: String,
+}
+
+/// Starts recording a meeting: opens the microphone and system audio, and
+/// transcribes into `meeting_id`, or into a new meeting titled `title`.
+#[tauri::command]
+pub async fn start_meeting(
+    app: AppHandle,
+    meeting_id: Option<String>,
+    title: Option<String>,
+    microphone: Option<String>,
+    device_id: Option<String>,
+    application: Option<String>,
+) -> Result<ActiveMeetingInfo, String> {
+    if active_meeting(&app)?.is_some() {
+        return Err("A meeting is already being recorded".to_string());
+    }
+
+    let started_at = now_millis();
+    // Put back if the capture fails to start; a new meeting has nothing to summarize
+    let (meeting_id, previous_status) = match meeting_id.filter(|id| !id.trim().is_empty()) {
+        Some(id) => {
+            let previous_status = get_status(&app, &id).await?;
+            set_status(&app, &id, "recording").await?;
+            (id, previous_status)
+        }
+        None => (
+            create_meeting(&app, title, started_at).await?,
+            "completed".to_string(),
+        ),
+    };
+
+    // Recording goes ahead without an archive rather than failing
+    let archive = archive::start_archive(&app, &meeting_id, started_at)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to start meeting audio archive: {}", e);
+            None
+        });
+
+    let draining = app.state::<MeetingRecorderState>().draining.clone();
+    drop(draining.lock().await);
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let listeners = listen_for_utterances(&app, sender);
+    if let Err(e) =
+        speaker::start_dual_audio_capture(app.clone(), None, microphone, device_id, application)
+            .await
+    {
+        for listener in listeners {
+            app.unlisten(listener);
+        }
+        archive::stop_archive(&app);
+        if let Err(e) = set_status(&app, &meeting_id, &previous_status).await {
+            warn!("Failed to restore meeting status: {}", e);
+        }
+        return Err(e);
+    }
+
+    let worker = tauri::async_runtime::spawn(run_worker(
+        app.clone(),
+        meeting_id.clone(),
+        started_at,
+        receiver,
+    ));
+    {
+        let state = app.state::<MeetingRecorderState>();
+        let mut active = state
+            .active
+            .lock()
+            .map_err(|e| format!("Failed to acquire recorder lock: {}", e))?;
+        *active = Some(ActiveMeeting {
+            meeting_id: meeting_id.clone(),
+            started_at,
+            listeners,
+            worker,
+            archive,
+        });
+    }
+
+    emit_status(&app, &meeting_id, "recording");
+    Ok(ActiveMeetingInfo {
+        meeting_id,
+        started_at,
+    })
+}
+
+/// Stops the capture and marks the meeting `processing`. Late transcripts and
+/// the summary are finished in the background; `meeting-status` reports
+/// `completed` once they are saved.
+#[tauri::command]
+pub async fn stop_meeting(app: AppHandle) -> Result<String, String> {
+    // Taken first, so no new meeting starts before this one's drain is held
+    let draining = app
+        .state::<MeetingRecorderState>()
+        .draining
+        .clone()
+        .lock_owned()
+        .await;
+    let active = app
+        .state::<MeetingRecorderState>()
+        .active
+        .lock()
+        .map_err(|e| format!("Failed to acquire recorder lock: {}", e))?
+        .take()
+        .ok_or_else(|| "No meeting is being recorded".to_string())?;
+    let meeting_id = active.meeting_id.clone();
+
+    if let Err(e) = set_status(&app, &meeting_id, "processing").await {
+        warn!("Failed to mark meeting as processing: {}", e);
+    }
+    emit_status(&app, &meeting_id, "processing");
+    if let Err(e) = speaker::stop_system_audio_capture(app.clone()).await {
+        warn!("Failed to stop meeting capture: {}", e);
+    }
+    archive::stop_archive(&app);
+
+    tauri::async_runtime::spawn(finish_meeting(app, active, draining));
+    Ok(meeting_id)
+}
+
+/// The meeting being recorded, if any; lets a reloaded webview pick it up.
+#[tauri::command]
+pub fn get_active_meeting(app: AppHandle) -> Result<Option<ActiveMeetingInfo>, String> {
+    active_meeting(&app)
+}
+
+/// Finishes meetings a crash left in `recording` or `processing`. Their lines
+/// are already saved; only the summary is missing.
+pub async fn recover_interrupted_meetings(app: AppHandle) {
+    let result = async {
+        let pool = db::get_pool(&app).await?;
+        sqlx::query_scalar::<_, String>(
+            "UPDATE meetings SET status = 'processing', updated_at = ?
+             WHERE status IN ('recording', 'processing')
+             RETURNING id",
+        )
+        .bind(now_millis())
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to recover interrupted meetings: {}", e))
+    }
+    .await;
+
+    let meeting_ids = match result {
+        Ok(meeting_ids) => meeting_ids,
+        Err(e) => {
+            warn!("{}", e);
+            return;
+        }
+    };
+    if !meeting_ids.is_empty() {
+        warn!("Recovering {} interrupted meeting(s)", meeting_ids.len());
+    }
+    for meeting_id in meeting_ids {
+        emit_status(&app, &meeting_id, "processing");
+        if let Err(e) = update_participants(&app, &meeting_id).await {
+            warn!("{}", e);
+        }
+        if let Err(e) = summarize_and_complete(&app, &meeting_id).await {
+            warn!("{}", e);
+        }
+    }
+}
+
+pub(super) fn active_meeting_id(app: &AppHandle) -> Option<String> {
+    active_meeting(app)
+        .ok()
+        .flatten()
+        .map(|meeting| meeting.meeting_id)
+}
+
+pub(super) fn is_recording(app: &AppHandle, meeting_id: &str) -> bool {
+    active_meeting_id(app).as_deref() == Some(meeting_id)
+}
+
+fn active_meeting(app: &AppHandle) -> Result<Option<ActiveMeetingInfo>, String> {
+    let state = app.state::<MeetingRecorderState>();
+    let active = state
+        .active
+        .lock()
+        .map_err(|e| format!("Failed to acquire recorder lock: {}", e))?;
+    Ok(active.as_ref().map(|meeting| ActiveMeetingInfo {
+        meeting_id: meeting.meeting_id.clone(),
+        started_at: meeting.started_at,
+    }))
+}
+
+// Queues the dual capture's utterances; unlabelled events belong to other captures
+fn listen_for_utterances(
+    app: &AppHandle,
+    sender: mpsc::UnboundedSender<Utterance>,
+) -> Vec<EventId> {
+    let segments = sender.clone();
+    let segment_listener = app.listen("speech-detected", move |event| {
+        let Ok(segment) = serde_json::from_str::<SegmentEvent>(event.payload()) else {
+            return;
+        };
+        if let Some(source) = segment.source {
+            let _ = segments.send(Utterance::Segment {
+                id: segment.id,
+                mime_type: segment.mime_type,
+                source,
+                speaker: segment.speaker,
+            });
+        }
+    });
+
+    let transcript_listener = app.listen("transcript-final", move |event| {
+        let Ok(transcript) = serde_json::from_str::<TranscriptEvent>(event.payload()) else {
+            return;
+        };
+        if let Some(source) = transcript.source {
+            let _ = sender.send(Utterance::Text {
+                text: transcript.text,
+                source,
+                speaker: transcript.speaker,
+            });
+        }
+    });
+
+    vec![segment_listener, transcript_listener]
+}
+
+// Transcribes and saves utterances one at a time, so lines keep their order
+async fn run_worker(
+    app: AppHandle,
+    meeting_id: String,
+    started_at: i64,
+    mut receiver: mpsc::UnboundedReceiver<Utterance>,
+) {
+    while let Some(utterance) = receiver.recv().await {
+        let (text, source, speaker) = match utterance {
+            Utterance::Text {
+                text,
+                source,
+                speaker,
+            } => (text, source, speaker),
+            Utterance::Segment {
+                id,
+                mime_type,
+                source,
+                speaker,
+            } => match api::transcribe_segment_text(&app, &id).await {
+                Ok(text) => {
+                    app.state::<SegmentStore>().remove(&id);
+                    // Streamed lines reach the copilot on their own
+                    copilot::observe(&app, Some(source), speaker, &text);
+                    (text, source, speaker)
+                }
+                Err(e) => {
+                    let _ = app.emit(
+                        "meeting-segment-untranscribed",
+                        UntranscribedSegment {
+                            meeting_id: meeting_id.clone(),
+                            segment_id: id,
+                            mime_type,
+                            source,
+                            speaker,
+                            error: e,
+                        },
+                    );
+                    continue;
+                }
+            },
+        };
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        match append_line(&app, &meeting_id, source, speaker, &text).await {
+            Ok(line) => {
+                if let Err(e) = update_duration(&app, &meeting_id, started_at).await {
+                    warn!("{}", e);
+                }
+                let _ = app.emit(
+                    "meeting-transcript-line",
+                    MeetingLine {
+                        meeting_id: meeting_id.clone(),
+                        line,
+                    },
+                );
+            }
+            Err(e) => warn!("Failed to save meeting transcript line: {}", e),
+        }
+    }
+}
+
+async fn finish_meeting(app: AppHandle, active: ActiveMeeting, draining: OwnedMutexGuard<()>) {
+    tokio::time::sleep(DRAIN_GRACE).await;
+    for listener in active.listeners {
+        app.unlisten(listener);
+    }
+    drop(draining);
+    // Ends once the utterances still queued are saved
+    let _ = active.worker.await;
+    if let Some(archive) = active.archive {
+        let _ = archive.await;
+    }
+
+    let meeting_id = active.meeting_id;
+    if let Err(e) = update_duration(&app, &meeting_id, active.started_at).await {
+        warn!("{}", e);
+    }
+    if let Err(e) = update_participants(&app, &meeting_id).await {
+        warn!("{}", e);
+    }
+
+    if let Err(e) = summarize_and_complete(&app, &meeting_id).await {
+        // Couldn't mark it completed; it stays `processing` until the next
+        // launch recovers it
+        warn!("{}", e);
+        return;
+    }
+
+    if let Err(e) = archive::enforce_retention(&app).await {
+        warn!("Failed to apply meeting audio retention: {}", e);
+    }
+}
+
+// A failed summary still completes the meeting; it can be retried from the UI.
+// Only failing to save the status is an error
+async fn summarize_and_complete(app: &AppHandle, meeting_id: &str) -> Result<(), String> {
+    if let Err(e) = summary::run_summary_job(app, meeting_id).await {
+        warn!("Failed to summarize meeting {}: {}", meeting_id, e);
+    }
+    complete_meeting(app, meeting_id).await?;
+    emit_status(app, meeting_id, "completed");
+    Ok(())
+}
+
+async fn create_meeting(
+    app: &AppHandle,
+    title: Option<String>,
+    created_at: i64,
+) -> Result<String, String> {
+    let id = format!(
+        "meeting-{}-{}",
+        created_at,
+        &uuid::Uuid::new_v4().simple().to_string()[..7]
+    );
+    let title = title
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| "Meeting".to_string());
+
+    let pool = db::get_pool(app).await?;
+    sqlx::query(
+        "INSERT INTO meetings (id, title, status, created_at, updated_at)
+         VALUES (?, ?, 'recording', ?, ?)",
+    )
+    .bind(&id)
+    .bind(&title)
+    .bind(created_at)
+    .bind(created_at)
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("Failed to create meeting: {}", e))?;
+    Ok(id)
+}
+
+async fn get_status(app: &AppHandle, meeting_id: &str) -> Result<String, String> {
+    let pool = db::get_pool(app).await?;
+    sqlx::query_scalar("SELECT status FROM meetings WHERE id = ?")
+        .bind(meeting_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| format!("Failed to load meeting status: {}", e))?
+        .ok_or_else(|| format!("Meeting not found: {}", meeting_id))
+}
+
+async fn set_status(app: &AppHandle, meeting_id: &str, status: &str) -> Result<(), String> {
+    let pool = db::get_pool(app).await?;
+    let result = sqlx::query("UPDATE meetings SET status = ?, updated_at = ? WHERE id = ?")
+        .bind(status)
+        .bind(now_millis())
+        .bind(meeting_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to update meeting status: {}", e))?;
+    if result.rows_affected() == 0 {
+        return Err(format!("Meeting not found: {}", meeting_id));
+    }
+    Ok(())
+}
+
+pub(super) async fn complete_meeting(app: &AppHandle, meeting_id: &str) -> Result<(), String> {
+    let pool = db::get_pool(app).await?;
+    sqlx::query("UPDATE meetings SET status = 'completed', updated_at = ? WHERE id = ?")
+        .bind(now_millis())
+        .bind(meeting_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to complete meeting: {}", e))?;
+    Ok(())
+}
+
+async fn update_duration(app: &AppHandle, meeting_id: &str, started_at: i64) -> Result<(), String> {
+    let duration_seconds = (now_millis() - started_at).max(0) / 1000;
+    let pool = db::get_pool(app).await?;
+    sqlx::query("UPDATE meetings SET duration_seconds = ? WHERE id = ?")
+        .bind(duration_seconds)
+        .bind(meeting_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to update meeting duration: {}", e))?;
+    Ok(())
+}
+
+// Everyone heard in the meeting, e.g. `Me, Speaker 1, Speaker 2`
+async fn update_participants(app: &AppHandle, meeting_id: &str) -> Result<(), String> {
+    let pool = db::get_pool(app).await?;
+    let speakers: Vec<(String, Option<i64>)> = sqlx::query_as(
+        "SELECT DISTINCT source, speaker FROM meeting_segments
+         WHERE meeting_id = ? ORDER BY source, speaker",
+    )
+    .bind(meeting_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to load meeting speakers: {}", e))?;
+    if speakers.is_empty() {
+        return Ok(());
+    }
+
+    let participants = speakers
+        .into_iter()
+        .map(|(source, speaker)| segment_label(parse_source(&source), speaker.map(|s| s as u32)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    sqlx::query("UPDATE meetings SET participants = ? WHERE id = ?")
+        .bind(participants)
+        .bind(meeting_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to update meeting participants: {}", e))?;
+    Ok(())
+}
+
+fn emit_status(app: &AppHandle, meeting_id: &str, status: &str) {
+    let _ = app.emit("meeting-status", MeetingStatus { meeting_id, status });
+}
@@ -0,0 +1,355 @@
+// Cloak meeting summaries: turns a transcript into structured notes (summary,
+// decisions, action items with owners, open questions) and drafts follow-up
+// emails from them, using the selected chat model.
+//
+// Long transcripts are summarized map-reduce style: each chunk is summarized
+// on its own, then the partial notes are merged (in groups, if they are still
+// too long) into one. The notes are stored as JSON in `meetings.summary_json`
+// and rendered as markdown into `meetings.summary` for display.
+use super::now_millis;
+use crate::api;
+use crate::db;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+// About 6k tokens; leaves room for the prompt and the answer on small models
+const CHUNK_CHARS: usize = 24_000;
+
+const NOTES_SCHEMA: &str = r#"{
+  "summary": "2-4 sentences on what the meeting was about and where it landed",
+  "decisions": ["Each decision that was made"],
+  "action_items": [{ "task": "Concrete next step", "owner": "Who does it, or null", "due": "When, or null" }],
+  "open_questions": ["Each question left unanswered"]
+}"#;
+
+const NOTES_RULES: &str = "Rules:
+- Respond with the JSON object only, no markdown fences or commentary
+- Use empty arrays when there is nothing to report
+- Do NOT add information that isn't in the input
+- Keep each entry to 1-2 sentences max
+- Lines may start with a speaker label (\"Me\", \"Speaker 1\", \"Them\"); use these labels as owners and to attribute decisions";
+
+const EMAIL_SYSTEM_PROMPT: &str = "You write follow-up emails after meetings. Given the meeting notes as JSON, draft a short, friendly email to the participants that recaps the meeting, lists the decisions and the action items with their owners and due dates, and raises the open questions. Refer to speakers by their labels only if no names are known.
+
+Respond with a JSON object only, no markdown fences or commentary:
+{ \"subject\": \"Email subject\", \"body\": \"Plain text email body\" }";
+
+/// Structured notes for a meeting, stored in `meetings.summary_json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeetingNotes {
+    pub summary: String,
+    pub decisions: Vec<String>,
+    pub action_items: Vec<ActionItem>,
+    pub open_questions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionItem {
+    pub task: String,
+    pub owner: Option<String>,
+    pub due: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FollowUpEmail {
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Clone, Serialize)]
+struct SummaryProgress<'a> {
+    meeting_id: &'a str,
+    // "map" while chunks are summarized, then "reduce"
+    stage: &'a str,
+    done: usize,
+    total: usize,
+}
+
+/// Summarizes a meeting's transcript again and marks the meeting completed.
+#[tauri::command]
+pub async fn summarize_meeting(
+    app: AppHandle,
+    meeting_id: String,
+) -> Result<Option<MeetingNotes>, String> {
+    let notes = run_summary_job(&app, &meeting_id).await;
+    // Completed either way, like the recorder; the summary can be retried
+    super::recorder::complete_meeting(&app, &meeting_id).await?;
+    notes
+}
+
+#[tauri::command]
+pub async fn get_meeting_notes(
+    app: AppHandle,
+    meeting_id: String,
+) -> Result<Option<MeetingNotes>, String> {
+    load_notes(&app, &meeting_id).await
+}
+
+/// Drafts a follow-up email from the meeting's notes, summarizing the meeting
+/// first if it has none yet.
+#[tauri::command]
+pub async fn draft_follow_up_email(
+    app: AppHandle,
+    meeting_id: String,
+) -> Result<FollowUpEmail, String> {
+    let notes = match load_notes(&app, &meeting_id).await? {
+        Some(notes) => notes,
+        None => run_summary_job(&app, &meeting_id)
+            .await?
+            .ok_or_else(|| "Meeting has no transcript to summarize".to_string())?,
+    };
+
+    let pool = db::get_pool(&app).await?;
+    let (title, participants): (String, Option<String>) =
+        sqlx::query_as("SELECT title, participants FROM meetings WHERE id = ?")
+            .bind(&meeting_id)
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| format!("Failed to load meeting: {}", e))?;
+    let notes_json = serde_json::to_string_pretty(&notes)
+        .map_err(|e| format!("Failed to serialize meeting notes: {}", e))?;
+
+    let reply = api::complete_chat(
+        &app,
+        &format!("meeting-email-{}", meeting_id),
+        EMAIL_SYSTEM_PROMPT,
+        &format!(
+            "Meeting: {}\nParticipants: {}\n\nNotes:\n{}",
+            title,
+            participants.as_deref().unwrap_or("unknown"),
+            notes_json
+        ),
+    )
+    .await?;
+    parse_json(&reply)
+}
+
+/// Builds, stores and returns the notes for a meeting; `None` when its
+/// transcript is empty. Also writes the markdown rendering to `summary`.
+pub(super) async fn run_summary_job(
+    app: &AppHandle,
+    meeting_id: &str,
+) -> Result<Option<MeetingNotes>, String> {
+    let pool = db::get_pool(app).await?;
+    let (transcript,): (String,) = sqlx::query_as("SELECT transcript FROM meetings WHERE id = ?")
+        .bind(meeting_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| format!("Failed to load meeting transcript: {}", e))?;
+    if transcript.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let notes = summarize_transcript(app, meeting_id, &transcript).await?;
+    let notes_json = serde_json::to_string(&notes)
+        .map_err(|e| format!("Failed to serialize meeting notes: {}", e))?;
+    sqlx::query("UPDATE meetings SET summary = ?, summary_json = ?, updated_at = ? WHERE id = ?")
+        .bind(render_markdown(&notes))
+        .bind(notes_json)
+        .bind(now_millis())
+        .bind(meeting_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to save meeting summary: {}", e))?;
+    Ok(Some(notes))
+}
+
+async fn load_notes(app: &AppHandle, meeting_id: &str) -> Result<Option<MeetingNotes>, String> {
+    let pool = db::get_pool(app).await?;
+    let row: Option<(Option<String>,)> =
+        sqlx::query_as("SELECT summary_json FROM meetings WHERE id = ?")
+            .bind(meeting_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| format!("Failed to load meeting summary: {}", e))?;
+    let Some((notes_json,)) = row else {
+        return Err(format!("Meeting not found: {}", meeting_id));
+    };
+    notes_json
+        .map(|json| {
+            serde_json::from_str(&json).map_err(|e| format!("Invalid meeting summary: {}", e))
+        })
+        .transpose()
+}
+
+async fn summarize_transcript(
+    app: &AppHandle,
+    meeting_id: &str,
+    transcript: &str,
+) -> Result<MeetingNotes, String> {
+    let chunks = split_chunks(transcript.lines(), CHUNK_CHARS);
+    if chunks.len() == 1 {
+        return extract_notes(app, meeting_id, 0, &chunks[0], None).await;
+    }
+
+    // Map: notes for each chunk on its own
+    let total = chunks.len();
+    let mut partials = Vec::with_capacity(total);
+    for (index, chunk) in chunks.iter().enumerate() {
+        emit_progress(app, meeting_id, "map", index, total);
+        partials.push(extract_notes(app, meeting_id, index, chunk, Some((index, total))).await?);
+    }
+
+    // Reduce: merge the partial notes, in groups that fit a request
+    let mut round = 0;
+    while partials.len() > 1 {
+        let serialized = partials
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to serialize meeting notes: {}", e))?;
+        let groups = split_chunks(serialized.iter().map(String::as_str), CHUNK_CHARS);
+        // Every note on its own is over the limit; merge in pairs to make progress
+        let groups = if groups.len() == serialized.len() {
+            serialized.chunks(2).map(|pair| pair.join("\n")).collect()
+        } else {
+            groups
+        };
+
+        let total = groups.len();
+        let mut merged = Vec::with_capacity(total);
+        for (index, group) in groups.iter().enumerate() {
+            emit_progress(app, meeting_id, "reduce", index, total);
+            merged.push(merge_notes(app, meeting_id, round, index, group).await?);
+        }
+        partials = merged;
+        round += 1;
+    }
+    Ok(partials.pop().unwrap_or_default())
+}
+
+async fn extract_notes(
+    app: &AppHandle,
+    meeting_id: &str,
+    index: usize,
+    transcript: &str,
+    part: Option<(usize, usize)>,
+) -> Result<MeetingNotes, String> {
+    let scope = match part {
+        Some((index, total)) => format!(
+            "You are given part {} of {} of a meeting transcript. Take notes on this part only.",
+            index + 1,
+            total
+        ),
+        None => "You are given the transcript of a meeting or call.".to_string(),
+    };
+    let system_prompt = format!(
+        "You are a meeting summarizer. {} Respond with JSON in this shape:\n\n{}\n\n{}",
+        scope, NOTES_SCHEMA, NOTES_RULES
+    );
+    let reply = api::complete_chat(
+        app,
+        &format!("meeting-summary-{}-map-{}", meeting_id, index),
+        &system_prompt,
+        &format!(
+            "Here is the meeting transcript to summarize:\n\n{}",
+            transcript
+        ),
+    )
+    .await?;
+    parse_json(&reply)
+}
+
+async fn merge_notes(
+    app: &AppHandle,
+    meeting_id: &str,
+    round: usize,
+    index: usize,
+    notes: &str,
+) -> Result<MeetingNotes, String> {
+    let system_prompt = format!(
+        "You are a meeting summarizer. You are given notes taken on consecutive parts of one \
+         meeting, one JSON object per line, in order. Merge them into notes for the whole \
+         meeting: write one summary, drop duplicates, and keep later decisions over earlier \
+         ones they replace. Respond with JSON in this shape:\n\n{}\n\n{}",
+        NOTES_SCHEMA, NOTES_RULES
+    );
+    let reply = api::complete_chat(
+        app,
+        &format!("meeting-summary-{}-reduce-{}-{}", meeting_id, round, index),
+        &system_prompt,
+        notes,
+    )
+    .await?;
+    parse_json(&reply)
+}
+
+// Joins lines into chunks of at most `max_chars`; a longer line is its own chunk
+fn split_chunks<'a>(lines: impl Iterator<Item = &'a str>, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        if !current.is_empty() && current.len() + line.len() + 1 > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() || chunks.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+// Models wrap JSON in fences or prose now and then; parse the outermost object
+fn parse_json<T: DeserializeOwned>(reply: &str) -> Result<T, String> {
+    let start = reply.find('{');
+    let end = reply.rfind('}');
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &reply[start..=end],
+        _ => return Err("Model did not return JSON".to_string()),
+    };
+    serde_json::from_str(json).map_err(|e| format!("Failed to parse model response: {}", e))
+}
+
+fn render_markdown(notes: &MeetingNotes) -> String {
+    let mut markdown = String::new();
+    if !notes.summary.trim().is_empty() {
+        markdown.push_str(&format!("## Summary\n{}\n\n", notes.summary.trim()));
+    }
+    if !notes.decisions.is_empty() {
+        markdown.push_str("## Decisions\n");
+        for decision in &notes.decisions {
+            markdown.push_str(&format!("- {}\n", decision));
+        }
+        markdown.push('\n');
+    }
+    if !notes.action_items.is_empty() {
+        markdown.push_str("## Action Items\n");
+        for item in &notes.action_items {
+            markdown.push_str(&format!("- {}", item.task));
+            if let Some(owner) = item.owner.as_deref().filter(|o| !o.is_empty()) {
+                markdown.push_str(&format!(" — **{}**", owner));
+            }
+            if let Some(due) = item.due.as_deref().filter(|d| !d.is_empty()) {
+                markdown.push_str(&format!(" (due {})", due));
+            }
+            markdown.push('\n');
+        }
+        markdown.push('\n');
+    }
+    if !notes.open_questions.is_empty() {
+        markdown.push_str("## Open Questions\n");
+        for question in &notes.open_questions {
+            markdown.push_str(&format!("- {}\n", question));
+        }
+    }
+    markdown.trim_end().to_string()
+}
+
+fn emit_progress(app: &AppHandle, meeting_id: &str, stage: &str, done: usize, total: usize) {
+    let _ = app.emit(
+        "meeting-summary-progress",
+        SummaryProgress {
+            meeting_id,
+            stage,
+            done,
+            total,
+        },
+    );
+}
@@ -0,0 +1,183 @@
+// Anthropic Messages API format
+use super::{
+    content_blocks, message_role, push_merged, split_data_url, system_text, tool_call_arguments,
+    tool_call_name, tool_result_text, ChatProvider, StreamDelta, ToolSpec,
+};
+use serde_json::Value;
+
+pub struct AnthropicProvider;
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+const DEFAULT_MAX_TOKENS: u64 = 8192;
+
+// Converts OpenAI text/image_url blocks into Anthropic text/image blocks
+fn convert_block(block: &Value) -> Value {
+    if block.get("type").and_then(|t| t.as_str()) == Some("image_url") {
+        let url = block
+            .get("image_url")
+            .and_then(|u| u.get("url"))
+            .and_then(|u| u.as_str())
+            .unwrap_or("");
+        let (base64, media_type) = split_data_url(url);
+        serde_json::json!({
+            "type": "image",
+            "source": {
+                "type": "base64",
+                "media_type": media_type,
+                "data": base64
+            }
+        })
+    } else {
+        serde_json::json!({
+            "type": "text",
+            "text": block.get("text").and_then(|t| t.as_str()).unwrap_or("")
+        })
+    }
+}
+
+impl ChatProvider for AnthropicProvider {
+    fn name(&self) -> &'static str {
+        "Anthropic"
+    }
+
+    fn build_request_body(
+        &self,
+        model: &str,
+        messages: &[Value],
+        extra_body: &Value,
+        tools: &[ToolSpec],
+    ) -> Value {
+        // System is top-level; messages carry content as an array of blocks
+        let mut anthropic_messages: Vec<Value> = Vec::new();
+        for m in messages.iter().filter(|m| message_role(m) != "system") {
+            match message_role(m) {
+                // Tool results go back as `tool_result` blocks in a user turn
+                "tool" => {
+                    let tool_use_id = m.get("tool_call_id").and_then(|i| i.as_str()).unwrap_or("");
+                    let block = serde_json::json!({
+                        "type": "tool_result",
+                        "tool_use_id": tool_use_id,
+                        "content": tool_result_text(m)
+                    });
+                    push_merged(&mut anthropic_messages, "user", "content", vec![block]);
+                }
+                role => {
+                    let mut blocks: Vec<Value> = content_blocks(m)
+                        .iter()
+                        .filter(|b| {
+                            b.get("type").and_then(|t| t.as_str()) == Some("image_url")
+                                || b.get("text")
+                                    .and_then(|t| t.as_str())
+                                    .is_some_and(|t| !t.is_empty())
+                        })
+                        .map(convert_block)
+                        .collect();
+                    if let Some(tool_calls) = m.get("tool_calls").and_then(|t| t.as_array()) {
+                        for tool_call in tool_calls {
+                            blocks.push(serde_json::json!({
+                                "type": "tool_use",
+                                "id": tool_call.get("id").and_then(|i| i.as_str()).unwrap_or(""),
+                                "name": tool_call_name(tool_call),
+                                "input": tool_call_arguments(tool_call)
+                            }));
+                        }
+                    }
+                    push_merged(&mut anthropic_messages, role, "content", blocks);
+                }
+            }
+        }
+
+        let max_tokens = extra_body
+            .get("max_tokens")
+            .cloned()
+            .unwrap_or(serde_json::json!(DEFAULT_MAX_TOKENS));
+        let mut body = serde_json::json!({
+            "model": model,
+            "max_tokens": max_tokens,
+            "system": system_text(messages),
+            "messages": anthropic_messages,
+            "stream": true
+        });
+        if !tools.is_empty() {
+            let tool_defs: Vec<Value> = tools
+                .iter()
+                .map(|tool| {
+                    serde_json::json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "input_schema": tool.parameters
+                    })
+                })
+                .collect();
+            if let Some(req_obj) = body.as_object_mut() {
+                req_obj.insert("tools".to_string(), Value::Array(tool_defs));
+            }
+        }
+        body
+    }
+
+    fn auth_headers(&self, token: &str) -> Vec<(&'static str, String)> {
+        vec![
+            ("x-api-key", token.to_string()),
+            ("anthropic-version", ANTHROPIC_VERSION.to_string()),
+        ]
+    }
+
+    fn parse_deltas(&self, event: &Value) -> Vec<StreamDelta> {
+        let index = event.get("index").and_then(|i| i.as_u64()).unwrap_or(0) as usize;
+
+        match event.get("type").and_then(|t| t.as_str()) {
+            Some("content_block_start") => {
+                let block = match event.get("content_block") {
+                    Some(block) => block,
+                    None => return vec![],
+                };
+                if block.get("type").and_then(|t| t.as_str()) != Some("tool_use") {
+                    return vec![];
+                }
+                vec![StreamDelta::ToolCallStart {
+                    index,
+                    id: block
+                        .get("id")
+                        .and_then(|i| i.as_str())
+                        .unwrap_or("")
+                        .to_string(),
+                    name: block
+                        .get("name")
+                        .and_then(|n| n.as_str())
+                        .unwrap_or("")
+                        .to_string(),
+                }]
+            }
+            Some("content_block_delta") => {
+                let delta = match event.get("delta") {
+                    Some(delta) => delta,
+                    None => return vec![],
+                };
+                if let Some(fragment) = delta.get("partial_json").and_then(|p| p.as_str()) {
+                    if fragment.is_empty() {
+                        return vec![];
+                    }
+                    return vec![StreamDelta::ToolCallArguments {
+                        index,
+                        fragment: fragment.to_string(),
+                    }];
+                }
+                match delta.get("text").and_then(|t| t.as_str()) {
+                    Some(text) if !text.is_empty() => vec![StreamDelta::Text(text.to_string())],
+                    _ => vec![],
+                }
+            }
+            _ => vec![],
+        }
+    }
+
+    fn extract_usage(&self, event: &Value) -> Option<Value> {
+        // Input tokens arrive in `message_start`, output tokens in `message_delta`
+        match event.get("type").and_then(|t| t.as_str()) {
+            Some("message_start") => event.get("message")?.get("usage").cloned(),
+            Some("message_delta") => event.get("usage").cloned(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,169 @@
+// Google Gemini native `streamGenerateContent` format
+use super::{
+    content_blocks, message_role, push_merged, split_data_url, system_text, tool_call_arguments,
+    tool_call_name, tool_result_text, ChatProvider, StreamDelta, ToolSpec,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub struct GeminiProvider;
+
+// Converts OpenAI text/image_url blocks into Gemini parts
+fn convert_block(block: &Value) -> Option<Value> {
+    if block.get("type").and_then(|t| t.as_str()) == Some("image_url") {
+        let url = block
+            .get("image_url")
+            .and_then(|u| u.get("url"))
+            .and_then(|u| u.as_str())?;
+        let (data, mime_type) = split_data_url(url);
+        Some(serde_json::json!({
+            "inline_data": { "mime_type": mime_type, "data": data }
+        }))
+    } else {
+        let text = block.get("text").and_then(|t| t.as_str())?;
+        Some(serde_json::json!({ "text": text }))
+    }
+}
+
+impl ChatProvider for GeminiProvider {
+    fn name(&self) -> &'static str {
+        "Google"
+    }
+
+    fn stream_url(&self, base_url: &str, model: &str) -> String {
+        if base_url.contains(":streamGenerateContent") {
+            return base_url.to_string();
+        }
+        let model = model.trim_start_matches("models/");
+        format!(
+            "{}/{}:streamGenerateContent?alt=sse",
+            base_url.trim_end_matches('/'),
+            model
+        )
+    }
+
+    fn build_request_body(
+        &self,
+        _model: &str,
+        messages: &[Value],
+        extra_body: &Value,
+        tools: &[ToolSpec],
+    ) -> Value {
+        // Gemini answers tool calls by function name, not by call ID
+        let mut tool_names: HashMap<&str, &str> = HashMap::new();
+        let mut contents: Vec<Value> = Vec::new();
+
+        for m in messages.iter().filter(|m| message_role(m) != "system") {
+            if message_role(m) == "tool" {
+                let call_id = m.get("tool_call_id").and_then(|i| i.as_str()).unwrap_or("");
+                let part = serde_json::json!({
+                    "functionResponse": {
+                        "name": tool_names.get(call_id).copied().unwrap_or(""),
+                        "response": { "content": tool_result_text(m) }
+                    }
+                });
+                push_merged(&mut contents, "user", "parts", vec![part]);
+                continue;
+            }
+
+            let role = if message_role(m) == "assistant" {
+                "model"
+            } else {
+                "user"
+            };
+            let mut parts: Vec<Value> =
+                content_blocks(m).iter().filter_map(convert_block).collect();
+            if let Some(tool_calls) = m.get("tool_calls").and_then(|t| t.as_array()) {
+                for tool_call in tool_calls {
+                    let name = tool_call_name(tool_call);
+                    if let Some(id) = tool_call.get("id").and_then(|i| i.as_str()) {
+                        tool_names.insert(id, name);
+                    }
+                    parts.push(serde_json::json!({
+                        "functionCall": { "name": name, "args": tool_call_arguments(tool_call) }
+                    }));
+                }
+            }
+            // Gemini rejects turns without parts
+            push_merged(&mut contents, role, "parts", parts);
+        }
+
+        let mut body = serde_json::json!({ "contents": contents });
+        let system = system_text(messages);
+        if let Some(req_obj) = body.as_object_mut() {
+            if !system.is_empty() {
+                req_obj.insert(
+                    "systemInstruction".to_string(),
+                    serde_json::json!({ "parts": [{ "text": system }] }),
+                );
+            }
+            if !tools.is_empty() {
+                let declarations: Vec<Value> = tools
+                    .iter()
+                    .map(|tool| {
+                        serde_json::json!({
+                            "name": tool.name,
+                            "description": tool.description,
+                            "parameters": tool.parameters
+                        })
+                    })
+                    .collect();
+                req_obj.insert(
+                    "tools".to_string(),
+                    serde_json::json!([{ "functionDeclarations": declarations }]),
+                );
+            }
+            if let Some(extra_obj) = extra_body.as_object() {
+                for (key, value) in extra_obj.iter() {
+                    req_obj.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        body
+    }
+
+    fn auth_headers(&self, token: &str) -> Vec<(&'static str, String)> {
+        vec![("x-goog-api-key", token.to_string())]
+    }
+
+    fn parse_deltas(&self, event: &Value) -> Vec<StreamDelta> {
+        let parts = match event
+            .get("candidates")
+            .and_then(|c| c.as_array())
+            .and_then(|c| c.first())
+            .and_then(|c| c.get("content"))
+            .and_then(|c| c.get("parts"))
+            .and_then(|p| p.as_array())
+        {
+            Some(parts) => parts,
+            None => return vec![],
+        };
+
+        let mut deltas = Vec::new();
+        let mut text = String::new();
+        for part in parts {
+            if let Some(t) = part.get("text").and_then(|t| t.as_str()) {
+                text.push_str(t);
+            }
+            // Function calls arrive whole rather than streamed in fragments
+            if let Some(call) = part.get("functionCall") {
+                deltas.push(StreamDelta::ToolCall {
+                    name: call
+                        .get("name")
+                        .and_then(|n| n.as_str())
+                        .unwrap_or("")
+                        .to_string(),
+                    arguments: call.get("args").map(|a| a.to_string()).unwrap_or_default(),
+                });
+            }
+        }
+        if !text.is_empty() {
+            deltas.insert(0, StreamDelta::Text(text));
+        }
+        deltas
+    }
+
+    fn extract_usage(&self, event: &Value) -> Option<Value> {
+        event.get("usageMetadata").cloned()
+    }
+}
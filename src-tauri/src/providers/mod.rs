@@ -0,0 +1,331 @@
+// Cloak chat providers: one module per wire format, looked up by name.
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+mod anthropic;
+mod gemini;
+mod openai;
+
+pub use anthropic::AnthropicProvider;
+pub use gemini::GeminiProvider;
+pub use openai::OpenAiProvider;
+
+/// A tool the model may call, described by a JSON schema for its arguments.
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolSpec {
+    pub name: String,
+    pub description: String,
+    pub parameters: Value,
+}
+
+/// A complete tool call requested by the model. `arguments` is a JSON string.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: String,
+}
+
+/// One piece of information carried by an SSE event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamDelta {
+    Text(String),
+    /// Start of a tool call streamed in pieces (OpenAI, Anthropic)
+    ToolCallStart {
+        index: usize,
+        id: String,
+        name: String,
+    },
+    /// Fragment of a streamed tool call's JSON arguments
+    ToolCallArguments {
+        index: usize,
+        fragment: String,
+    },
+    /// A tool call delivered in one event (Gemini)
+    ToolCall {
+        name: String,
+        arguments: String,
+    },
+}
+
+/// A chat completion wire format.
+///
+/// `chat_stream_response` always builds messages in OpenAI format, including
+/// assistant `tool_calls` and `tool` result messages; each provider converts
+/// them into its own request body and interprets its own SSE payloads.
+pub trait ChatProvider: Send + Sync {
+    /// Registry name, matched against `Model.provider` (e.g. "OpenAI").
+    fn name(&self) -> &'static str;
+
+    /// URL to POST the streaming request to. Defaults to the configured URL.
+    fn stream_url(&self, base_url: &str, _model: &str) -> String {
+        base_url.to_string()
+    }
+
+    /// Builds the streaming request body from OpenAI-style messages.
+    /// `tools` is empty unless tool calling is enabled for the request.
+    fn build_request_body(
+        &self,
+        model: &str,
+        messages: &[Value],
+        extra_body: &Value,
+        tools: &[ToolSpec],
+    ) -> Value;
+
+    /// Headers used to authenticate with `token`.
+    fn auth_headers(&self, token: &str) -> Vec<(&'static str, String)>;
+
+    /// Returns true if an SSE `data:` payload marks the end of the stream.
+    fn is_done(&self, data: &str) -> bool {
+        data == "[DONE]"
+    }
+
+    /// Extracts text and tool call deltas from one parsed SSE event.
+    fn parse_deltas(&self, event: &Value) -> Vec<StreamDelta>;
+
+    /// Extracts token usage from one parsed SSE event, if any.
+    fn extract_usage(&self, event: &Value) -> Option<Value>;
+}
+
+static PROVIDERS: Lazy<Vec<Box<dyn ChatProvider>>> = Lazy::new(|| {
+    vec![
+        Box::new(OpenAiProvider),
+        Box::new(AnthropicProvider),
+        Box::new(GeminiProvider),
+    ]
+});
+
+/// Looks up a registered provider by name (case-insensitive).
+pub fn get_provider(name: &str) -> Option<&'static dyn ChatProvider> {
+    PROVIDERS
+        .iter()
+        .find(|p| p.name().eq_ignore_ascii_case(name))
+        .map(|p| p.as_ref())
+}
+
+/// Picks the wire format for a model's provider and configured endpoint.
+///
+/// OpenAI-compatible endpoints (`.../chat/completions`) always speak the OpenAI
+/// format, whoever hosts them. Unknown providers fall back to OpenAI as well.
+pub fn resolve_provider(provider: Option<&str>, url: &str) -> &'static dyn ChatProvider {
+    let openai: &'static dyn ChatProvider = &OpenAiProvider;
+    if url.trim_end_matches('/').ends_with("/chat/completions") {
+        return openai;
+    }
+    provider.and_then(get_provider).unwrap_or(openai)
+}
+
+/// Merges a newly reported usage object into the collected one.
+/// Later keys win, so providers that report usage across several events
+/// (Anthropic's `message_start` + `message_delta`) end up with the full picture.
+pub fn merge_usage(usage: &mut Option<Value>, collected: Value) {
+    match (usage.as_mut().and_then(|u| u.as_object_mut()), collected) {
+        (Some(existing), Value::Object(new_fields)) => {
+            for (key, value) in new_fields {
+                existing.insert(key, value);
+            }
+        }
+        (_, collected) => *usage = Some(collected),
+    }
+}
+
+/// Adds the usage of another request in the same chat stream (tool calling
+/// sends several), summing numeric fields.
+pub fn add_usage(total: &mut Option<Value>, turn: Value) {
+    match (total.as_mut().and_then(|u| u.as_object_mut()), turn) {
+        (Some(existing), Value::Object(turn_fields)) => {
+            for (key, value) in turn_fields {
+                let sum = match (existing.get(&key).and_then(|v| v.as_u64()), value.as_u64()) {
+                    (Some(a), Some(b)) => Value::from(a + b),
+                    _ => value,
+                };
+                existing.insert(key, sum);
+            }
+        }
+        (_, turn) => *total = Some(turn),
+    }
+}
+
+/// Assembles tool calls from the deltas of one streamed response.
+#[derive(Default)]
+pub struct ToolCallAccumulator {
+    calls: BTreeMap<usize, ToolCall>,
+}
+
+impl ToolCallAccumulator {
+    pub fn push(&mut self, delta: &StreamDelta) {
+        match delta {
+            StreamDelta::Text(_) => {}
+            StreamDelta::ToolCallStart { index, id, name } => {
+                let call = self.calls.entry(*index).or_default();
+                if !id.is_empty() {
+                    call.id = id.clone();
+                }
+                if !name.is_empty() {
+                    call.name = name.clone();
+                }
+            }
+            StreamDelta::ToolCallArguments { index, fragment } => {
+                self.calls
+                    .entry(*index)
+                    .or_default()
+                    .arguments
+                    .push_str(fragment);
+            }
+            StreamDelta::ToolCall { name, arguments } => {
+                let index = self.calls.keys().next_back().map_or(0, |i| i + 1);
+                self.calls.insert(
+                    index,
+                    ToolCall {
+                        id: String::new(),
+                        name: name.clone(),
+                        arguments: arguments.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Completed calls in stream order. Missing IDs are generated and empty
+    /// arguments become `{}`, so every call can be echoed back to the provider.
+    pub fn finish(self) -> Vec<ToolCall> {
+        self.calls
+            .into_values()
+            .filter(|call| !call.name.is_empty())
+            .map(|mut call| {
+                if call.id.is_empty() {
+                    call.id = format!("call_{}", uuid::Uuid::new_v4().simple());
+                }
+                if call.arguments.trim().is_empty() {
+                    call.arguments = "{}".to_string();
+                }
+                call
+            })
+            .collect()
+    }
+}
+
+/// Splits a byte stream into SSE `data:` payloads.
+///
+/// Bytes are buffered until a full line arrives, so multi-byte UTF-8 characters
+/// split across network chunks are decoded correctly.
+#[derive(Default)]
+pub struct SseBuffer {
+    buffer: Vec<u8>,
+}
+
+impl SseBuffer {
+    /// Feeds raw bytes and returns the `data:` payloads of all complete lines.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut payloads = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let trimmed_line = line.trim();
+
+            if let Some(data) = trimmed_line.strip_prefix("data:") {
+                let data = data.trim_start();
+                if !data.is_empty() {
+                    payloads.push(data.to_string());
+                }
+            }
+        }
+        payloads
+    }
+}
+
+/// Splits an OpenAI `image_url` data URL into (base64 data, media type).
+pub(crate) fn split_data_url(url: &str) -> (&str, &str) {
+    if let Some(b) = url.strip_prefix("data:image/png;base64,") {
+        (b, "image/png")
+    } else if let Some(b) = url.strip_prefix("data:image/jpeg;base64,") {
+        (b, "image/jpeg")
+    } else {
+        (url, "image/jpeg")
+    }
+}
+
+/// Returns the OpenAI-style content of a message as a list of blocks.
+/// Plain string content becomes a single text block.
+pub(crate) fn content_blocks(message: &Value) -> Vec<Value> {
+    match message.get("content") {
+        Some(Value::Array(blocks)) => blocks.clone(),
+        Some(Value::String(s)) => vec![serde_json::json!({ "type": "text", "text": s })],
+        _ => vec![],
+    }
+}
+
+pub(crate) fn message_role(message: &Value) -> &str {
+    message
+        .get("role")
+        .and_then(|r| r.as_str())
+        .unwrap_or("user")
+}
+
+/// Concatenated text of all system messages.
+pub(crate) fn system_text(messages: &[Value]) -> String {
+    messages
+        .iter()
+        .filter(|m| message_role(m) == "system")
+        .flat_map(content_blocks)
+        .filter_map(|b| b.get("text").and_then(|t| t.as_str()).map(String::from))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Parsed arguments of an OpenAI-style `tool_calls` entry (`function.arguments` is a JSON string).
+pub(crate) fn tool_call_arguments(tool_call: &Value) -> Value {
+    tool_call
+        .get("function")
+        .and_then(|f| f.get("arguments"))
+        .and_then(|a| a.as_str())
+        .and_then(|a| serde_json::from_str(a).ok())
+        .unwrap_or_else(|| serde_json::json!({}))
+}
+
+pub(crate) fn tool_call_name(tool_call: &Value) -> &str {
+    tool_call
+        .get("function")
+        .and_then(|f| f.get("name"))
+        .and_then(|n| n.as_str())
+        .unwrap_or("")
+}
+
+/// Text content of a `tool` result message.
+pub(crate) fn tool_result_text(message: &Value) -> String {
+    content_blocks(message)
+        .iter()
+        .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Appends a converted message, merging it into the previous one when both have
+/// the same role. Anthropic and Gemini expect tool results for one turn, and any
+/// images attached after them, to arrive as a single user turn.
+pub(crate) fn push_merged(
+    messages: &mut Vec<Value>,
+    role: &str,
+    parts_key: &str,
+    parts: Vec<Value>,
+) {
+    if parts.is_empty() {
+        return;
+    }
+    if let Some(last) = messages.last_mut() {
+        if last.get("role").and_then(|r| r.as_str()) == Some(role) {
+            if let Some(existing) = last.get_mut(parts_key).and_then(|p| p.as_array_mut()) {
+                existing.extend(parts);
+                return;
+            }
+        }
+    }
+    let mut message = serde_json::Map::new();
+    message.insert("role".to_string(), Value::String(role.to_string()));
+    message.insert(parts_key.to_string(), Value::Array(parts));
+    messages.push(Value::Object(message));
+}
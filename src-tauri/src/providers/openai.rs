@@ -0,0 +1,115 @@
+// OpenAI chat completions format (also used by every OpenAI-compatible endpoint)
+use super::{ChatProvider, StreamDelta, ToolSpec};
+use serde_json::Value;
+
+pub struct OpenAiProvider;
+
+impl ChatProvider for OpenAiProvider {
+    fn name(&self) -> &'static str {
+        "OpenAI"
+    }
+
+    fn build_request_body(
+        &self,
+        model: &str,
+        messages: &[Value],
+        extra_body: &Value,
+        tools: &[ToolSpec],
+    ) -> Value {
+        let mut body = serde_json::json!({
+            "model": model,
+            "messages": messages,
+            "stream": true
+        });
+        if let Some(req_obj) = body.as_object_mut() {
+            if !tools.is_empty() {
+                let tool_defs: Vec<Value> = tools
+                    .iter()
+                    .map(|tool| {
+                        serde_json::json!({
+                            "type": "function",
+                            "function": {
+                                "name": tool.name,
+                                "description": tool.description,
+                                "parameters": tool.parameters
+                            }
+                        })
+                    })
+                    .collect();
+                req_obj.insert("tools".to_string(), Value::Array(tool_defs));
+            }
+            if let Some(extra_obj) = extra_body.as_object() {
+                for (key, value) in extra_obj.iter() {
+                    req_obj.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        body
+    }
+
+    fn auth_headers(&self, token: &str) -> Vec<(&'static str, String)> {
+        // Local servers run without an API key
+        if token.is_empty() {
+            return vec![];
+        }
+        vec![("Authorization", format!("Bearer {}", token))]
+    }
+
+    fn parse_deltas(&self, event: &Value) -> Vec<StreamDelta> {
+        let mut deltas = Vec::new();
+        let delta = match event
+            .get("choices")
+            .and_then(|c| c.as_array())
+            .and_then(|c| c.first())
+            .and_then(|c| c.get("delta"))
+        {
+            Some(delta) => delta,
+            None => return deltas,
+        };
+
+        if let Some(content) = delta.get("content").and_then(|c| c.as_str()) {
+            if !content.is_empty() {
+                deltas.push(StreamDelta::Text(content.to_string()));
+            }
+        }
+
+        // Tool calls arrive as fragments keyed by `index`; only the first carries id and name
+        if let Some(tool_calls) = delta.get("tool_calls").and_then(|t| t.as_array()) {
+            for (position, tool_call) in tool_calls.iter().enumerate() {
+                let index = tool_call
+                    .get("index")
+                    .and_then(|i| i.as_u64())
+                    .map_or(position, |i| i as usize);
+                let id = tool_call.get("id").and_then(|i| i.as_str()).unwrap_or("");
+                let function = tool_call.get("function");
+                let name = function
+                    .and_then(|f| f.get("name"))
+                    .and_then(|n| n.as_str())
+                    .unwrap_or("");
+                if !id.is_empty() || !name.is_empty() {
+                    deltas.push(StreamDelta::ToolCallStart {
+                        index,
+                        id: id.to_string(),
+                        name: name.to_string(),
+                    });
+                }
+                if let Some(fragment) = function
+                    .and_then(|f| f.get("arguments"))
+                    .and_then(|a| a.as_str())
+                    .filter(|a| !a.is_empty())
+                {
+                    deltas.push(StreamDelta::ToolCallArguments {
+                        index,
+                        fragment: fragment.to_string(),
+                    });
+                }
+            }
+        }
+
+        deltas
+    }
+
+    fn extract_usage(&self, event: &Value) -> Option<Value> {
+        event.get("usage").filter(|u| !u.is_null()).cloned()
+    }
+}
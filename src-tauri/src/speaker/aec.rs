@@ -0,0 +1,330 @@
+// Cloak echo cancellation: on speakers, the microphone also hears the remote
+// party, so a dual capture would transcribe them twice. System audio is what
+// the speakers play, so it serves as the reference: an NLMS adaptive filter
+// learns the speaker-to-microphone echo path and subtracts its estimate from
+// the microphone, then a residual suppressor attenuates what the filter left,
+// in proportion to `strength`.
+//
+// Double talk is handled with two filters rather than a level detector, since
+// microphone and loopback gains are unknown: a background filter adapts all
+// the time, and the foreground filter that produces the output only takes its
+// weights once they cancel better. Adapting to the user's voice spoils the
+// background filter for a moment but never the output.
+//
+// Both sides must be at the same rate, and system audio has to reach us before
+// its echo reaches the microphone. The two devices start at different times and
+// loopback goes quiet with nothing playing, so the reference can also run far
+// ahead of the microphone, pairing it with audio older than its echo; that lead
+// is trimmed back so the echo stays within the filter's tail.
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+// Echo path the filter can model; capped in taps so high rates stay cheap
+const TAIL_MS: usize = 128;
+const MAX_TAPS: usize = 2048;
+const BLOCK_MS: usize = 10;
+// Microphone audio held back for its reference before it is processed without
+// one (loopback devices send nothing while nothing plays)
+const MAX_WAIT_MS: usize = 100;
+// System audio kept ahead of the microphone before the oldest is dropped
+const MAX_BACKLOG_MS: usize = 500;
+// Lead of the reference over the microphone left after alignment. Its smallest
+// value over `ALIGN_MS` is what the devices add; the rest is arrival jitter
+const MAX_LEAD_MS: usize = 10;
+const ALIGN_MS: usize = 500;
+// NLMS step size: faster convergence above, less misadjustment below
+const STEP_SIZE: f32 = 0.3;
+// Reference quieter than this (block RMS) counts as silence
+const REFERENCE_FLOOR: f32 = 1e-4;
+// Background error energy must be this much lower to replace the foreground
+const IMPROVEMENT_RATIO: f32 = 0.8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EchoCancellationConfig {
+    /// Cancel system audio out of the microphone during dual capture
+    pub enabled: bool,
+    /// 0-1: how hard echo left over by the filter is suppressed. Higher removes
+    /// more echo but can duck the user's words while both sides talk
+    pub strength: f32,
+}
+
+impl Default for EchoCancellationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            strength: 0.6,
+        }
+    }
+}
+
+/// System audio handed from the speaker side of a dual capture to the
+/// microphone's canceller.
+#[derive(Clone, Default)]
+pub struct EchoReference {
+    samples: Arc<Mutex<VecDeque<f32>>>,
+}
+
+/// Passes system audio through unchanged, copying it into the reference.
+pub struct ReferenceTap<S> {
+    inner: S,
+    reference: Option<EchoReference>,
+    max_backlog: usize,
+}
+
+impl<S: Stream<Item = f32> + Unpin> ReferenceTap<S> {
+    /// `reference` of `None` passes the stream through untouched.
+    pub fn new(inner: S, reference: Option<EchoReference>, sample_rate: u32) -> Self {
+        Self {
+            inner,
+            reference,
+            max_backlog: ms_to_samples(MAX_BACKLOG_MS, sample_rate),
+        }
+    }
+}
+
+impl<S: Stream<Item = f32> + Unpin> Stream for ReferenceTap<S> {
+    type Item = f32;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_next(cx);
+        if let (Poll::Ready(Some(sample)), Some(reference)) = (&poll, &this.reference) {
+            let mut samples = reference.samples.lock().unwrap();
+            samples.push_back(*sample);
+            // The microphone stalled; its echo of this audio is long gone
+            while samples.len() > this.max_backlog {
+                samples.pop_front();
+            }
+        }
+        poll
+    }
+}
+
+/// Microphone stream with the echo of the reference removed.
+pub struct EchoCanceller<S> {
+    inner: S,
+    canceller: Option<(NlmsCanceller, EchoReference)>,
+    block: usize,
+    max_wait: usize,
+    max_lead: usize,
+    align_blocks: usize,
+    // Blocks so far in the alignment window, and the smallest lead among them
+    aligned_blocks: usize,
+    min_lead: usize,
+    pending: VecDeque<f32>,
+    output: VecDeque<f32>,
+    finished: bool,
+}
+
+impl<S: Stream<Item = f32> + Unpin> EchoCanceller<S> {
+    /// `reference` of `None`, or a disabled config, passes the microphone
+    /// through untouched.
+    pub fn new(
+        inner: S,
+        reference: Option<EchoReference>,
+        config: &EchoCancellationConfig,
+        sample_rate: u32,
+    ) -> Self {
+        let canceller = reference
+            .filter(|_| config.enabled)
+            .map(|reference| (NlmsCanceller::new(config, sample_rate), reference));
+        Self {
+            inner,
+            canceller,
+            block: ms_to_samples(BLOCK_MS, sample_rate),
+            max_wait: ms_to_samples(MAX_WAIT_MS, sample_rate),
+            max_lead: ms_to_samples(MAX_LEAD_MS, sample_rate),
+            align_blocks: ALIGN_MS / BLOCK_MS,
+            aligned_blocks: 0,
+            min_lead: usize::MAX,
+            pending: VecDeque::new(),
+            output: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    // Cancels every full block whose reference has arrived, or that waited long
+    // enough; `flush` also takes the final partial block
+    fn process_pending(&mut self, flush: bool) {
+        let Some((canceller, reference)) = self.canceller.as_mut() else {
+            return;
+        };
+        while self.pending.len() >= self.block || (flush && !self.pending.is_empty()) {
+            let len = self.block.min(self.pending.len());
+            let mut far: Vec<f32> = {
+                let mut samples = reference.samples.lock().unwrap();
+                if samples.len() < len && self.pending.len() < self.max_wait && !flush {
+                    break;
+                }
+                let available = samples.len().min(len);
+                let far = samples.drain(..available).collect();
+
+                self.min_lead = self.min_lead.min(samples.len());
+                self.aligned_blocks += 1;
+                if self.aligned_blocks >= self.align_blocks {
+                    // The reference stayed ahead by more than jitter: skip it forward
+                    if self.min_lead > self.max_lead {
+                        samples.drain(..self.min_lead - self.max_lead);
+                    }
+                    self.aligned_blocks = 0;
+                    self.min_lead = usize::MAX;
+                }
+                far
+            };
+            far.resize(len, 0.0);
+            let near: Vec<f32> = self.pending.drain(..len).collect();
+            canceller.process(&near, &far, &mut self.output);
+        }
+    }
+}
+
+impl<S: Stream<Item = f32> + Unpin> Stream for EchoCanceller<S> {
+    type Item = f32;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.canceller.is_none() {
+            return Pin::new(&mut this.inner).poll_next(cx);
+        }
+        loop {
+            if let Some(sample) = this.output.pop_front() {
+                return Poll::Ready(Some(sample));
+            }
+            if this.finished {
+                return Poll::Ready(None);
+            }
+
+            match Pin::new(&mut this.inner).poll_next(cx) {
+                Poll::Ready(Some(sample)) => {
+                    this.pending.push_back(sample);
+                    this.process_pending(false);
+                }
+                Poll::Ready(None) => {
+                    this.process_pending(true);
+                    this.finished = true;
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+struct NlmsCanceller {
+    taps: usize,
+    // Produces the output
+    foreground: Vec<f32>,
+    // Always adapting; copied to the foreground when it does better
+    background: Vec<f32>,
+    // Reference history written twice, so the newest `taps` samples are always
+    // the contiguous slice starting at `pos`, newest first
+    history: Vec<f32>,
+    pos: usize,
+    energy: f32,
+    strength: f32,
+    gain: f32,
+}
+
+impl NlmsCanceller {
+    fn new(config: &EchoCancellationConfig, sample_rate: u32) -> Self {
+        let taps = ms_to_samples(TAIL_MS, sample_rate).min(MAX_TAPS);
+        Self {
+            taps,
+            foreground: vec![0.0; taps],
+            background: vec![0.0; taps],
+            history: vec![0.0; taps * 2],
+            pos: 0,
+            energy: 0.0,
+            strength: config.strength.clamp(0.0, 1.0),
+            gain: 1.0,
+        }
+    }
+
+    fn process(&mut self, near: &[f32], far: &[f32], output: &mut VecDeque<f32>) {
+        let far_rms = rms(far);
+        let adapt = far_rms > REFERENCE_FLOOR;
+
+        let mut cleaned = Vec::with_capacity(near.len());
+        let mut echo_energy = 0.0f32;
+        let mut near_energy = 0.0f32;
+        let mut foreground_error = 0.0f32;
+        let mut background_error = 0.0f32;
+        for (&d, &x) in near.iter().zip(far) {
+            self.pos = (self.pos + self.taps - 1) % self.taps;
+            let oldest = self.history[self.pos];
+            self.history[self.pos] = x;
+            self.history[self.pos + self.taps] = x;
+            self.energy = (self.energy + x * x - oldest * oldest).max(0.0);
+
+            let window = &self.history[self.pos..self.pos + self.taps];
+            let echo = dot(&self.foreground, window);
+            let error = d - echo;
+            let background = d - dot(&self.background, window);
+            if adapt {
+                let step = STEP_SIZE * background / (self.energy + 1e-6);
+                for (w, x) in self.background.iter_mut().zip(window) {
+                    *w += step * x;
+                }
+            }
+            echo_energy += echo * echo;
+            near_energy += d * d;
+            foreground_error += error * error;
+            background_error += background * background;
+            cleaned.push(error);
+        }
+
+        // Rounding drifts the running sum
+        self.energy = self.history[self.pos..self.pos + self.taps]
+            .iter()
+            .map(|x| x * x)
+            .sum();
+        if adapt {
+            if background_error < IMPROVEMENT_RATIO * foreground_error
+                && background_error < near_energy
+            {
+                self.foreground.copy_from_slice(&self.background);
+            } else if background_error > near_energy
+                || self.background.iter().any(|w| !w.is_finite())
+            {
+                // Learned the user's voice, or diverged: start from the foreground again
+                self.background.copy_from_slice(&self.foreground);
+            }
+        }
+
+        // Share of the microphone that is echo, by the filter's estimate
+        let echo_share = if far_rms > REFERENCE_FLOOR && near_energy > 0.0 {
+            (echo_energy / near_energy).min(1.0)
+        } else {
+            0.0
+        };
+        let target = 1.0 - self.strength * echo_share;
+        // Duck quickly, recover slowly, and ramp across the block without clicks
+        let smoothing = if target < self.gain { 0.5 } else { 0.2 };
+        let start = self.gain;
+        self.gain += (target - self.gain) * smoothing;
+        let len = cleaned.len().max(1) as f32;
+        for (i, sample) in cleaned.into_iter().enumerate() {
+            let gain = start + (self.gain - start) * (i + 1) as f32 / len;
+            output.push_back(sample * gain);
+        }
+    }
+}
+
+fn ms_to_samples(ms: usize, sample_rate: u32) -> usize {
+    (ms * sample_rate as usize / 1000).max(1)
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
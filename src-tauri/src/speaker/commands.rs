@@ -1,18 +1,33 @@
 // Cloak AI Speech Detection, and capture system audio (speaker output) as a stream of f32 samples.
-use crate::speaker::{AudioDevice, SpeakerInput};
+use crate::speaker::vad::calculate_audio_metrics;
+use crate::speaker::{
+    create_detector, speech_regions, AudioApplication, AudioDevice, AudioSource, BoxedAudioStream,
+    Denoiser, DiarizationConfig, Diarizer, DspStream, EchoCancellationConfig, EchoCanceller,
+    EchoReference, MicrophoneInput, NoiseSuppressionConfig, ReferenceTap, SegmentStore,
+    SileroVadConfig, SpeakerInput, SpeechRegion, StreamSwitch, SwitchableStream, VadEngine,
+    VoiceActivityDetector, DEFAULT_TARGET_RATE,
+};
+use crate::stt::codec::{self, AudioEncoding};
+use crate::stt::StreamingSession;
 use anyhow::Result;
-use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
-use futures_util::StreamExt;
-use hound::{WavSpec, WavWriter};
+use futures_util::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
-use std::io::Cursor;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Listener, Manager};
 use tauri_plugin_shell::ShellExt;
-use tracing::{error, warn};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tracing::{error, info, warn};
+
+// Device changes arrive in bursts (a headset adds a sink, a source and a new
+// default at once) and are handled together once they settle
+const DEVICE_SETTLE: Duration = Duration::from_millis(500);
+
+// hop_size and the *_chunks counts are tuned at this rate and scaled to the
+// capture rate, so chunk counts keep their duration
+const REFERENCE_RATE: usize = 44100;
 
 // VAD Configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +41,30 @@ pub struct VadConfig {
     pub pre_speech_chunks: usize,
     pub noise_gate_threshold: f32,
     pub max_recording_duration_secs: u64,
+    // Speech detector; `energy` uses sensitivity_rms and peak_threshold
+    #[serde(default)]
+    pub engine: VadEngine,
+    #[serde(default)]
+    pub silero: SileroVadConfig,
+    // Rate every capture is resampled to; None keeps the device rate
+    #[serde(default = "default_target_sample_rate")]
+    pub target_sample_rate: Option<u32>,
+    // Format of the clips in `speech-detected`
+    #[serde(default)]
+    pub encoding: AudioEncoding,
+    // Removes system audio picked up by the microphone in dual capture
+    #[serde(default)]
+    pub echo_cancellation: EchoCancellationConfig,
+    // Spectral denoising ahead of the noise gate and VAD, per source
+    #[serde(default)]
+    pub noise_suppression: NoiseSuppressionConfig,
+    // Moves a running capture to the new default device when it changes
+    #[serde(default)]
+    pub follow_default_device: bool,
+}
+
+fn default_target_sample_rate() -> Option<u32> {
+    Some(DEFAULT_TARGET_RATE)
 }
 
 impl Default for VadConfig {
@@ -40,6 +79,13 @@ impl Default for VadConfig {
             pre_speech_chunks: 12,  // ~0.27s - enough to catch word start
             noise_gate_threshold: 0.003, // Stronger noise filtering
             max_recording_duration_secs: 180, // 3 minutes default
+            engine: VadEngine::Energy,
+            silero: SileroVadConfig::default(),
+            target_sample_rate: default_target_sample_rate(),
+            encoding: AudioEncoding::Wav,
+            echo_cancellation: EchoCancellationConfig::default(),
+            noise_suppression: NoiseSuppressionConfig::default(),
+            follow_default_device: false,
         }
     }
 }
@@ -49,6 +95,7 @@ pub async fn start_system_audio_capture(
     app: AppHandle,
     vad_config: Option<VadConfig>,
     device_id: Option<String>,
+    application: Option<String>,
 ) -> Result<(), String> {
     let state = app.state::<crate::AudioState>();
 
@@ -74,12 +121,20 @@ pub async fn start_system_audio_capture(
         *vad_cfg = config;
     }
 
-    let input = SpeakerInput::new_with_device(device_id).map_err(|e| {
-        error!("Failed to create speaker input: {}", e);
-        format!("Failed to access system audio: {}", e)
-    })?;
+    let vad_config = state
+        .vad_config
+        .lock()
+        .map_err(|e| format!("Failed to read VAD config: {}", e))?
+        .clone();
 
-    let stream = input.stream();
+    // Only a capture of the default device follows it; a device the user picked
+    // or a single application stays put
+    let follows = vad_config.follow_default_device && device_id.is_none() && application.is_none();
+    let input = open_speaker_input(device_id, application)?;
+
+    let speaker_stream = input.stream();
+    let (device_sr, channels) = (speaker_stream.sample_rate(), speaker_stream.channels());
+    let stream = process_stream(speaker_stream, device_sr, channels, &vad_config)?;
     let sr = stream.sample_rate();
 
     // Validate sample rate
@@ -91,12 +146,31 @@ pub async fn start_system_audio_capture(
         ));
     }
 
+    // Swappable so the capture can move to a new default device
+    let stream = SwitchableStream::new(Box::pin(stream));
+    let follower = follows.then(|| DeviceFollower {
+        config: vad_config.clone(),
+        speaker: Some(FollowedDevice {
+            switch: stream.switch(),
+            sample_rate: sr,
+        }),
+        microphone: None,
+    });
+
     let app_clone = app.clone();
-    let vad_config = state
-        .vad_config
-        .lock()
-        .map_err(|e| format!("Failed to read VAD config: {}", e))?
-        .clone();
+
+    // Streaming transcription replaces per-utterance WAV segments in VAD mode
+    let stt_session = if vad_config.enabled {
+        open_stt_session(&app, sr, None).await
+    } else {
+        None
+    };
+    let diarizer = create_diarizer(&app, sr)?;
+    let detector = if vad_config.enabled {
+        Some(create_detector(&app, &vad_config, sr)?)
+    } else {
+        None
+    };
 
     // Mark as capturing BEFORE spawning task
     *state
@@ -106,13 +180,22 @@ pub async fn start_system_audio_capture(
 
     println!("Starting system audio capture at {} Hz", sr);
 
+    set_device_follower(&app, follower);
+
     // Emit capture started event
     let _ = app_clone.emit("capture-started", sr);
 
     let state_clone = app.state::<crate::AudioState>();
     let task = tokio::spawn(async move {
-        if vad_config.enabled {
-            run_vad_capture(app_clone.clone(), stream, sr, vad_config).await;
+        if let Some(detector) = detector {
+            let pipeline = CapturePipeline {
+                detector,
+                stt: stt_session,
+                source: None,
+                diarizer,
+                denoiser: create_denoiser(&vad_config, None, sr),
+            };
+            run_vad_capture(app_clone.clone(), stream, sr, vad_config, pipeline).await;
         } else {
             run_continuous_capture(app_clone.clone(), stream, sr, vad_config).await;
         }
@@ -123,6 +206,7 @@ pub async fn start_system_audio_capture(
                 *guard = None;
             };
         }
+        set_device_follower(&app_clone, None);
     });
 
     *state_clone
@@ -133,13 +217,353 @@ pub async fn start_system_audio_capture(
     Ok(())
 }
 
+// Microphone ("me") and system audio ("them") captured together, each with its
+// own VAD so crosstalk is split into separately labelled utterances
+#[tauri::command]
+pub async fn start_dual_audio_capture(
+    app: AppHandle,
+    vad_config: Option<VadConfig>,
+    microphone: Option<String>,
+    device_id: Option<String>,
+    application: Option<String>,
+) -> Result<(), String> {
+    let state = app.state::<crate::AudioState>();
+
+    {
+        let guard = state
+            .stream_task
+            .lock()
+            .map_err(|e| format!("Failed to acquire lock: {}", e))?;
+
+        if guard.is_some() {
+            warn!("Capture already running");
+            return Err("Capture already running".to_string());
+        }
+    }
+
+    if let Some(config) = vad_config {
+        let mut vad_cfg = state
+            .vad_config
+            .lock()
+            .map_err(|e| format!("Failed to acquire VAD config lock: {}", e))?;
+        *vad_cfg = config;
+    }
+
+    // Utterance boundaries are what separate the speakers, so VAD is always on
+    let mut vad_config = state
+        .vad_config
+        .lock()
+        .map_err(|e| format!("Failed to read VAD config: {}", e))?
+        .clone();
+    vad_config.enabled = true;
+
+    // Each side follows the default only when it was opened as the default
+    let speaker_follows =
+        vad_config.follow_default_device && device_id.is_none() && application.is_none();
+    let microphone_follows = vad_config.follow_default_device && microphone.is_none();
+    let microphone = MicrophoneInput::new(microphone).stream().map_err(|e| {
+        error!("Failed to create microphone input: {}", e);
+        format!("Failed to access microphone: {}", e)
+    })?;
+    let speaker = open_speaker_input(device_id, application)?.stream();
+
+    // The microphone is already mono
+    let device_sr = microphone.sample_rate();
+    let mic_stream = process_stream(microphone, device_sr, 1, &vad_config)?;
+    let (device_sr, channels) = (speaker.sample_rate(), speaker.channels());
+    let speaker_stream = process_stream(speaker, device_sr, channels, &vad_config)?;
+
+    let mic_sr = mic_stream.sample_rate();
+    let speaker_sr = speaker_stream.sample_rate();
+    for sr in [mic_sr, speaker_sr] {
+        if !(8000..=96000).contains(&sr) {
+            error!("Invalid sample rate: {}", sr);
+            return Err(format!(
+                "Invalid sample rate: {}. Expected 8000-96000 Hz",
+                sr
+            ));
+        }
+    }
+
+    let mic_stream = SwitchableStream::new(Box::pin(mic_stream));
+    let speaker_stream = SwitchableStream::new(Box::pin(speaker_stream));
+    let follower = (speaker_follows || microphone_follows).then(|| DeviceFollower {
+        config: vad_config.clone(),
+        speaker: speaker_follows.then(|| FollowedDevice {
+            switch: speaker_stream.switch(),
+            sample_rate: speaker_sr,
+        }),
+        microphone: microphone_follows.then(|| FollowedDevice {
+            switch: mic_stream.switch(),
+            sample_rate: mic_sr,
+        }),
+    });
+
+    // System audio is the echo reference for the microphone, at the same rate
+    let echo_reference = if mic_sr == speaker_sr {
+        Some(EchoReference::default())
+    } else {
+        if vad_config.echo_cancellation.enabled {
+            warn!(
+                "Echo cancellation off: microphone at {} Hz, system audio at {} Hz",
+                mic_sr, speaker_sr
+            );
+        }
+        None
+    };
+    let speaker_stream = ReferenceTap::new(
+        speaker_stream,
+        echo_reference
+            .clone()
+            .filter(|_| vad_config.echo_cancellation.enabled),
+        speaker_sr,
+    );
+    let mic_stream = EchoCanceller::new(
+        mic_stream,
+        echo_reference,
+        &vad_config.echo_cancellation,
+        mic_sr,
+    );
+
+    let mic_stt = open_stt_session(&app, mic_sr, Some(AudioSource::Me)).await;
+    let speaker_stt = open_stt_session(&app, speaker_sr, Some(AudioSource::Them)).await;
+    // Only system audio mixes several voices; the microphone is always "me"
+    let diarizer = create_diarizer(&app, speaker_sr)?;
+    let mic_pipeline = CapturePipeline {
+        detector: create_detector(&app, &vad_config, mic_sr)?,
+        stt: mic_stt,
+        source: Some(AudioSource::Me),
+        diarizer: None,
+        denoiser: create_denoiser(&vad_config, Some(AudioSource::Me), mic_sr),
+    };
+    let speaker_pipeline = CapturePipeline {
+        detector: create_detector(&app, &vad_config, speaker_sr)?,
+        stt: speaker_stt,
+        source: Some(AudioSource::Them),
+        diarizer,
+        denoiser: create_denoiser(&vad_config, Some(AudioSource::Them), speaker_sr),
+    };
+
+    *state
+        .is_capturing
+        .lock()
+        .map_err(|e| format!("Failed to set capturing state: {}", e))? = true;
+
+    set_device_follower(&app, follower);
+
+    info!(
+        "Starting dual capture: microphone at {} Hz, system audio at {} Hz",
+        mic_sr, speaker_sr
+    );
+    let _ = app.emit("capture-started", speaker_sr);
+
+    let app_clone = app.clone();
+    let task = tokio::spawn(async move {
+        tokio::join!(
+            run_vad_capture(
+                app_clone.clone(),
+                mic_stream,
+                mic_sr,
+                vad_config.clone(),
+                mic_pipeline,
+            ),
+            run_vad_capture(
+                app_clone.clone(),
+                speaker_stream,
+                speaker_sr,
+                vad_config,
+                speaker_pipeline,
+            ),
+        );
+
+        let state = app_clone.state::<crate::AudioState>();
+        if let Ok(mut guard) = state.stream_task.lock() {
+            *guard = None;
+        };
+        set_device_follower(&app_clone, None);
+    });
+
+    *state
+        .stream_task
+        .lock()
+        .map_err(|e| format!("Failed to store task: {}", e))? = Some(task);
+
+    Ok(())
+}
+
+// Falls back to WAV segments when the streaming provider can't be reached
+async fn open_stt_session(
+    app: &AppHandle,
+    sr: u32,
+    source: Option<AudioSource>,
+) -> Option<StreamingSession> {
+    match crate::stt::start_session(app, sr, source).await {
+        Ok(session) => session,
+        Err(e) => {
+            warn!("Streaming transcription unavailable, using WAV segments: {}", e);
+            let _ = app.emit("streaming-stt-error", e);
+            None
+        }
+    }
+}
+
+// Shared DSP stage: mono at the configured capture rate from here on
+fn process_stream<S: Stream<Item = f32> + Unpin>(
+    stream: S,
+    sample_rate: u32,
+    channels: u16,
+    config: &VadConfig,
+) -> Result<DspStream<S>, String> {
+    DspStream::new(stream, sample_rate, channels, config.target_sample_rate).map_err(|e| {
+        error!("Failed to set up audio processing: {}", e);
+        e
+    })
+}
+
+// Chunk size at `sr` lasting as long as `hop_size` samples at the reference rate
+fn scaled_hop_size(hop_size: usize, sr: u32) -> usize {
+    (hop_size * sr as usize / REFERENCE_RATE).max(64)
+}
+
+fn create_diarizer(app: &AppHandle, sr: u32) -> Result<Option<Diarizer>, String> {
+    let config = app
+        .state::<crate::AudioState>()
+        .diarization_config
+        .lock()
+        .map_err(|e| format!("Failed to read diarization config: {}", e))?
+        .clone();
+    Ok(config.enabled.then(|| Diarizer::new(config, sr)))
+}
+
+// Payload of `speech-detected`: an utterance kept in the `SegmentStore`.
+// Fetch it with `get_speech_segment` or transcribe it with `transcribe_segment`
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeechSegment {
+    pub id: String,
+    pub mime_type: &'static str,
+    pub duration_secs: f32,
+    // Side of a dual capture
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<AudioSource>,
+    // Diarized speaker number (Speaker 1, 2, ...)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<u32>,
+}
+
+/// A finished utterance of a capture, as raw mono samples.
+pub struct CapturedUtterance {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    pub source: Option<AudioSource>,
+    pub speaker: Option<u32>,
+}
+
+// Receiver of every utterance, when something (e.g. the meeting archive) has
+// subscribed through `AudioState::utterance_tap`
+fn utterance_tap(app: &AppHandle) -> Option<UnboundedSender<CapturedUtterance>> {
+    app.state::<crate::AudioState>()
+        .utterance_tap
+        .lock()
+        .ok()
+        .and_then(|tap| tap.clone())
+}
+
+fn send_to_tap(
+    tap: &Option<UnboundedSender<CapturedUtterance>>,
+    samples: &[f32],
+    sample_rate: u32,
+    source: Option<AudioSource>,
+    speaker: Option<u32>,
+) {
+    if let Some(tap) = tap {
+        let _ = tap.send(CapturedUtterance {
+            samples: samples.to_vec(),
+            sample_rate,
+            source,
+            speaker,
+        });
+    }
+}
+
+// System audio of `device_id`, or only what `application` plays
+fn open_speaker_input(
+    device_id: Option<String>,
+    application: Option<String>,
+) -> Result<SpeakerInput, String> {
+    match application {
+        Some(application) => SpeakerInput::new_for_application(application),
+        None => SpeakerInput::new_with_device(device_id),
+    }
+    .map_err(|e| {
+        error!("Failed to create speaker input: {}", e);
+        format!("Failed to access system audio: {}", e)
+    })
+}
+
+// Per-stream state of a VAD capture: single capture has one, dual capture
+// one per side
+struct CapturePipeline {
+    detector: Box<dyn VoiceActivityDetector>,
+    stt: Option<StreamingSession>,
+    source: Option<AudioSource>,
+    diarizer: Option<Diarizer>,
+    denoiser: Option<Denoiser>,
+}
+
+fn create_denoiser(config: &VadConfig, source: Option<AudioSource>, sr: u32) -> Option<Denoiser> {
+    config
+        .noise_suppression
+        .enabled_for(source)
+        .then(|| Denoiser::new(sr))
+}
+
+// Forwards speech to the streaming session, if there is one
+fn stream_speech(stt: &mut Option<StreamingSession>, samples: &[f32]) {
+    if let Some(Err(e)) = stt.as_mut().map(|session| session.send_audio(samples)) {
+        drop_closed_session(stt, &e);
+    }
+}
+
+// Commits the utterance to the streaming session; false when there is no
+// session left to transcribe it
+fn commit_speech(stt: &mut Option<StreamingSession>, speaker: Option<u32>) -> bool {
+    match stt.as_ref().map(|session| session.commit(speaker)) {
+        Some(Ok(())) => true,
+        Some(Err(e)) => {
+            drop_closed_session(stt, &e);
+            false
+        }
+        None => false,
+    }
+}
+
+// The provider ended the session (already reported as `streaming-stt-error`).
+// The rest of the capture falls back to WAV segments, starting with the
+// utterance in progress, which is still in the speech buffer
+fn drop_closed_session(stt: &mut Option<StreamingSession>, error: &str) {
+    warn!("{}, using WAV segments", error);
+    *stt = None;
+}
+
 // VAD-enabled capture - OPTIMIZED for real-time speech detection
+// With a streaming session, speech is forwarded as it is captured and each
+// utterance is committed instead of being emitted as a WAV segment
 async fn run_vad_capture(
     app: AppHandle,
     stream: impl StreamExt<Item = f32> + Unpin,
     sr: u32,
     config: VadConfig,
+    pipeline: CapturePipeline,
 ) {
+    let CapturePipeline {
+        mut detector,
+        mut stt,
+        source,
+        mut diarizer,
+        mut denoiser,
+    } = pipeline;
+    let mut config = config;
+    config.hop_size = scaled_hop_size(config.hop_size, sr);
     let mut stream = stream;
     let mut buffer: VecDeque<f32> = VecDeque::new();
     let mut pre_speech: VecDeque<f32> =
@@ -150,6 +574,7 @@ async fn run_vad_capture(
     let mut speech_chunks = 0;
     let mut chunk_count = 0;
     let max_samples = sr as usize * 30; // 30s safety cap per utterance
+    let tap = utterance_tap(&app);
 
     while let Some(sample) = stream.next().await {
         buffer.push_back(sample);
@@ -163,18 +588,28 @@ async fn run_vad_capture(
                 }
             }
 
+            // Levels before suppression, so the UI can show what was removed
+            let (rms_before, peak_before) = calculate_audio_metrics(&mono);
+            let mono = match denoiser.as_mut() {
+                Some(denoiser) => denoiser.process(&mono),
+                None => mono,
+            };
+
             // Apply noise gate BEFORE VAD (critical for accuracy)
             let mono = apply_noise_gate(&mono, config.noise_gate_threshold);
 
             let (rms, peak) = calculate_audio_metrics(&mono);
-            let is_speech = rms > config.sensitivity_rms || peak > config.peak_threshold;
+            let is_speech = detector.is_speech(&mono);
 
             chunk_count += 1;
             // Emit signal level for UI visualization (approx every 100ms)
             if chunk_count % 5 == 0 {
                 let _ = app.emit("audio-signal-level", serde_json::json!({
                     "rms": rms,
-                    "peak": peak
+                    "peak": peak,
+                    "rms_before": rms_before,
+                    "peak_before": peak_before,
+                    "source": source
                 }));
             }
             if chunk_count % 100 == 0 {
@@ -190,20 +625,30 @@ async fn run_vad_capture(
 
                     // Include pre-speech buffer for natural sound
                     speech_buffer.extend(pre_speech.drain(..));
+                    stream_speech(&mut stt, &speech_buffer);
 
-                    let _ = app.emit("speech-start", ());
+                    let _ = app.emit("speech-start", source);
                 }
 
                 speech_chunks += 1;
                 speech_buffer.extend_from_slice(&mono);
+                stream_speech(&mut stt, &mono);
                 silence_chunks = 0; // Reset silence counter on any speech
 
                 // Safety cap: force emit if exceeds 30s
                 if speech_buffer.len() > max_samples {
-                    let normalized_buffer = normalize_audio_level(&speech_buffer, 0.1);
-                    if let Ok(b64) = samples_to_wav_b64(sr, &normalized_buffer) {
-                        // let duration = speech_buffer.len() as f32 / sr as f32;
-                        let _ = app.emit("speech-detected", b64);
+                    let speaker = diarizer.as_mut().and_then(|d| d.assign(&speech_buffer));
+                    send_to_tap(&tap, &speech_buffer, sr, source, speaker);
+                    if !commit_speech(&mut stt, speaker) {
+                        let normalized_buffer = normalize_audio_level(&speech_buffer, 0.1);
+                        let _ = emit_speech_segment(
+                            &app,
+                            sr,
+                            &normalized_buffer,
+                            config.encoding,
+                            source,
+                            speaker,
+                        );
                     }
                     speech_buffer.clear();
                     in_speech = false;
@@ -216,6 +661,7 @@ async fn run_vad_capture(
 
                     // Continue collecting during silence (important for natural speech)
                     speech_buffer.extend_from_slice(&mono);
+                    stream_speech(&mut stt, &mono);
 
                     // Check if silence duration exceeds threshold
                     if silence_chunks >= config.silence_chunks {
@@ -232,15 +678,29 @@ async fn run_vad_capture(
                             }
 
                             // Emit complete speech segment
-                            let normalized_buffer = normalize_audio_level(&speech_buffer, 0.1);
-                            if let Ok(b64) = samples_to_wav_b64(sr, &normalized_buffer) {
-                                // let duration = speech_buffer.len() as f32 / sr as f32;
-                                let _ = app.emit("speech-detected", b64);
-                            } else {
-                                error!("Failed to encode speech to WAV");
-                                let _ = app.emit("audio-encoding-error", "Failed to encode speech");
+                            let speaker =
+                                diarizer.as_mut().and_then(|d| d.assign(&speech_buffer));
+                            send_to_tap(&tap, &speech_buffer, sr, source, speaker);
+                            if !commit_speech(&mut stt, speaker) {
+                                let normalized_buffer = normalize_audio_level(&speech_buffer, 0.1);
+                                if emit_speech_segment(
+                                    &app,
+                                    sr,
+                                    &normalized_buffer,
+                                    config.encoding,
+                                    source,
+                                    speaker,
+                                )
+                                .is_err()
+                                {
+                                    error!("Failed to encode speech");
+                                    let _ = app.emit("audio-encoding-error", "Failed to encode speech");
+                                }
                             }
                         } else {
+                            if let Some(Err(e)) = stt.as_ref().map(|session| session.discard()) {
+                                drop_closed_session(&mut stt, &e);
+                            }
                             let _ = app.emit(
                                 "speech-discarded",
                                 "Audio too short (likely background noise)",
@@ -354,18 +814,18 @@ async fn run_continuous_capture(
     if !audio_buffer.is_empty() {
         // let duration = start_time.elapsed().as_secs_f32();
 
+        if let Some(mut denoiser) = create_denoiser(&config, None, sr) {
+            audio_buffer = denoiser.process_all(&audio_buffer);
+        }
+
         // Apply noise gate
         let cleaned_audio = apply_noise_gate(&audio_buffer, config.noise_gate_threshold);
         let cleaned_audio = normalize_audio_level(&cleaned_audio, 0.1);
 
-        match samples_to_wav_b64(sr, &cleaned_audio) {
-            Ok(b64) => {
-                let _ = app.emit("speech-detected", b64);
-            }
-            Err(e) => {
-                error!("Failed to encode continuous audio: {}", e);
-                let _ = app.emit("audio-encoding-error", e);
-            }
+        if let Err(e) = emit_speech_segment(&app, sr, &cleaned_audio, config.encoding, None, None)
+        {
+            error!("Failed to encode continuous audio: {}", e);
+            let _ = app.emit("audio-encoding-error", e);
         }
     } else {
         warn!("No audio captured in continuous mode");
@@ -392,21 +852,6 @@ fn apply_noise_gate(samples: &[f32], threshold: f32) -> Vec<f32> {
         .collect()
 }
 
-// Calculate RMS and peak (optimized)
-fn calculate_audio_metrics(chunk: &[f32]) -> (f32, f32) {
-    let mut sumsq = 0.0f32;
-    let mut peak = 0.0f32;
-
-    for &v in chunk {
-        let a = v.abs();
-        peak = peak.max(a);
-        sumsq += v * v;
-    }
-
-    let rms = (sumsq / chunk.len() as f32).sqrt();
-    (rms, peak)
-}
-
 fn normalize_audio_level(samples: &[f32], target_rms: f32) -> Vec<f32> {
     if samples.is_empty() {
         return Vec::new();
@@ -434,8 +879,12 @@ fn normalize_audio_level(samples: &[f32], target_rms: f32) -> Vec<f32> {
         .collect()
 }
 
-// Convert samples to WAV base64 (with proper error handling)
-fn samples_to_wav_b64(sample_rate: u32, mono_f32: &[f32]) -> Result<String, String> {
+// Encode samples for upload (with proper error handling)
+fn encode_samples(
+    sample_rate: u32,
+    mono_f32: &[f32],
+    encoding: AudioEncoding,
+) -> Result<Vec<u8>, String> {
     // Validate sample rate
     if !(8000..=96000).contains(&sample_rate) {
         error!("Invalid sample rate: {}", sample_rate);
@@ -450,28 +899,34 @@ fn samples_to_wav_b64(sample_rate: u32, mono_f32: &[f32]) -> Result<String, Stri
         return Err("Empty audio buffer".to_string());
     }
 
-    let mut cursor = Cursor::new(Vec::new());
-    let spec = WavSpec {
-        channels: 1,
-        sample_rate,
-        bits_per_sample: 16,
-        sample_format: hound::SampleFormat::Int,
-    };
-
-    let mut writer = WavWriter::new(&mut cursor, spec).map_err(|e| {
-        error!("Failed to create WAV writer: {}", e);
-        e.to_string()
-    })?;
-
-    for &s in mono_f32 {
-        let clamped = s.clamp(-1.0, 1.0);
-        let sample_i16 = (clamped * i16::MAX as f32) as i16;
-        writer.write_sample(sample_i16).map_err(|e| e.to_string())?;
-    }
-
-    writer.finalize().map_err(|e| e.to_string())?;
+    codec::encode(encoding, sample_rate, mono_f32).map_err(|e| {
+        error!("Failed to encode audio: {}", e);
+        e
+    })
+}
 
-    Ok(B64.encode(cursor.into_inner()))
+// Encodes an utterance, keeps it in the segment store and announces its ID
+fn emit_speech_segment(
+    app: &AppHandle,
+    sample_rate: u32,
+    samples: &[f32],
+    encoding: AudioEncoding,
+    source: Option<AudioSource>,
+    speaker: Option<u32>,
+) -> Result<(), String> {
+    let audio = encode_samples(sample_rate, samples, encoding)?;
+    let id = app.state::<SegmentStore>().insert(audio);
+    let _ = app.emit(
+        "speech-detected",
+        SpeechSegment {
+            id,
+            mime_type: encoding.mime_type(),
+            duration_secs: samples.len() as f32 / sample_rate as f32,
+            source,
+            speaker,
+        },
+    );
+    Ok(())
 }
 
 #[tauri::command]
@@ -490,6 +945,8 @@ pub async fn stop_system_audio_capture(app: AppHandle) -> Result<(), String> {
         }
     }
 
+    set_device_follower(&app, None);
+
     // LONGER delay for proper cleanup (300ms instead of 150ms)
     tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
@@ -592,6 +1049,14 @@ pub async fn update_vad_config(app: AppHandle, config: VadConfig) -> Result<(),
     if config.max_recording_duration_secs > 3600 {
         return Err("Invalid max_recording_duration_secs: must be <= 3600 (1 hour)".to_string());
     }
+    if !(config.silero.threshold > 0.0 && config.silero.threshold < 1.0) {
+        return Err("Invalid silero.threshold: must be between 0.0 and 1.0".to_string());
+    }
+    if let Some(rate) = config.target_sample_rate {
+        if !(8000..=48000).contains(&rate) {
+            return Err("Invalid target_sample_rate: must be 8000-48000 Hz".to_string());
+        }
+    }
 
     let state = app.state::<crate::AudioState>();
     *state
@@ -602,6 +1067,77 @@ pub async fn update_vad_config(app: AppHandle, config: VadConfig) -> Result<(),
     Ok(())
 }
 
+// Runs the VAD engine over a recording (WAV, FLAC or Ogg/Opus) and returns where it hears speech, for
+// tuning engines and thresholds against recorded fixtures
+#[tauri::command]
+pub async fn analyze_vad_recording(
+    app: AppHandle,
+    path: String,
+    vad_config: Option<VadConfig>,
+) -> Result<Vec<SpeechRegion>, String> {
+    let config = match vad_config {
+        Some(config) => config,
+        None => app
+            .state::<crate::AudioState>()
+            .vad_config
+            .lock()
+            .map_err(|e| format!("Failed to read VAD config: {}", e))?
+            .clone(),
+    };
+
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| format!("Failed to read recording: {}", e))?;
+    let (samples, device_sr) = codec::decode(&bytes)?;
+
+    // Same resampling, noise gate and chunking as a live capture
+    let stream = process_stream(futures_util::stream::iter(samples), device_sr, 1, &config)?;
+    let sr = stream.sample_rate();
+    let samples: Vec<f32> = stream.collect().await;
+    let mut detector = create_detector(&app, &config, sr)?;
+    let hop_size = scaled_hop_size(config.hop_size, sr);
+
+    tokio::task::spawn_blocking(move || {
+        let gated = apply_noise_gate(&samples, config.noise_gate_threshold);
+        speech_regions(detector.as_mut(), &gated, sr, hop_size)
+    })
+    .await
+    .map_err(|e| format!("Failed to analyze recording: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_diarization_config(app: AppHandle) -> Result<DiarizationConfig, String> {
+    let state = app.state::<crate::AudioState>();
+    let config = state
+        .diarization_config
+        .lock()
+        .map_err(|e| format!("Failed to get diarization config: {}", e))?
+        .clone();
+    Ok(config)
+}
+
+// Takes effect on the next capture; speaker numbers restart with each session
+#[tauri::command]
+pub async fn update_diarization_config(
+    app: AppHandle,
+    config: DiarizationConfig,
+) -> Result<(), String> {
+    if !(config.distance_threshold > 0.0 && config.distance_threshold <= 2.0) {
+        return Err("Invalid distance_threshold: must be greater than 0.0 and at most 2.0".to_string());
+    }
+    if config.max_speakers == 0 {
+        return Err("Invalid max_speakers: must be at least 1".to_string());
+    }
+
+    let state = app.state::<crate::AudioState>();
+    *state
+        .diarization_config
+        .lock()
+        .map_err(|e| format!("Failed to update diarization config: {}", e))? = config;
+
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn get_capture_status(app: AppHandle) -> Result<bool, String> {
     let state = app.state::<crate::AudioState>();
@@ -640,3 +1176,220 @@ pub fn get_output_devices() -> Result<Vec<AudioDevice>, String> {
         format!("Failed to get output devices: {}", e)
     })
 }
+
+// Payload of `audio-devices-changed`
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioDevicesChanged {
+    pub default_input_changed: bool,
+    pub default_output_changed: bool,
+    // A running capture moved to the new default device
+    pub capture_switched: bool,
+}
+
+// Devices of a running capture that follows the default device
+#[derive(Clone)]
+pub struct DeviceFollower {
+    config: VadConfig,
+    speaker: Option<FollowedDevice>,
+    microphone: Option<FollowedDevice>,
+}
+
+#[derive(Clone)]
+struct FollowedDevice {
+    switch: StreamSwitch,
+    sample_rate: u32,
+}
+
+fn set_device_follower(app: &AppHandle, follower: Option<DeviceFollower>) {
+    let state = app.state::<crate::AudioState>();
+    if let Ok(mut guard) = state.device_follower.lock() {
+        *guard = follower;
+    };
+}
+
+// Emits `audio-devices-changed` when an audio device is plugged in or removed
+// or the default changes, after moving a capture that follows the default
+pub fn watch_audio_devices(app: &AppHandle) {
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    let watcher = crate::speaker::watch_devices(move || {
+        let _ = sender.send(());
+    });
+    match watcher {
+        Ok(watcher) => {
+            // Managed so it runs for as long as the app
+            app.manage(watcher);
+            tauri::async_runtime::spawn(run_device_events(app.clone(), receiver));
+        }
+        Err(e) => warn!("Failed to watch audio devices: {}", e),
+    }
+}
+
+async fn run_device_events(app: AppHandle, mut changes: UnboundedReceiver<()>) {
+    let mut defaults = default_devices().await;
+    while changes.recv().await.is_some() {
+        tokio::time::sleep(DEVICE_SETTLE).await;
+        while changes.try_recv().is_ok() {}
+
+        let current = default_devices().await;
+        let default_input_changed = current.0 != defaults.0;
+        let default_output_changed = current.1 != defaults.1;
+        defaults = current;
+
+        let capture_switched =
+            follow_default_devices(&app, default_input_changed, default_output_changed).await;
+        let _ = app.emit(
+            "audio-devices-changed",
+            AudioDevicesChanged {
+                default_input_changed,
+                default_output_changed,
+                capture_switched,
+            },
+        );
+    }
+}
+
+// Ids of the default input and output
+async fn default_devices() -> (Option<String>, Option<String>) {
+    tokio::task::spawn_blocking(|| {
+        let default_of = |devices: Result<Vec<AudioDevice>>| {
+            devices
+                .ok()?
+                .into_iter()
+                .find(|device| device.is_default)
+                .map(|device| device.id)
+        };
+        (
+            default_of(crate::speaker::list_input_devices()),
+            default_of(crate::speaker::list_output_devices()),
+        )
+    })
+    .await
+    .unwrap_or_default()
+}
+
+// Moves the running capture onto new default devices, if it follows them.
+// The capture keeps its VAD state, so an utterance in progress carries on
+async fn follow_default_devices(
+    app: &AppHandle,
+    input_changed: bool,
+    output_changed: bool,
+) -> bool {
+    let follower = {
+        let state = app.state::<crate::AudioState>();
+        let Ok(guard) = state.device_follower.lock() else {
+            return false;
+        };
+        match guard.as_ref() {
+            Some(follower) => follower.clone(),
+            None => return false,
+        }
+    };
+
+    let mut switched = false;
+    if let (true, Some(speaker)) = (output_changed, follower.speaker) {
+        let config = follower.config.clone();
+        let opened = tokio::task::spawn_blocking(move || {
+            open_default_speaker(&config, speaker.sample_rate)
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|opened| opened);
+        switched |= switch_device(&speaker.switch, opened, "system audio");
+    }
+    if let (true, Some(microphone)) = (input_changed, follower.microphone) {
+        let config = follower.config;
+        let opened = tokio::task::spawn_blocking(move || {
+            open_default_microphone(&config, microphone.sample_rate)
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|opened| opened);
+        switched |= switch_device(&microphone.switch, opened, "microphone");
+    }
+    switched
+}
+
+fn switch_device(
+    switch: &StreamSwitch,
+    opened: Result<BoxedAudioStream, String>,
+    device: &str,
+) -> bool {
+    match opened {
+        Ok(stream) => {
+            let switched = switch.replace(stream);
+            if switched {
+                info!("Capture moved to the new default {} device", device);
+            }
+            switched
+        }
+        Err(e) => {
+            warn!("Failed to follow the default {} device: {}", device, e);
+            false
+        }
+    }
+}
+
+// Default system audio device, processed to the rate of the running capture
+fn open_default_speaker(config: &VadConfig, sample_rate: u32) -> Result<BoxedAudioStream, String> {
+    let speaker = open_speaker_input(None, None)?.stream();
+    let (device_sr, channels) = (speaker.sample_rate(), speaker.channels());
+    at_capture_rate(
+        process_stream(speaker, device_sr, channels, config)?,
+        sample_rate,
+    )
+}
+
+fn open_default_microphone(
+    config: &VadConfig,
+    sample_rate: u32,
+) -> Result<BoxedAudioStream, String> {
+    let microphone = MicrophoneInput::new(None)
+        .stream()
+        .map_err(|e| format!("Failed to access microphone: {}", e))?;
+    let device_sr = microphone.sample_rate();
+    at_capture_rate(
+        process_stream(microphone, device_sr, 1, config)?,
+        sample_rate,
+    )
+}
+
+// VAD and transcription keep running at the capture's rate, so a device that
+// cannot be resampled to it (target_sample_rate off) is not switched to
+fn at_capture_rate<S: Stream<Item = f32> + Unpin + Send + 'static>(
+    stream: DspStream<S>,
+    sample_rate: u32,
+) -> Result<BoxedAudioStream, String> {
+    if stream.sample_rate() != sample_rate {
+        return Err(format!(
+            "Device runs at {} Hz, capture at {} Hz",
+            stream.sample_rate(),
+            sample_rate
+        ));
+    }
+    Ok(Box::pin(stream))
+}
+
+// Applications playing audio that can be captured on their own (Linux only)
+#[tauri::command]
+pub fn get_audio_applications() -> Result<Vec<AudioApplication>, String> {
+    crate::speaker::list_audio_applications().map_err(|e| {
+        error!("Failed to get audio applications: {}", e);
+        format!("Failed to get audio applications: {}", e)
+    })
+}
+
+// Encoded bytes of a `speech-detected` segment, sent as a raw ArrayBuffer
+#[tauri::command]
+pub fn get_speech_segment(app: AppHandle, id: String) -> Result<tauri::ipc::Response, String> {
+    let audio = app
+        .state::<SegmentStore>()
+        .get(&id)
+        .ok_or_else(|| format!("Speech segment {} is no longer available", id))?;
+    Ok(tauri::ipc::Response::new(audio.to_vec()))
+}
+
+// Frees a segment once the webview is done with it
+#[tauri::command]
+pub fn release_speech_segment(app: AppHandle, id: String) {
+    app.state::<SegmentStore>().remove(&id);
+}
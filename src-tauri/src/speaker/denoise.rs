@@ -0,0 +1,194 @@
+// Cloak noise suppression: steady background noise (fans, HVAC, café hum)
+// removed per frequency before VAD sees the audio, which the soft-knee gate
+// cannot do since it only looks at sample amplitude.
+//
+// Spectral suppression over ~32ms frames with 50% overlap: the noise spectrum
+// is tracked as the slowly rising minimum of the smoothed power, and each bin
+// gets a Wiener gain from its decision-directed SNR estimate. The gain floor
+// keeps some of the noise, which avoids "musical noise" artifacts.
+use crate::speaker::AudioSource;
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+const FRAME_MS: usize = 32;
+// Most a bin is attenuated (-20 dB)
+const GAIN_FLOOR: f32 = 0.1;
+// Decision-directed SNR smoothing; higher is smoother but slower on onsets
+const SNR_SMOOTHING: f32 = 0.98;
+const POWER_SMOOTHING: f32 = 0.8;
+// Per-frame growth of the noise estimate while the power stays above it,
+// about 0.3 dB/s at 16 kHz, so speech is not taken for noise
+const NOISE_RISE: f32 = 1.001;
+const NOISE_FALL: f32 = 0.1;
+// Frames that seed the noise estimate directly
+const WARMUP_FRAMES: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoiseSuppressionConfig {
+    /// Denoise the microphone ("me" in a dual capture)
+    pub microphone: bool,
+    /// Denoise system audio; calls usually arrive denoised already
+    pub system_audio: bool,
+}
+
+impl Default for NoiseSuppressionConfig {
+    fn default() -> Self {
+        Self {
+            microphone: true,
+            system_audio: false,
+        }
+    }
+}
+
+impl NoiseSuppressionConfig {
+    /// Whether audio from `source` is denoised; `None` is a system audio
+    /// capture on its own.
+    pub fn enabled_for(&self, source: Option<AudioSource>) -> bool {
+        match source {
+            Some(AudioSource::Me) => self.microphone,
+            Some(AudioSource::Them) | None => self.system_audio,
+        }
+    }
+}
+
+pub struct Denoiser {
+    size: usize,
+    hop: usize,
+    // sqrt-Hann, applied on analysis and synthesis so overlap-add sums to one
+    window: Vec<f32>,
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+    // Last `size` input samples
+    frame: Vec<f32>,
+    input: Vec<f32>,
+    overlap: Vec<f32>,
+    output: VecDeque<f32>,
+    spectrum: Vec<Complex<f32>>,
+    power: Vec<f32>,
+    noise: Vec<f32>,
+    // Clean-speech SNR estimate of the previous frame, per bin
+    previous_snr: Vec<f32>,
+    frames: usize,
+}
+
+impl Denoiser {
+    pub fn new(sample_rate: u32) -> Self {
+        let size = (FRAME_MS * sample_rate as usize / 1000)
+            .next_power_of_two()
+            .max(64);
+        let hop = size / 2;
+        let window = (0..size)
+            .map(|i| {
+                let phase = 2.0 * std::f32::consts::PI * i as f32 / size as f32;
+                (0.5 - 0.5 * phase.cos()).sqrt()
+            })
+            .collect();
+        let mut planner = FftPlanner::new();
+        let bins = size / 2 + 1;
+        Self {
+            size,
+            hop,
+            window,
+            forward: planner.plan_fft_forward(size),
+            inverse: planner.plan_fft_inverse(size),
+            frame: vec![0.0; size],
+            input: Vec::with_capacity(hop),
+            overlap: vec![0.0; size],
+            // Pads the output so every call returns as many samples as it got
+            output: VecDeque::from(vec![0.0; hop]),
+            spectrum: vec![Complex::default(); size],
+            power: vec![0.0; bins],
+            noise: vec![0.0; bins],
+            previous_snr: vec![0.0; bins],
+            frames: 0,
+        }
+    }
+
+    /// Samples of delay added by `process`.
+    pub fn latency(&self) -> usize {
+        self.hop * 2
+    }
+
+    /// Denoises a chunk of a continuous stream. Returns as many samples as
+    /// given, delayed by `latency()`.
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        for &sample in samples {
+            self.input.push(sample);
+            if self.input.len() == self.hop {
+                self.process_hop();
+            }
+        }
+        let len = samples.len().min(self.output.len());
+        self.output.drain(..len).collect()
+    }
+
+    /// Denoises a whole recording, without the added delay.
+    pub fn process_all(&mut self, samples: &[f32]) -> Vec<f32> {
+        let latency = self.latency();
+        let mut padded = samples.to_vec();
+        padded.resize(samples.len() + latency, 0.0);
+        let mut output = self.process(&padded);
+        output.drain(..latency.min(output.len()));
+        output
+    }
+
+    fn process_hop(&mut self) {
+        self.frame.copy_within(self.hop.., 0);
+        self.frame[self.size - self.hop..].copy_from_slice(&self.input);
+        self.input.clear();
+
+        for ((bin, &sample), &w) in self.spectrum.iter_mut().zip(&self.frame).zip(&self.window) {
+            *bin = Complex::new(sample * w, 0.0);
+        }
+        self.forward.process(&mut self.spectrum);
+
+        let bins = self.power.len();
+        let warming_up = self.frames < WARMUP_FRAMES;
+        for k in 0..bins {
+            let bin_power = self.spectrum[k].norm_sqr();
+            self.power[k] = if self.frames == 0 {
+                bin_power
+            } else {
+                POWER_SMOOTHING * self.power[k] + (1.0 - POWER_SMOOTHING) * bin_power
+            };
+
+            let noise = &mut self.noise[k];
+            if warming_up {
+                *noise += (self.power[k] - *noise) / (self.frames + 1) as f32;
+            } else if self.power[k] < *noise {
+                *noise += (self.power[k] - *noise) * NOISE_FALL;
+            } else {
+                *noise *= NOISE_RISE;
+            }
+
+            let posterior = bin_power / self.noise[k].max(1e-12);
+            let prior = SNR_SMOOTHING * self.previous_snr[k]
+                + (1.0 - SNR_SMOOTHING) * (posterior - 1.0).max(0.0);
+            let gain = (prior / (1.0 + prior)).max(GAIN_FLOOR);
+            self.previous_snr[k] = gain * gain * posterior;
+
+            self.spectrum[k] *= gain;
+            // Keep the spectrum conjugate-symmetric so the output stays real
+            if k > 0 && k < self.size - k {
+                self.spectrum[self.size - k] = self.spectrum[k].conj();
+            }
+        }
+        self.frames += 1;
+
+        self.inverse.process(&mut self.spectrum);
+        let scale = 1.0 / self.size as f32;
+        for ((acc, bin), &w) in self
+            .overlap
+            .iter_mut()
+            .zip(&self.spectrum)
+            .zip(&self.window)
+        {
+            *acc += bin.re * scale * w;
+        }
+        self.output.extend(self.overlap.drain(..self.hop));
+        self.overlap.resize(self.size, 0.0);
+    }
+}
@@ -0,0 +1,274 @@
+// Cloak on-device speaker diarization: every utterance gets a voice embedding
+// (MFCC mean and spread) and is clustered online into Speaker 1, Speaker 2, ...
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const FRAME_MS: u32 = 25;
+const HOP_MS: u32 = 10;
+const MEL_BANDS: usize = 26;
+// Cepstral coefficients kept, excluding c0 (loudness)
+const CEPSTRA: usize = 12;
+const PRE_EMPHASIS: f32 = 0.97;
+// Frames more than ~26 dB below the loudest frame are pauses, not voice
+const SILENCE_FLOOR: f32 = 6.0;
+// Fewer voiced frames than this (~0.3s) say too little about the voice
+const MIN_VOICED_FRAMES: usize = 30;
+// Digital silence carries no voice at all
+const MIN_RMS: f32 = 1e-4;
+// Short utterances can join a speaker but never start a new one
+const MIN_NEW_SPEAKER_SECS: f32 = 1.0;
+// Centroids keep adapting slowly after this many utterances
+const MAX_CENTROID_WEIGHT: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiarizationConfig {
+    pub enabled: bool,
+    /// Largest voice distance that still counts as a known speaker; lower
+    /// values split voices more eagerly
+    pub distance_threshold: f32,
+    pub max_speakers: usize,
+}
+
+impl Default for DiarizationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            distance_threshold: 0.2,
+            max_speakers: 6,
+        }
+    }
+}
+
+struct SpeakerCluster {
+    centroid: Vec<f32>,
+    utterances: usize,
+}
+
+/// Speaker clustering for one audio stream. Speaker numbers are stable for the
+/// lifetime of the diarizer, i.e. one capture session.
+pub struct Diarizer {
+    config: DiarizationConfig,
+    sample_rate: u32,
+    frame_len: usize,
+    hop_len: usize,
+    fft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    filterbank: Vec<Vec<f32>>,
+    speakers: Vec<SpeakerCluster>,
+}
+
+impl Diarizer {
+    pub fn new(config: DiarizationConfig, sample_rate: u32) -> Self {
+        let frame_len = (sample_rate * FRAME_MS / 1000) as usize;
+        let hop_len = (sample_rate * HOP_MS / 1000) as usize;
+        let fft_len = frame_len.next_power_of_two();
+        let window = (0..frame_len)
+            .map(|i| {
+                0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / (frame_len - 1) as f32).cos()
+            })
+            .collect();
+
+        Self {
+            config,
+            sample_rate,
+            frame_len,
+            hop_len,
+            fft: FftPlanner::new().plan_fft_forward(fft_len),
+            window,
+            filterbank: mel_filterbank(fft_len, sample_rate, MEL_BANDS),
+            speakers: Vec::new(),
+        }
+    }
+
+    /// Returns the 1-based speaker number for an utterance, or `None` when it
+    /// has too little voiced audio to tell.
+    pub fn assign(&mut self, samples: &[f32]) -> Option<u32> {
+        let embedding = self.embed(samples)?;
+        let duration = samples.len() as f32 / self.sample_rate as f32;
+
+        let best = self
+            .speakers
+            .iter()
+            .enumerate()
+            .map(|(i, speaker)| (i, voice_distance(&speaker.centroid, &embedding)))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+
+        let index = match best {
+            Some((i, distance)) if distance <= self.config.distance_threshold => {
+                let speaker = &mut self.speakers[i];
+                let weight = speaker.utterances.min(MAX_CENTROID_WEIGHT) as f32;
+                for (c, e) in speaker.centroid.iter_mut().zip(&embedding) {
+                    *c = (*c * weight + e) / (weight + 1.0);
+                }
+                speaker.utterances += 1;
+                i
+            }
+            // Not confident enough to add a voice; go with the closest one
+            Some((i, _))
+                if duration < MIN_NEW_SPEAKER_SECS
+                    || self.speakers.len() >= self.config.max_speakers.max(1) =>
+            {
+                i
+            }
+            _ => {
+                self.speakers.push(SpeakerCluster {
+                    centroid: embedding,
+                    utterances: 1,
+                });
+                self.speakers.len() - 1
+            }
+        };
+
+        Some(index as u32 + 1)
+    }
+
+    // Mean and standard deviation of the MFCCs over voiced frames, concatenated
+    fn embed(&self, samples: &[f32]) -> Option<Vec<f32>> {
+        if samples.len() < self.frame_len || self.hop_len == 0 {
+            return None;
+        }
+        let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+        if rms < MIN_RMS {
+            return None;
+        }
+
+        let fft_len = self.fft.len();
+        let mut buffer = vec![Complex::new(0.0f32, 0.0); fft_len];
+        let mut frames: Vec<(f32, Vec<f32>)> = Vec::new();
+
+        for start in (0..=samples.len() - self.frame_len).step_by(self.hop_len) {
+            let frame = &samples[start..start + self.frame_len];
+            for (i, slot) in buffer.iter_mut().enumerate() {
+                let value = if i < self.frame_len {
+                    let previous = if i > 0 { frame[i - 1] } else { 0.0 };
+                    (frame[i] - PRE_EMPHASIS * previous) * self.window[i]
+                } else {
+                    0.0
+                };
+                *slot = Complex::new(value, 0.0);
+            }
+            self.fft.process(&mut buffer);
+
+            let power: Vec<f32> = buffer[..fft_len / 2 + 1]
+                .iter()
+                .map(|c| c.norm_sqr() / fft_len as f32)
+                .collect();
+            let energy = (power.iter().sum::<f32>() + 1e-10).ln();
+            let log_mel: Vec<f32> = self
+                .filterbank
+                .iter()
+                .map(|filter| {
+                    let band: f32 = filter.iter().zip(&power).map(|(w, p)| w * p).sum();
+                    band.max(1e-10).ln()
+                })
+                .collect();
+            frames.push((energy, dct(&log_mel, CEPSTRA)));
+        }
+
+        let loudest = frames
+            .iter()
+            .map(|(energy, _)| *energy)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let voiced: Vec<&Vec<f32>> = frames
+            .iter()
+            .filter(|(energy, _)| *energy >= loudest - SILENCE_FLOOR)
+            .map(|(_, cepstra)| cepstra)
+            .collect();
+        if voiced.len() < MIN_VOICED_FRAMES {
+            return None;
+        }
+
+        let count = voiced.len() as f32;
+        let mut mean = vec![0.0f32; CEPSTRA];
+        for cepstra in &voiced {
+            for (m, c) in mean.iter_mut().zip(cepstra.iter()) {
+                *m += c / count;
+            }
+        }
+        let mut std_dev = vec![0.0f32; CEPSTRA];
+        for cepstra in &voiced {
+            for ((s, c), m) in std_dev.iter_mut().zip(cepstra.iter()).zip(&mean) {
+                *s += (c - m).powi(2) / count;
+            }
+        }
+        std_dev.iter_mut().for_each(|s| *s = s.sqrt());
+
+        mean.extend(std_dev);
+        Some(mean)
+    }
+}
+
+/// Label used in transcripts, e.g. `Speaker 2`.
+pub fn speaker_label(speaker: u32) -> String {
+    format!("Speaker {}", speaker)
+}
+
+// DCT-II of the log mel energies, skipping c0
+fn dct(log_mel: &[f32], coefficients: usize) -> Vec<f32> {
+    let bands = log_mel.len() as f32;
+    (1..=coefficients)
+        .map(|k| {
+            log_mel
+                .iter()
+                .enumerate()
+                .map(|(m, value)| {
+                    value * (std::f32::consts::PI * k as f32 * (m as f32 + 0.5) / bands).cos()
+                })
+                .sum()
+        })
+        .collect()
+}
+
+fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10f32.powf(mel / 2595.0) - 1.0)
+}
+
+// Triangular filters spaced evenly on the mel scale, up to 8 kHz (voice band)
+fn mel_filterbank(fft_len: usize, sample_rate: u32, bands: usize) -> Vec<Vec<f32>> {
+    let bins = fft_len / 2 + 1;
+    let max_hz = (sample_rate as f32 / 2.0).min(8000.0);
+    let (low, high) = (hz_to_mel(20.0), hz_to_mel(max_hz));
+    let edges: Vec<f32> = (0..bands + 2)
+        .map(|i| {
+            let hz = mel_to_hz(low + (high - low) * i as f32 / (bands + 1) as f32);
+            hz * fft_len as f32 / sample_rate as f32
+        })
+        .collect();
+
+    (0..bands)
+        .map(|band| {
+            let (left, center, right) = (edges[band], edges[band + 1], edges[band + 2]);
+            (0..bins)
+                .map(|bin| {
+                    let bin = bin as f32;
+                    if bin <= left || bin >= right {
+                        0.0
+                    } else if bin <= center {
+                        (bin - left) / (center - left).max(f32::EPSILON)
+                    } else {
+                        (right - bin) / (right - center).max(f32::EPSILON)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// RMS difference of the MFCC means, each scaled by the pooled frame-to-frame
+// spread: how far apart two voices are relative to normal variation in speech
+fn voice_distance(a: &[f32], b: &[f32]) -> f32 {
+    let (mean_a, spread_a) = a.split_at(CEPSTRA);
+    let (mean_b, spread_b) = b.split_at(CEPSTRA);
+    let sum: f32 = (0..CEPSTRA)
+        .map(|k| {
+            let variance = (spread_a[k].powi(2) + spread_b[k].powi(2)) / 2.0;
+            (mean_a[k] - mean_b[k]).powi(2) / variance.max(1e-6)
+        })
+        .sum();
+    (sum / CEPSTRA as f32).sqrt()
+}
@@ -0,0 +1,135 @@
+// Cloak capture DSP: downmixes interleaved device audio to mono and resamples
+// it to one target rate, so everything downstream sees the same format
+use futures_util::Stream;
+use rubato::{FftFixedIn, Resampler};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tracing::error;
+
+/// What speech-to-text providers expect; a third of the data of 48 kHz
+pub const DEFAULT_TARGET_RATE: u32 = 16000;
+
+// Input frames per resampler call (~23ms at 44.1 kHz)
+const CHUNK_FRAMES: usize = 1024;
+const SUB_CHUNKS: usize = 2;
+
+pub struct DspStream<S> {
+    inner: S,
+    channels: usize,
+    output_rate: u32,
+    resampler: Option<FftFixedIn<f32>>,
+    // Interleaved samples of the frame being assembled
+    frame: Vec<f32>,
+    // Mono samples waiting for a full resampler chunk
+    pending: Vec<f32>,
+    output: VecDeque<f32>,
+    finished: bool,
+}
+
+impl<S: Stream<Item = f32> + Unpin> DspStream<S> {
+    /// Wraps a stream of interleaved samples at `input_rate`. `target_rate` of
+    /// `None` keeps the device rate and only downmixes.
+    pub fn new(
+        inner: S,
+        input_rate: u32,
+        channels: u16,
+        target_rate: Option<u32>,
+    ) -> Result<Self, String> {
+        if input_rate == 0 {
+            return Err("Audio device did not report a sample rate".to_string());
+        }
+
+        let output_rate = target_rate.unwrap_or(input_rate);
+        let resampler = if output_rate != input_rate {
+            let resampler = FftFixedIn::new(
+                input_rate as usize,
+                output_rate as usize,
+                CHUNK_FRAMES,
+                SUB_CHUNKS,
+                1,
+            )
+            .map_err(|e| format!("Failed to create resampler: {}", e))?;
+            Some(resampler)
+        } else {
+            None
+        };
+
+        let channels = channels.max(1) as usize;
+        Ok(Self {
+            inner,
+            channels,
+            output_rate,
+            resampler,
+            frame: Vec::with_capacity(channels),
+            pending: Vec::with_capacity(CHUNK_FRAMES),
+            output: VecDeque::new(),
+            finished: false,
+        })
+    }
+
+    /// Rate of the samples this stream yields.
+    pub fn sample_rate(&self) -> u32 {
+        self.output_rate
+    }
+
+    fn push_sample(&mut self, sample: f32) {
+        self.frame.push(sample);
+        if self.frame.len() < self.channels {
+            return;
+        }
+        let mono = self.frame.iter().sum::<f32>() / self.channels as f32;
+        self.frame.clear();
+
+        let Some(resampler) = self.resampler.as_mut() else {
+            self.output.push_back(mono);
+            return;
+        };
+
+        self.pending.push(mono);
+        let needed = resampler.input_frames_next();
+        if self.pending.len() >= needed {
+            let chunk: Vec<f32> = self.pending.drain(..needed).collect();
+            match resampler.process(&[chunk], None) {
+                Ok(resampled) => self.output.extend(resampled.into_iter().flatten()),
+                Err(e) => error!("Failed to resample audio: {}", e),
+            }
+        }
+    }
+
+    // Resamples whatever is left once the device stream ends
+    fn flush(&mut self) {
+        if let Some(resampler) = self.resampler.as_mut() {
+            if !self.pending.is_empty() {
+                let chunk = std::mem::take(&mut self.pending);
+                match resampler.process_partial(Some(&[chunk]), None) {
+                    Ok(resampled) => self.output.extend(resampled.into_iter().flatten()),
+                    Err(e) => error!("Failed to resample audio: {}", e),
+                }
+            }
+        }
+        self.finished = true;
+    }
+}
+
+impl<S: Stream<Item = f32> + Unpin> Stream for DspStream<S> {
+    type Item = f32;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(sample) = this.output.pop_front() {
+                return Poll::Ready(Some(sample));
+            }
+            if this.finished {
+                return Poll::Ready(None);
+            }
+
+            match Pin::new(&mut this.inner).poll_next(cx) {
+                Poll::Ready(Some(sample)) => this.push_sample(sample),
+                Poll::Ready(None) => this.flush(),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
@@ -1,10 +1,12 @@
 // Cloak linux speaker input and stream
-use super::AudioDevice;
+use super::watch::{WatchThread, STOP_POLL};
+use super::{AudioApplication, AudioDevice};
 use anyhow::{anyhow, Result};
 use futures_util::Stream;
 use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::rc::Rc;
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 use std::task::{Poll, Waker};
 use std::thread;
@@ -14,9 +16,13 @@ use libpulse_binding as pulse;
 use libpulse_simple_binding as psimple;
 
 use psimple::Simple;
-use pulse::context::introspect::Introspector;
+use pulse::callbacks::ListResult;
+use pulse::context::introspect::{Introspector, SinkInputInfo};
+use pulse::context::subscribe::{Facility, InterestMaskSet, Operation as SubscriptionOperation};
 use pulse::context::Context;
 use pulse::mainloop::standard::Mainloop;
+use pulse::operation::{Operation, State as OperationState};
+use pulse::proplist::properties;
 use pulse::sample::{Format, Spec};
 use pulse::stream::Direction;
 
@@ -219,8 +225,433 @@ pub fn get_output_devices() -> Result<Vec<AudioDevice>> {
     Ok(Rc::try_unwrap(devices).unwrap().into_inner())
 }
 
+// Streams applications are playing (PulseAudio sink-inputs)
+pub fn get_audio_applications() -> Result<Vec<AudioApplication>> {
+    let mut pulse = PulseConnection::connect("cloak-app-enum")?;
+    pulse.request(|introspector, result| {
+        let mut applications = Vec::new();
+        introspector.get_sink_input_info_list(move |item| match item {
+            ListResult::Item(info) => applications.extend(application_of(info)),
+            ListResult::End | ListResult::Error => {
+                *result.borrow_mut() = Some(std::mem::take(&mut applications));
+            }
+        })
+    })
+}
+
+// Streams owned by a module (loopbacks, ours included) have no client
+fn application_of(info: &SinkInputInfo) -> Option<AudioApplication> {
+    info.client?;
+    let binary = info
+        .proplist
+        .get_str(properties::APPLICATION_PROCESS_BINARY);
+    let name = info
+        .proplist
+        .get_str(properties::APPLICATION_NAME)
+        .or_else(|| binary.clone())?;
+    Some(AudioApplication {
+        id: info.index.to_string(),
+        name,
+        binary,
+        media: info.proplist.get_str(properties::MEDIA_NAME),
+    })
+}
+
+// Blocking PulseAudio connection for introspection requests
+struct PulseConnection {
+    mainloop: Mainloop,
+    context: Context,
+}
+
+impl PulseConnection {
+    fn connect(name: &str) -> Result<Self> {
+        let mut mainloop =
+            Mainloop::new().ok_or_else(|| anyhow!("Failed to create PulseAudio mainloop"))?;
+        let mut context = Context::new(&mainloop, name)
+            .ok_or_else(|| anyhow!("Failed to create PulseAudio context"))?;
+
+        context
+            .connect(None, pulse::context::FlagSet::NOFLAGS, None)
+            .map_err(|_| anyhow!("Failed to connect to PulseAudio"))?;
+
+        loop {
+            match mainloop.iterate(true) {
+                pulse::mainloop::standard::IterateResult::Success(_) => {}
+                _ => return Err(anyhow!("Failed to iterate mainloop")),
+            }
+
+            match context.get_state() {
+                pulse::context::State::Ready => break,
+                pulse::context::State::Failed | pulse::context::State::Terminated => {
+                    return Err(anyhow!("PulseAudio context failed"));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self { mainloop, context })
+    }
+
+    // Starts a request, then runs the mainloop until its callback stores a result
+    fn request<T: 'static, C: ?Sized>(
+        &mut self,
+        start: impl FnOnce(&mut Introspector, Rc<RefCell<Option<T>>>) -> Operation<C>,
+    ) -> Result<T> {
+        let result = Rc::new(RefCell::new(None));
+        let mut introspector = self.context.introspect();
+        let op = start(&mut introspector, result.clone());
+
+        loop {
+            if let Some(value) = result.borrow_mut().take() {
+                return Ok(value);
+            }
+            if !matches!(op.get_state(), OperationState::Running) {
+                return Err(anyhow!("PulseAudio request was cancelled"));
+            }
+            match self.mainloop.iterate(true) {
+                pulse::mainloop::standard::IterateResult::Success(_) => {}
+                _ => return Err(anyhow!("Failed to iterate mainloop")),
+            }
+        }
+    }
+}
+
+impl Drop for PulseConnection {
+    fn drop(&mut self) {
+        self.context.disconnect();
+    }
+}
+
+// Notifications for sinks and sources coming and going, and for default device
+// changes. PipeWire's PulseAudio server sends these too.
+pub(super) fn watch_devices(on_change: impl Fn() + Send + 'static) -> Result<WatchThread> {
+    WatchThread::spawn("pulse_device_watcher", move |stop, ready| {
+        let mut connection = PulseConnection::connect("cloak-device-watch")?;
+
+        connection
+            .context
+            .set_subscribe_callback(Some(Box::new(move |facility, operation, _| {
+                // Sinks and sources also report every volume change
+                let device = matches!(facility, Some(Facility::Sink | Facility::Source))
+                    && matches!(
+                        operation,
+                        Some(SubscriptionOperation::New | SubscriptionOperation::Removed)
+                    );
+                // Server changes are default device changes
+                if device || matches!(facility, Some(Facility::Server)) {
+                    on_change();
+                }
+            })));
+        let _subscription = connection.context.subscribe(
+            InterestMaskSet::SINK | InterestMaskSet::SOURCE | InterestMaskSet::SERVER,
+            |_| {},
+        );
+        ready();
+
+        while !stop.load(Ordering::Acquire) {
+            match connection.mainloop.iterate(false) {
+                // Nothing pending
+                pulse::mainloop::standard::IterateResult::Success(0) => {
+                    thread::sleep(STOP_POLL);
+                }
+                pulse::mainloop::standard::IterateResult::Success(_) => {}
+                _ => return Err(anyhow!("PulseAudio connection lost")),
+            }
+        }
+        Ok(())
+    })
+}
+
+// Prefix of the null sink an application is routed through, followed by the pid
+// of the Cloak process that owns it
+const ROUTE_SINK_PREFIX: &str = "cloak_capture_";
+
+// Isolates one application for capture: its streams are moved onto a private
+// null sink whose monitor is recorded, and a loopback plays that sink on the
+// device they came from so the user still hears them. Streams it opens later,
+// as call apps do when a call starts or their device changes, are moved too.
+// Dropping it moves the streams back.
+struct ApplicationRoute {
+    pulse: PulseConnection,
+    sink_name: String,
+    // (sink-input, sink it was playing on)
+    moved: Arc<Mutex<Vec<(u32, u32)>>>,
+    modules: Vec<u32>,
+    // Routes the application's new streams while capture runs
+    follower: Option<WatchThread>,
+}
+
+impl ApplicationRoute {
+    fn create(application: &str) -> Result<Self> {
+        let mut pulse = PulseConnection::connect("cloak-app-capture")?;
+        let streams: Vec<(u32, u32)> = pulse.request(|introspector, result| {
+            let application = application.to_string();
+            let mut streams = Vec::new();
+            introspector.get_sink_input_info_list(move |item| match item {
+                ListResult::Item(info) => {
+                    if application_of(info).is_some_and(|app| app.name == application) {
+                        streams.push((info.index, info.sink));
+                    }
+                }
+                ListResult::End | ListResult::Error => {
+                    *result.borrow_mut() = Some(std::mem::take(&mut streams));
+                }
+            })
+        })?;
+        let Some(&(_, output_sink)) = streams.first() else {
+            return Err(anyhow!("{} is not playing any audio", application));
+        };
+
+        let output_name = pulse
+            .request(|introspector, result| {
+                let mut name = None;
+                introspector.get_sink_info_by_index(output_sink, move |item| match item {
+                    ListResult::Item(info) => {
+                        name = info.name.as_ref().map(|name| name.to_string());
+                    }
+                    ListResult::End | ListResult::Error => {
+                        *result.borrow_mut() = Some(name.take());
+                    }
+                })
+            })?
+            .ok_or_else(|| anyhow!("Failed to find the device {} plays on", application))?;
+
+        let mut route = Self {
+            pulse,
+            sink_name: format!("{}{}", ROUTE_SINK_PREFIX, std::process::id()),
+            moved: Arc::default(),
+            modules: Vec::new(),
+            follower: None,
+        };
+        route.load_module(
+            "module-null-sink",
+            &format!(
+                "sink_name={} sink_properties=device.description=Cloak-Capture",
+                route.sink_name
+            ),
+        )?;
+        route.load_module(
+            "module-loopback",
+            &format!(
+                "source={} sink={} latency_msec=30 source_dont_move=true sink_dont_move=true",
+                route.monitor(),
+                output_name
+            ),
+        )?;
+
+        for (index, sink) in streams {
+            if move_stream(&mut route.pulse, index, &route.sink_name)? {
+                route.moved.lock().unwrap().push((index, sink));
+            } else {
+                warn!(
+                    "Failed to route stream {} of {} for capture",
+                    index, application
+                );
+            }
+        }
+        if route.moved.lock().unwrap().is_empty() {
+            return Err(anyhow!("Failed to route {} for capture", application));
+        }
+
+        // Capture still works without it, until the application reopens a stream
+        match route.follow_new_streams(application, output_sink) {
+            Ok(follower) => route.follower = Some(follower),
+            Err(e) => warn!("Failed to watch {} for new streams: {}", application, e),
+        }
+
+        Ok(route)
+    }
+
+    fn follow_new_streams(&self, application: &str, output_sink: u32) -> Result<WatchThread> {
+        let application = application.to_string();
+        let sink_name = self.sink_name.clone();
+        let moved = self.moved.clone();
+        WatchThread::spawn("app_capture_follower", move |stop, ready| {
+            let mut connection = PulseConnection::connect("cloak-app-follow")?;
+            let route_sink = connection.request(|introspector, result| {
+                let mut index = None;
+                introspector.get_sink_info_by_name(&sink_name, move |item| match item {
+                    ListResult::Item(info) => index = Some(info.index),
+                    ListResult::End | ListResult::Error => {
+                        *result.borrow_mut() = Some(index.take());
+                    }
+                })
+            })?;
+
+            let opened = Rc::new(RefCell::new(Vec::new()));
+            connection.context.set_subscribe_callback(Some(Box::new({
+                let opened = opened.clone();
+                move |facility, operation, index| {
+                    if matches!(facility, Some(Facility::SinkInput))
+                        && matches!(operation, Some(SubscriptionOperation::New))
+                    {
+                        opened.borrow_mut().push(index);
+                    }
+                }
+            })));
+            let _subscription = connection
+                .context
+                .subscribe(InterestMaskSet::SINK_INPUT, |_| {});
+            ready();
+
+            while !stop.load(Ordering::Acquire) {
+                match connection.mainloop.iterate(false) {
+                    // Nothing pending
+                    pulse::mainloop::standard::IterateResult::Success(0) => {
+                        thread::sleep(STOP_POLL);
+                    }
+                    pulse::mainloop::standard::IterateResult::Success(_) => {}
+                    _ => return Err(anyhow!("PulseAudio connection lost")),
+                }
+
+                let opened: Vec<u32> = opened.borrow_mut().drain(..).collect();
+                for index in opened {
+                    let Some(sink) = playing_on(&mut connection, index, &application)? else {
+                        continue;
+                    };
+                    // PulseAudio remembers where an application's streams were
+                    // moved, so new ones may already play on the route
+                    let restore_to = if Some(sink) == route_sink {
+                        output_sink
+                    } else if move_stream(&mut connection, index, &sink_name)? {
+                        sink
+                    } else {
+                        warn!("Failed to route new stream {} for capture", index);
+                        continue;
+                    };
+                    moved.lock().unwrap().push((index, restore_to));
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn load_module(&mut self, name: &str, argument: &str) -> Result<()> {
+        let index = self.pulse.request(|introspector, result| {
+            introspector.load_module(name, argument, move |index| {
+                *result.borrow_mut() = Some(index);
+            })
+        })?;
+        if index == pulse::def::INVALID_INDEX {
+            return Err(anyhow!("Failed to load PulseAudio {}", name));
+        }
+        self.modules.push(index);
+        Ok(())
+    }
+
+    fn monitor(&self) -> String {
+        format!("{}.monitor", self.sink_name)
+    }
+}
+
+impl Drop for ApplicationRoute {
+    fn drop(&mut self) {
+        // Stops routing new streams before the moved ones are counted
+        self.follower.take();
+        // Streams go back first, or unloading the null sink would send them to
+        // the default device rather than where they were
+        let moved = std::mem::take(&mut *self.moved.lock().unwrap());
+        for (index, sink) in moved {
+            let restored = self.pulse.request(|introspector, result| {
+                introspector.move_sink_input_by_index(
+                    index,
+                    sink,
+                    Some(Box::new(move |success| {
+                        *result.borrow_mut() = Some(success)
+                    })),
+                )
+            });
+            if !matches!(restored, Ok(true)) {
+                warn!("Failed to move stream {} back to its device", index);
+            }
+        }
+        for module in std::mem::take(&mut self.modules).into_iter().rev() {
+            let _ = self.pulse.request(|introspector, result| {
+                introspector.unload_module(module, move |success| {
+                    *result.borrow_mut() = Some(success);
+                })
+            });
+        }
+    }
+}
+
+// Sink that sink-input `index` plays on, if it belongs to `application`
+fn playing_on(pulse: &mut PulseConnection, index: u32, application: &str) -> Result<Option<u32>> {
+    let application = application.to_string();
+    pulse.request(|introspector, result| {
+        let mut sink = None;
+        introspector.get_sink_input_info(index, move |item| match item {
+            ListResult::Item(info) => {
+                if application_of(info).is_some_and(|app| app.name == application) {
+                    sink = Some(info.sink);
+                }
+            }
+            ListResult::End | ListResult::Error => {
+                *result.borrow_mut() = Some(sink.take());
+            }
+        })
+    })
+}
+
+fn move_stream(pulse: &mut PulseConnection, index: u32, sink_name: &str) -> Result<bool> {
+    pulse.request(|introspector, result| {
+        introspector.move_sink_input_by_name(
+            index,
+            sink_name,
+            Some(Box::new(move |success| {
+                *result.borrow_mut() = Some(success)
+            })),
+        )
+    })
+}
+
+/// Unloads application routes left behind by Cloak processes that were killed
+/// mid-capture; PulseAudio keeps modules loaded after their client is gone.
+pub fn remove_stale_routes() -> Result<()> {
+    let mut pulse = PulseConnection::connect("cloak-route-cleanup")?;
+    let stale: Vec<u32> = pulse.request(|introspector, result| {
+        let mut stale = Vec::new();
+        introspector.get_module_info_list(move |item| match item {
+            ListResult::Item(info) => {
+                let owner = info
+                    .argument
+                    .as_deref()
+                    .and_then(|argument| argument.split_once(ROUTE_SINK_PREFIX))
+                    .map(|(_, rest)| {
+                        rest.chars()
+                            .take_while(char::is_ascii_digit)
+                            .collect::<String>()
+                    });
+                // Both the null sink and its loopback name the route's sink
+                if let Some(pid) = owner.filter(|pid| !pid.is_empty()) {
+                    if !std::path::Path::new("/proc").join(&pid).exists() {
+                        stale.push(info.index);
+                    }
+                }
+            }
+            ListResult::End | ListResult::Error => {
+                *result.borrow_mut() = Some(std::mem::take(&mut stale));
+            }
+        })
+    })?;
+
+    for module in stale {
+        let unloaded = pulse.request(|introspector, result| {
+            introspector.unload_module(module, move |success| {
+                *result.borrow_mut() = Some(success);
+            })
+        })?;
+        if !unloaded {
+            warn!("Failed to unload stale capture module {}", module);
+        }
+    }
+    Ok(())
+}
+
 pub struct SpeakerInput {
     source_name: Option<String>,
+    // Captures only this application (its PulseAudio application.name)
+    application: Option<String>,
 }
 
 impl SpeakerInput {
@@ -233,7 +664,23 @@ impl SpeakerInput {
             }
             _ => None,
         };
-        Ok(Self { source_name })
+        Ok(Self {
+            source_name,
+            application: None,
+        })
+    }
+
+    pub fn for_application(application: String) -> Result<Self> {
+        let playing = get_audio_applications()?
+            .iter()
+            .any(|app| app.name == application);
+        if !playing {
+            return Err(anyhow!("{} is not playing any audio", application));
+        }
+        Ok(Self {
+            source_name: None,
+            application: Some(application),
+        })
     }
 
     pub fn stream(self) -> SpeakerStream {
@@ -248,12 +695,33 @@ impl SpeakerInput {
         let queue_clone = sample_queue.clone();
         let waker_clone = waker_state.clone();
         let source_name = self.source_name;
+        let application = self.application;
 
         let mut capture_thread = Some(thread::spawn(move || {
+            // PipeWire is recorded natively when it runs; PulseAudio covers the
+            // rest, and application capture, which routes through its modules
+            #[cfg(feature = "pipewire")]
+            if application.is_none() && super::pipewire::is_available() {
+                let target = source_name
+                    .as_deref()
+                    .and_then(|source| source.strip_suffix(".monitor"));
+                match super::pipewire::capture_loop(
+                    &queue_clone,
+                    &waker_clone,
+                    target,
+                    DEFAULT_SAMPLE_RATE,
+                    &init_tx,
+                ) {
+                    Ok(()) => return,
+                    Err(e) => warn!("PipeWire capture unavailable, using PulseAudio: {}", e),
+                }
+            }
+
             if let Err(e) = SpeakerStream::capture_audio_loop(
                 queue_clone,
                 waker_clone,
                 source_name.as_deref(),
+                application.as_deref(),
                 init_tx,
             ) {
                 eprintln!("Audio capture loop failed: {}", e);
@@ -273,14 +741,7 @@ impl SpeakerInput {
         };
 
         if !init_success {
-            {
-                let mut state = waker_state.lock().unwrap();
-                state.shutdown = true;
-                if let Some(waker) = state.waker.take() {
-                    drop(state);
-                    waker.wake();
-                }
-            }
+            shut_down(&waker_state);
 
             if let Some(handle) = capture_thread.take() {
                 let _ = handle.join();
@@ -296,10 +757,10 @@ impl SpeakerInput {
     }
 }
 
-struct WakerState {
+pub(super) struct WakerState {
     waker: Option<Waker>,
     has_data: bool,
-    shutdown: bool,
+    pub(super) shutdown: bool,
 }
 
 pub struct SpeakerStream {
@@ -314,10 +775,16 @@ impl SpeakerStream {
         self.sample_rate
     }
 
+    // PulseAudio mixes the monitor down to the mono spec below
+    pub fn channels(&self) -> u16 {
+        1
+    }
+
     fn capture_audio_loop(
         sample_queue: Arc<Mutex<VecDeque<f32>>>,
         waker_state: Arc<Mutex<WakerState>>,
         source_name: Option<&str>,
+        application: Option<&str>,
         init_tx: std::sync::mpsc::Sender<Result<u32>>,
     ) -> Result<()> {
         let spec = Spec {
@@ -331,8 +798,20 @@ impl SpeakerStream {
             return Err(anyhow!("Invalid audio specification"));
         }
 
-        let final_source = source_name
-            .map(|s| s.to_string())
+        // Held until capture stops, which puts the application's streams back
+        let route = match application.map(ApplicationRoute::create).transpose() {
+            Ok(route) => route,
+            Err(e) => {
+                error!("[capture_audio_loop] Failed to isolate application: {}", e);
+                let _ = init_tx.send(Err(e));
+                return Ok(());
+            }
+        };
+
+        let final_source = route
+            .as_ref()
+            .map(|route| route.monitor())
+            .or_else(|| source_name.map(|s| s.to_string()))
             .or_else(get_default_monitor_source);
 
         let init_result: Result<(Simple, u32)> = (|| {
@@ -379,40 +858,7 @@ impl SpeakerStream {
                                 })
                                 .collect();
 
-                            if !samples.is_empty() {
-                                // Consistent buffer overflow handling
-                                let dropped = {
-                                    let mut queue = sample_queue.lock().unwrap();
-                                    let max_buffer_size = 131072; // 128KB buffer (matching macOS/Windows)
-
-                                    queue.extend(samples.iter());
-
-                                    // If buffer exceeds maximum, drop oldest samples
-                                    if queue.len() > max_buffer_size {
-                                        let to_drop = queue.len() - max_buffer_size;
-                                        queue.drain(0..to_drop);
-                                        to_drop
-                                    } else {
-                                        0
-                                    }
-                                };
-
-                                if dropped > 0 {
-                                    warn!("[capture_audio_loop] Linux buffer overflow - dropped {} samples", dropped);
-                                }
-
-                                // Wake up consumer
-                                {
-                                    let mut state = waker_state.lock().unwrap();
-                                    if !state.has_data {
-                                        state.has_data = true;
-                                        if let Some(waker) = state.waker.take() {
-                                            drop(state);
-                                            waker.wake();
-                                        }
-                                    }
-                                }
-                            }
+                            push_samples(&sample_queue, &waker_state, &samples);
                         }
                         Err(e) => {
                             error!("[capture_audio_loop] PulseAudio read error: {}", e);
@@ -430,6 +876,61 @@ impl SpeakerStream {
     }
 }
 
+// Queues captured samples for the stream and wakes its consumer
+pub(super) fn push_samples(
+    sample_queue: &Mutex<VecDeque<f32>>,
+    waker_state: &Mutex<WakerState>,
+    samples: &[f32],
+) {
+    if samples.is_empty() {
+        return;
+    }
+
+    // Consistent buffer overflow handling
+    let dropped = {
+        let mut queue = sample_queue.lock().unwrap();
+        let max_buffer_size = 131072; // 128KB buffer (matching macOS/Windows)
+
+        queue.extend(samples.iter());
+
+        // If buffer exceeds maximum, drop oldest samples
+        if queue.len() > max_buffer_size {
+            let to_drop = queue.len() - max_buffer_size;
+            queue.drain(0..to_drop);
+            to_drop
+        } else {
+            0
+        }
+    };
+
+    if dropped > 0 {
+        warn!(
+            "[capture_audio_loop] Linux buffer overflow - dropped {} samples",
+            dropped
+        );
+    }
+
+    // Wake up consumer
+    let mut state = waker_state.lock().unwrap();
+    if !state.has_data {
+        state.has_data = true;
+        if let Some(waker) = state.waker.take() {
+            drop(state);
+            waker.wake();
+        }
+    }
+}
+
+// Ends the stream for its consumer
+pub(super) fn shut_down(waker_state: &Mutex<WakerState>) {
+    let mut state = waker_state.lock().unwrap();
+    state.shutdown = true;
+    if let Some(waker) = state.waker.take() {
+        drop(state);
+        waker.wake();
+    }
+}
+
 fn get_default_monitor_source() -> Option<String> {
     Some("@DEFAULT_MONITOR@".to_string())
 }
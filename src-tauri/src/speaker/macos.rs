@@ -151,12 +151,17 @@ pub struct SpeakerStream {
     _tap: ca::TapGuard,
     waker_state: Arc<Mutex<WakerState>>,
     current_sample_rate: Arc<AtomicU32>,
+    channels: u16,
 }
 
 impl SpeakerStream {
     pub fn sample_rate(&self) -> u32 {
         self.current_sample_rate.load(Ordering::Acquire)
     }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
 }
 
 struct Ctx {
@@ -330,6 +335,13 @@ impl SpeakerInput {
         let asbd = self.tap.asbd().unwrap();
 
         let format = av::AudioFormat::with_asbd(&asbd).unwrap();
+        // The stereo tap is read from its first buffer: interleaved formats
+        // carry every channel there, non-interleaved ones only the first
+        let channels = if format.is_interleaved() {
+            format.channel_count().max(1) as u16
+        } else {
+            1
+        };
 
         let buffer_size = 1024 * 128;
         let rb = HeapRb::<f32>::new(buffer_size);
@@ -360,6 +372,7 @@ impl SpeakerInput {
             _tap: self.tap,
             waker_state,
             current_sample_rate,
+            channels,
         }
     }
 }
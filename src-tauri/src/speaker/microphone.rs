@@ -0,0 +1,248 @@
+// Cloak microphone input via cpal, downmixed to a mono f32 stream
+use anyhow::{anyhow, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{SampleFormat, StreamConfig};
+use futures_util::Stream;
+use std::collections::VecDeque;
+use std::sync::{mpsc, Arc, Mutex};
+use std::task::{Poll, Waker};
+use std::thread;
+use std::time::Duration;
+use tracing::{error, warn};
+
+// Same cap as the speaker streams (~3s at 44.1 kHz)
+const MAX_BUFFER_SIZE: usize = 131072;
+
+pub struct MicrophoneInput {
+    device_name: Option<String>,
+}
+
+impl MicrophoneInput {
+    /// `device_name` is matched against cpal device names; the default input
+    /// device is used when it is missing or not found.
+    pub fn new(device_name: Option<String>) -> Self {
+        let device_name = device_name.filter(|name| !name.is_empty() && name != "default");
+        Self { device_name }
+    }
+
+    // Opens the device on a dedicated thread (cpal streams are not Send)
+    pub fn stream(self) -> Result<MicrophoneStream> {
+        let sample_queue = Arc::new(Mutex::new(VecDeque::new()));
+        let waker_state = Arc::new(Mutex::new(WakerState {
+            waker: None,
+            has_data: false,
+            shutdown: false,
+        }));
+        let (init_tx, init_rx) = mpsc::channel();
+
+        let queue_clone = sample_queue.clone();
+        let waker_clone = waker_state.clone();
+        let device_name = self.device_name;
+
+        let capture_thread = thread::spawn(move || {
+            if let Err(e) =
+                MicrophoneStream::capture_loop(queue_clone, waker_clone, device_name, init_tx)
+            {
+                error!("Microphone capture loop failed: {}", e);
+            }
+        });
+
+        let sample_rate = match init_rx.recv_timeout(Duration::from_secs(5)) {
+            Ok(Ok(sr)) => sr,
+            Ok(Err(e)) => {
+                let _ = capture_thread.join();
+                return Err(e);
+            }
+            Err(_) => {
+                waker_state.lock().unwrap().shutdown = true;
+                return Err(anyhow!("Timed out opening the microphone"));
+            }
+        };
+
+        Ok(MicrophoneStream {
+            sample_queue,
+            waker_state,
+            capture_thread: Some(capture_thread),
+            sample_rate,
+        })
+    }
+}
+
+struct WakerState {
+    waker: Option<Waker>,
+    has_data: bool,
+    shutdown: bool,
+}
+
+pub struct MicrophoneStream {
+    sample_queue: Arc<Mutex<VecDeque<f32>>>,
+    waker_state: Arc<Mutex<WakerState>>,
+    capture_thread: Option<thread::JoinHandle<()>>,
+    sample_rate: u32,
+}
+
+impl MicrophoneStream {
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn capture_loop(
+        sample_queue: Arc<Mutex<VecDeque<f32>>>,
+        waker_state: Arc<Mutex<WakerState>>,
+        device_name: Option<String>,
+        init_tx: mpsc::Sender<Result<u32>>,
+    ) -> Result<()> {
+        let init_result = (|| -> Result<(cpal::Stream, u32)> {
+            let host = cpal::default_host();
+            let named = device_name.as_ref().and_then(|name| {
+                let found = host
+                    .input_devices()
+                    .ok()?
+                    .find(|d| d.name().map(|n| &n == name).unwrap_or(false));
+                if found.is_none() {
+                    warn!("Microphone '{}' not found, using the default input", name);
+                }
+                found
+            });
+            let device = named
+                .or_else(|| host.default_input_device())
+                .ok_or_else(|| anyhow!("No microphone available"))?;
+
+            let supported = device
+                .default_input_config()
+                .map_err(|e| anyhow!("Failed to read microphone config: {}", e))?;
+            let sample_format = supported.sample_format();
+            let config: StreamConfig = supported.into();
+            let channels = config.channels.max(1) as usize;
+            let sample_rate = config.sample_rate.0;
+
+            let queue = sample_queue.clone();
+            let waker = waker_state.clone();
+            let err_fn = |e| error!("Microphone stream error: {}", e);
+
+            let stream = match sample_format {
+                SampleFormat::F32 => device.build_input_stream(
+                    &config,
+                    move |data: &[f32], _| push_frames(&queue, &waker, data, channels, |s| s),
+                    err_fn,
+                    None,
+                ),
+                SampleFormat::I16 => device.build_input_stream(
+                    &config,
+                    move |data: &[i16], _| {
+                        push_frames(&queue, &waker, data, channels, |s| {
+                            s as f32 / i16::MAX as f32
+                        })
+                    },
+                    err_fn,
+                    None,
+                ),
+                SampleFormat::U16 => device.build_input_stream(
+                    &config,
+                    move |data: &[u16], _| {
+                        push_frames(&queue, &waker, data, channels, |s| {
+                            (s as f32 - 32768.0) / 32768.0
+                        })
+                    },
+                    err_fn,
+                    None,
+                ),
+                other => return Err(anyhow!("Unsupported microphone sample format: {:?}", other)),
+            }
+            .map_err(|e| anyhow!("Failed to open microphone: {}", e))?;
+
+            stream
+                .play()
+                .map_err(|e| anyhow!("Failed to start microphone: {}", e))?;
+            Ok((stream, sample_rate))
+        })();
+
+        match init_result {
+            Ok((stream, sample_rate)) => {
+                let _ = init_tx.send(Ok(sample_rate));
+                // The stream runs on cpal's callback thread; keep it alive until dropped
+                while !waker_state.lock().unwrap().shutdown {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                drop(stream);
+            }
+            Err(e) => {
+                let _ = init_tx.send(Err(e));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Averages interleaved frames to mono and queues them for the consumer
+fn push_frames<T: Copy>(
+    sample_queue: &Mutex<VecDeque<f32>>,
+    waker_state: &Mutex<WakerState>,
+    data: &[T],
+    channels: usize,
+    to_f32: impl Fn(T) -> f32,
+) {
+    if data.is_empty() {
+        return;
+    }
+
+    {
+        let mut queue = sample_queue.lock().unwrap();
+        queue.extend(
+            data.chunks(channels)
+                .map(|frame| frame.iter().map(|&s| to_f32(s)).sum::<f32>() / frame.len() as f32),
+        );
+        if queue.len() > MAX_BUFFER_SIZE {
+            let to_drop = queue.len() - MAX_BUFFER_SIZE;
+            queue.drain(0..to_drop);
+            warn!("Microphone buffer overflow - dropped {} samples", to_drop);
+        }
+    }
+
+    let mut state = waker_state.lock().unwrap();
+    if !state.has_data {
+        state.has_data = true;
+        if let Some(waker) = state.waker.take() {
+            drop(state);
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for MicrophoneStream {
+    fn drop(&mut self) {
+        {
+            let mut state = self.waker_state.lock().unwrap();
+            state.shutdown = true;
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+        if let Some(thread) = self.capture_thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Stream for MicrophoneStream {
+    type Item = f32;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let mut queue = self.sample_queue.lock().unwrap();
+        if let Some(sample) = queue.pop_front() {
+            return Poll::Ready(Some(sample));
+        }
+
+        let mut state = self.waker_state.lock().unwrap();
+        if state.shutdown {
+            return Poll::Ready(None);
+        }
+
+        state.has_data = false;
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
@@ -2,6 +2,9 @@ use anyhow::Result;
 use futures_util::Stream;
 use serde::{Deserialize, Serialize};
 use std::pin::Pin;
+#[cfg(target_os = "linux")]
+use std::sync::Arc;
+use watch::WatchThread;
 
 #[cfg(target_os = "macos")]
 mod macos;
@@ -17,11 +20,53 @@ use windows::{SpeakerInput as PlatformSpeakerInput, SpeakerStream as PlatformSpe
 mod linux;
 #[cfg(target_os = "linux")]
 use linux::{SpeakerInput as PlatformSpeakerInput, SpeakerStream as PlatformSpeakerStream};
+// Native PipeWire capture, used over PulseAudio when PipeWire is running
+#[cfg(all(target_os = "linux", feature = "pipewire"))]
+mod pipewire;
 
+mod aec;
 mod commands;
+mod denoise;
+mod diarization;
+mod dsp;
+mod microphone;
+mod segments;
+mod silero;
+mod switch;
+mod vad;
+mod watch;
 
 // Re-export commands for tauri handler
+pub use aec::{EchoCancellationConfig, EchoCanceller, EchoReference, ReferenceTap};
 pub use commands::*;
+pub use denoise::{Denoiser, NoiseSuppressionConfig};
+pub use diarization::{speaker_label, DiarizationConfig, Diarizer};
+pub use dsp::{DspStream, DEFAULT_TARGET_RATE};
+pub use microphone::{MicrophoneInput, MicrophoneStream};
+pub use segments::SegmentStore;
+pub use silero::{SileroVad, SileroVadConfig};
+pub use switch::{BoxedAudioStream, StreamSwitch, SwitchableStream};
+pub use vad::{
+    create_detector, speech_regions, EnergyVad, SpeechRegion, VadEngine, VoiceActivityDetector,
+};
+
+// Who is talking in a dual capture: the microphone is the user, system audio
+// is everyone else on the call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioSource {
+    Me,
+    Them,
+}
+
+impl AudioSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            AudioSource::Me => "Me",
+            AudioSource::Them => "Them",
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioDevice {
@@ -30,6 +75,16 @@ pub struct AudioDevice {
     pub is_default: bool,
 }
 
+// A stream an application is playing, which can be captured on its own
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioApplication {
+    pub id: String,
+    pub name: String,
+    pub binary: Option<String>,
+    // What is playing, e.g. a track or tab title
+    pub media: Option<String>,
+}
+
 #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
 pub(crate) fn list_input_devices() -> Result<Vec<AudioDevice>> {
     #[cfg(target_os = "macos")]
@@ -54,6 +109,99 @@ pub(crate) fn list_output_devices() -> Result<Vec<AudioDevice>> {
     return linux::get_output_devices();
 }
 
+// Only Linux can route a single application for capture
+#[cfg(target_os = "linux")]
+pub(crate) fn list_audio_applications() -> Result<Vec<AudioApplication>> {
+    linux::get_audio_applications()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn list_audio_applications() -> Result<Vec<AudioApplication>> {
+    Ok(vec![])
+}
+
+// Application capture leaves PulseAudio modules loaded if Cloak is killed while
+// it runs, keeping the application's audio on a sink nobody plays. Run at startup
+pub fn remove_stale_capture_routes() {
+    #[cfg(target_os = "linux")]
+    if let Err(e) = linux::remove_stale_routes() {
+        tracing::warn!("Failed to clean up stale application capture: {}", e);
+    }
+}
+
+// Keeps device change notifications coming until dropped
+pub struct DeviceWatcher {
+    _thread: WatchThread,
+}
+
+// Calls `on_change` when an audio device is plugged in or removed, or the
+// default input or output changes
+#[cfg(target_os = "linux")]
+pub(crate) fn watch_devices(on_change: impl Fn() + Send + Sync + 'static) -> Result<DeviceWatcher> {
+    let on_change = Arc::new(on_change);
+    // Preferred even on PipeWire, whose registry does not report default changes
+    let pulse = linux::watch_devices({
+        let on_change = on_change.clone();
+        move || on_change()
+    });
+    let thread = match pulse {
+        Ok(thread) => thread,
+        #[cfg(feature = "pipewire")]
+        Err(e) if pipewire::is_available() => {
+            tracing::warn!(
+                "PulseAudio device watcher unavailable, using PipeWire: {}",
+                e
+            );
+            pipewire::watch_devices(move || on_change())?
+        }
+        Err(e) => return Err(e),
+    };
+    Ok(DeviceWatcher { _thread: thread })
+}
+
+// macOS and Windows don't have native notifications hooked up yet (Core Audio
+// property listeners and IMMNotificationClient would provide them), so these
+// platforms poll: the device lists are compared every `DEVICE_POLL`
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn watch_devices(on_change: impl Fn() + Send + Sync + 'static) -> Result<DeviceWatcher> {
+    use std::sync::atomic::Ordering;
+    use std::time::Instant;
+
+    // Ids with their default flags, so a new default counts as a change
+    fn snapshot() -> Vec<(String, bool)> {
+        let inputs = list_input_devices().unwrap_or_default();
+        let outputs = list_output_devices().unwrap_or_default();
+        inputs
+            .into_iter()
+            .chain(outputs)
+            .map(|device| (device.id, device.is_default))
+            .collect()
+    }
+
+    let thread = WatchThread::spawn("device_poller", move |stop, ready| {
+        let mut devices = snapshot();
+        ready();
+        let mut last_poll = Instant::now();
+        while !stop.load(Ordering::Acquire) {
+            std::thread::sleep(watch::STOP_POLL);
+            if last_poll.elapsed() < DEVICE_POLL {
+                continue;
+            }
+            last_poll = Instant::now();
+            let current = snapshot();
+            if current != devices {
+                devices = current;
+                on_change();
+            }
+        }
+        Ok(())
+    })?;
+    Ok(DeviceWatcher { _thread: thread })
+}
+
+#[cfg(not(target_os = "linux"))]
+const DEVICE_POLL: std::time::Duration = std::time::Duration::from_secs(2);
+
 #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
 pub(crate) fn list_input_devices() -> Result<Vec<AudioDevice>> {
     Ok(vec![])
@@ -85,6 +233,21 @@ impl SpeakerInput {
         Ok(Self { inner })
     }
 
+    // Creates a speaker input that captures only `application`, as named in
+    // `list_audio_applications`
+    #[cfg(target_os = "linux")]
+    pub fn new_for_application(application: String) -> Result<Self> {
+        let inner = PlatformSpeakerInput::for_application(application)?;
+        Ok(Self { inner })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn new_for_application(_application: String) -> Result<Self> {
+        Err(anyhow::anyhow!(
+            "Capturing a single application is only supported on Linux"
+        ))
+    }
+
     #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
     pub fn new() -> Result<Self> {
         Err(anyhow::anyhow!(
@@ -145,4 +308,13 @@ impl SpeakerStream {
         #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
         0
     }
+
+    // Interleaved channels per frame; `DspStream` mixes them down to mono
+    pub fn channels(&self) -> u16 {
+        #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+        return self.inner.channels();
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+        1
+    }
 }
@@ -0,0 +1,223 @@
+// Cloak PipeWire capture: records system audio straight from the PipeWire graph
+// rather than through its PulseAudio compatibility layer, which adds latency
+// and hides failed reads, and watches the graph for devices coming and going.
+//
+// Capture streams let the session manager link them, so when the device being
+// recorded goes away the stream follows the new default instead of going silent.
+use super::linux::{push_samples, shut_down, WakerState};
+use super::watch::{WatchThread, STOP_POLL};
+use anyhow::{anyhow, Result};
+use pipewire as pw;
+use pw::spa;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashSet, VecDeque};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+/// Whether a PipeWire daemon is running for this session.
+pub fn is_available() -> bool {
+    let Some(runtime_dir) =
+        std::env::var_os("PIPEWIRE_RUNTIME_DIR").or_else(|| std::env::var_os("XDG_RUNTIME_DIR"))
+    else {
+        return false;
+    };
+    let remote = std::env::var("PIPEWIRE_REMOTE").unwrap_or_else(|_| "pipewire-0".to_string());
+    Path::new(&runtime_dir).join(remote).exists()
+}
+
+/// Records the monitor of sink `target` (the default sink for `None`) as mono
+/// f32 at `sample_rate` until the stream is shut down or fails. Returns an error
+/// only when capture could not start, before anything is sent on `init_tx`.
+pub(super) fn capture_loop(
+    sample_queue: &Arc<Mutex<VecDeque<f32>>>,
+    waker_state: &Arc<Mutex<WakerState>>,
+    target: Option<&str>,
+    sample_rate: u32,
+    init_tx: &Sender<Result<u32>>,
+) -> Result<()> {
+    pw::init();
+    let mainloop = pw::main_loop::MainLoop::new(None)?;
+    let context = pw::context::Context::new(&mainloop)?;
+    let core = context.connect(None)?;
+
+    let mut props = pw::properties::properties! {
+        "media.type" => "Audio",
+        "media.category" => "Capture",
+        "node.name" => "cloak",
+        // Record what the sink plays rather than a source
+        "stream.capture.sink" => "true",
+    };
+    if let Some(target) = target {
+        props.insert("target.object", target);
+    }
+    let stream = pw::stream::Stream::new(&core, "System Audio Capture", props)?;
+
+    let _listener = stream
+        .add_local_listener_with_user_data(())
+        .state_changed({
+            let mainloop = mainloop.clone();
+            move |_, _, _, state| {
+                if let pw::stream::StreamState::Error(e) = state {
+                    error!("[pipewire] Capture stream failed: {}", e);
+                    mainloop.quit();
+                }
+            }
+        })
+        .process({
+            let sample_queue = sample_queue.clone();
+            let waker_state = waker_state.clone();
+            move |stream, _| {
+                let Some(mut buffer) = stream.dequeue_buffer() else {
+                    return;
+                };
+                let Some(data) = buffer.datas_mut().first_mut() else {
+                    return;
+                };
+                let size = data.chunk().size() as usize;
+                let Some(bytes) = data.data() else {
+                    return;
+                };
+                let samples: Vec<f32> = bytes[..size.min(bytes.len())]
+                    .chunks_exact(4)
+                    .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                    .collect();
+                push_samples(&sample_queue, &waker_state, &samples);
+            }
+        })
+        .register()?;
+
+    // PipeWire converts whatever the sink plays to this format
+    let mut format = spa::param::audio::AudioInfoRaw::new();
+    format.set_format(spa::param::audio::AudioFormat::F32LE);
+    format.set_rate(sample_rate);
+    format.set_channels(1);
+    let format = spa::pod::Value::Object(spa::pod::Object {
+        type_: spa::utils::SpaTypes::ObjectParamFormat.as_raw(),
+        id: spa::param::ParamType::EnumFormat.as_raw(),
+        properties: format.into(),
+    });
+    let bytes =
+        spa::pod::serialize::PodSerializer::serialize(std::io::Cursor::new(Vec::new()), &format)
+            .map_err(|e| anyhow!("Failed to build PipeWire format: {:?}", e))?
+            .0
+            .into_inner();
+    let mut params = [spa::pod::Pod::from_bytes(&bytes)
+        .ok_or_else(|| anyhow!("Failed to build PipeWire format"))?];
+
+    stream.connect(
+        spa::utils::Direction::Input,
+        None,
+        pw::stream::StreamFlags::AUTOCONNECT | pw::stream::StreamFlags::MAP_BUFFERS,
+        &mut params,
+    )?;
+
+    let _timer = stop_when(&mainloop, {
+        let waker_state = waker_state.clone();
+        move || waker_state.lock().unwrap().shutdown
+    })?;
+
+    let _ = init_tx.send(Ok(sample_rate));
+    mainloop.run();
+
+    // Stopped, or the stream failed: either way the consumer sees it end
+    shut_down(waker_state);
+    Ok(())
+}
+
+/// Watches the PipeWire graph for audio devices being added or removed, calling
+/// `on_change` from its own thread until the returned thread is dropped.
+pub(super) fn watch_devices(on_change: impl Fn() + Send + 'static) -> Result<WatchThread> {
+    WatchThread::spawn("pipewire_device_watcher", move |stop, ready| {
+        watch_loop(stop, on_change, ready)
+    })
+}
+
+fn watch_loop(
+    stop: &Arc<AtomicBool>,
+    on_change: impl Fn() + 'static,
+    ready: &dyn Fn(),
+) -> Result<()> {
+    pw::init();
+    let mainloop = pw::main_loop::MainLoop::new(None)?;
+    let context = pw::context::Context::new(&mainloop)?;
+    let core = context.connect(None)?;
+    let registry = core.get_registry()?;
+
+    // Devices already present are announced before the first sync completes
+    let ready = Rc::new(Cell::new(false));
+    let pending = core.sync(0)?;
+    let _core_listener = core
+        .add_listener_local()
+        .done({
+            let ready = ready.clone();
+            move |id, seq| {
+                if id == pw::core::PW_ID_CORE && seq == pending {
+                    ready.set(true);
+                }
+            }
+        })
+        .register();
+
+    // Removals only carry the id, so remember which globals are devices
+    let devices = Rc::new(RefCell::new(HashSet::new()));
+    let on_change = Rc::new(on_change);
+    let _registry_listener = registry
+        .add_listener_local()
+        .global({
+            let devices = devices.clone();
+            let on_change = on_change.clone();
+            move |global| {
+                let is_device = global.type_ == pw::types::ObjectType::Node
+                    && global
+                        .props
+                        .and_then(|props| props.get("media.class"))
+                        .is_some_and(|class| matches!(class, "Audio/Sink" | "Audio/Source"));
+                if is_device {
+                    devices.borrow_mut().insert(global.id);
+                    if ready.get() {
+                        on_change();
+                    }
+                }
+            }
+        })
+        .global_remove(move |id| {
+            if devices.borrow_mut().remove(&id) {
+                on_change();
+            }
+        })
+        .register();
+
+    let _timer = stop_when(&mainloop, {
+        let stop = stop.clone();
+        move || stop.load(Ordering::Acquire)
+    })?;
+
+    ready();
+    mainloop.run();
+    Ok(())
+}
+
+// Quits `mainloop` once `should_stop` returns true; the loop stops checking when
+// the returned timer is dropped
+fn stop_when<'l>(
+    mainloop: &'l pw::main_loop::MainLoop,
+    should_stop: impl Fn() -> bool + 'static,
+) -> Result<impl Sized + 'l> {
+    let timer = mainloop.loop_().add_timer({
+        let mainloop = mainloop.clone();
+        move |_| {
+            if should_stop() {
+                mainloop.quit();
+            }
+        }
+    });
+    timer
+        .update_timer(Some(STOP_POLL), Some(STOP_POLL))
+        .into_result()
+        .map_err(|e| anyhow!("Failed to start PipeWire timer: {}", e))?;
+    Ok(timer)
+}
@@ -0,0 +1,42 @@
+// Cloak speech segment store: encoded utterances stay in Rust and the webview
+// refers to them by ID, so audio never crosses the bridge as base64
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+// Segments nobody released are dropped oldest first past this many
+const MAX_SEGMENTS: usize = 32;
+
+#[derive(Default)]
+pub struct SegmentStore {
+    segments: Mutex<VecDeque<(String, Arc<[u8]>)>>,
+}
+
+impl SegmentStore {
+    /// Keeps an encoded segment and returns its ID.
+    pub fn insert(&self, audio: Vec<u8>) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        if let Ok(mut segments) = self.segments.lock() {
+            while segments.len() >= MAX_SEGMENTS {
+                segments.pop_front();
+            }
+            segments.push_back((id.clone(), audio.into()));
+        }
+        id
+    }
+
+    /// Looks a segment up without removing it, so a failed transcription can
+    /// be retried with another provider.
+    pub fn get(&self, id: &str) -> Option<Arc<[u8]>> {
+        let segments = self.segments.lock().ok()?;
+        segments
+            .iter()
+            .find(|(segment_id, _)| segment_id == id)
+            .map(|(_, audio)| audio.clone())
+    }
+
+    pub fn remove(&self, id: &str) {
+        if let Ok(mut segments) = self.segments.lock() {
+            segments.retain(|(segment_id, _)| segment_id != id);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+// Cloak Silero VAD: the Silero v5 speech model run on the CPU with ONNX Runtime
+use crate::speaker::vad::VoiceActivityDetector;
+use crate::stt::audio::LinearResampler;
+use ort::session::Session;
+use ort::value::Tensor;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+// The model takes 512-sample windows at 16 kHz, preceded by the last 64
+// samples of the previous window
+const MODEL_RATE: u32 = 16000;
+const WINDOW: usize = 512;
+const CONTEXT: usize = 64;
+const STATE_LEN: usize = 2 * 128;
+// Speech ends only once the probability falls this far below the threshold
+const HYSTERESIS: f32 = 0.15;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SileroVadConfig {
+    /// ONNX model file; defaults to the first `*.onnx` in `<app data>/models/vad`
+    pub model_path: Option<String>,
+    /// Speech probability (0-1) at which speech starts
+    pub threshold: f32,
+}
+
+impl Default for SileroVadConfig {
+    fn default() -> Self {
+        Self {
+            model_path: None,
+            threshold: 0.5,
+        }
+    }
+}
+
+pub struct SileroVad {
+    session: Session,
+    sample_rate: u32,
+    resampler: LinearResampler,
+    pending: Vec<f32>,
+    context: Vec<f32>,
+    state: Vec<f32>,
+    threshold: f32,
+    speaking: bool,
+    failed: bool,
+}
+
+impl SileroVad {
+    /// Loads the model for a stream at `sample_rate`; audio is resampled to
+    /// 16 kHz internally.
+    pub fn new(model_path: &Path, threshold: f32, sample_rate: u32) -> Result<Self, String> {
+        let session = Session::builder()
+            .and_then(|builder| builder.with_intra_threads(1))
+            .and_then(|builder| builder.commit_from_file(model_path))
+            .map_err(|e| format!("Failed to load Silero VAD model: {}", e))?;
+
+        Ok(Self {
+            session,
+            sample_rate,
+            resampler: LinearResampler::new(sample_rate, MODEL_RATE),
+            pending: Vec::with_capacity(WINDOW * 2),
+            context: vec![0.0; CONTEXT],
+            state: vec![0.0; STATE_LEN],
+            threshold,
+            speaking: false,
+            failed: false,
+        })
+    }
+
+    // Speech probability of one window; updates the recurrent state
+    fn infer(&mut self, window: &[f32]) -> ort::Result<f32> {
+        let mut input = Vec::with_capacity(CONTEXT + WINDOW);
+        input.extend_from_slice(&self.context);
+        input.extend_from_slice(window);
+
+        let outputs = self.session.run(ort::inputs![
+            "input" => Tensor::from_array(([1usize, CONTEXT + WINDOW], input))?,
+            "state" => Tensor::from_array(([2usize, 1, 128], self.state.clone()))?,
+            "sr" => Tensor::from_array(((), vec![MODEL_RATE as i64]))?,
+        ])?;
+
+        let (_, probability) = outputs["output"].try_extract_tensor::<f32>()?;
+        let probability = probability.first().copied().unwrap_or(0.0);
+        let (_, state) = outputs["stateN"].try_extract_tensor::<f32>()?;
+        if state.len() == STATE_LEN {
+            self.state.copy_from_slice(state);
+        }
+        self.context.copy_from_slice(&window[WINDOW - CONTEXT..]);
+        Ok(probability)
+    }
+}
+
+impl VoiceActivityDetector for SileroVad {
+    // Chunks shorter than a model window keep the last decision
+    fn is_speech(&mut self, chunk: &[f32]) -> bool {
+        let resampled = self.resampler.process(chunk);
+        self.pending.extend(resampled);
+
+        while self.pending.len() >= WINDOW {
+            let window: Vec<f32> = self.pending.drain(..WINDOW).collect();
+            match self.infer(&window) {
+                Ok(probability) => {
+                    if probability >= self.threshold {
+                        self.speaking = true;
+                    } else if probability < (self.threshold - HYSTERESIS).max(0.01) {
+                        self.speaking = false;
+                    }
+                }
+                Err(e) => {
+                    if !self.failed {
+                        warn!("Silero VAD inference failed: {}", e);
+                        self.failed = true;
+                    }
+                    self.speaking = false;
+                }
+            }
+        }
+        self.speaking
+    }
+
+    fn reset(&mut self) {
+        self.resampler = LinearResampler::new(self.sample_rate, MODEL_RATE);
+        self.pending.clear();
+        self.context.iter_mut().for_each(|s| *s = 0.0);
+        self.state.iter_mut().for_each(|s| *s = 0.0);
+        self.speaking = false;
+    }
+}
+
+pub fn resolve_model_path(app: &AppHandle, config: &SileroVadConfig) -> Result<PathBuf, String> {
+    if let Some(path) = config
+        .model_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+    {
+        let path = PathBuf::from(path);
+        if !path.is_file() {
+            return Err(format!("Silero VAD model not found at {}", path.display()));
+        }
+        return Ok(path);
+    }
+
+    let models_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("models")
+        .join("vad");
+    find_model_in(&models_dir).ok_or_else(|| {
+        format!(
+            "No Silero VAD model selected. Choose an ONNX model file or place one in {}",
+            models_dir.display()
+        )
+    })
+}
+
+// First ONNX model (`*.onnx`) in `dir`, by file name
+fn find_model_in(dir: &Path) -> Option<PathBuf> {
+    let mut models: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("onnx"))
+        .collect();
+    models.sort();
+    models.into_iter().next()
+}
@@ -0,0 +1,78 @@
+// Cloak device switching: a capture stream whose device can be replaced while
+// it runs. Swapping underneath the VAD loop, rather than restarting capture,
+// keeps the utterance being recorded and the transcription session, so a
+// capture can follow a new default device in the middle of a sentence.
+use futures_util::Stream;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Waker};
+
+pub type BoxedAudioStream = Pin<Box<dyn Stream<Item = f32> + Send>>;
+
+#[derive(Default)]
+struct SwitchState {
+    next: Option<BoxedAudioStream>,
+    waker: Option<Waker>,
+}
+
+/// Hands a replacement device stream to a running `SwitchableStream`.
+#[derive(Clone)]
+pub struct StreamSwitch {
+    state: Weak<Mutex<SwitchState>>,
+}
+
+impl StreamSwitch {
+    /// Replaces the device stream, which must already be processed to the
+    /// capture's rate. Returns false once the capture has ended.
+    pub fn replace(&self, stream: BoxedAudioStream) -> bool {
+        let Some(state) = self.state.upgrade() else {
+            return false;
+        };
+        let mut state = state.lock().unwrap();
+        state.next = Some(stream);
+        if let Some(waker) = state.waker.take() {
+            drop(state);
+            waker.wake();
+        }
+        true
+    }
+}
+
+pub struct SwitchableStream {
+    current: BoxedAudioStream,
+    state: Arc<Mutex<SwitchState>>,
+}
+
+impl SwitchableStream {
+    pub fn new(stream: BoxedAudioStream) -> Self {
+        Self {
+            current: stream,
+            state: Arc::default(),
+        }
+    }
+
+    pub fn switch(&self) -> StreamSwitch {
+        StreamSwitch {
+            state: Arc::downgrade(&self.state),
+        }
+    }
+}
+
+impl Stream for SwitchableStream {
+    type Item = f32;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let previous = {
+            let mut state = this.state.lock().unwrap();
+            state.waker = Some(cx.waker().clone());
+            state
+                .next
+                .take()
+                .map(|next| std::mem::replace(&mut this.current, next))
+        };
+        // Stops the old device, outside the lock
+        drop(previous);
+        this.current.as_mut().poll_next(cx)
+    }
+}
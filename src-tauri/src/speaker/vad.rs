@@ -0,0 +1,124 @@
+// Cloak voice activity detection: interchangeable engines that tell the capture
+// loop whether each audio chunk contains speech
+use crate::speaker::silero::{self, SileroVad};
+use crate::speaker::VadConfig;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+pub trait VoiceActivityDetector: Send {
+    /// Whether a chunk of mono samples at the capture rate contains speech.
+    /// Chunks are fed in order, so engines may carry state between them.
+    fn is_speech(&mut self, chunk: &[f32]) -> bool;
+
+    /// Forgets any state carried between chunks.
+    fn reset(&mut self) {}
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VadEngine {
+    /// RMS and peak thresholds; no model needed
+    #[default]
+    Energy,
+    /// Silero neural VAD (ONNX), much better at ignoring music and noise
+    Silero,
+}
+
+/// Speech when either the chunk's RMS or its peak crosses a threshold.
+pub struct EnergyVad {
+    sensitivity_rms: f32,
+    peak_threshold: f32,
+}
+
+impl EnergyVad {
+    pub fn new(sensitivity_rms: f32, peak_threshold: f32) -> Self {
+        Self {
+            sensitivity_rms,
+            peak_threshold,
+        }
+    }
+}
+
+impl VoiceActivityDetector for EnergyVad {
+    fn is_speech(&mut self, chunk: &[f32]) -> bool {
+        let (rms, peak) = calculate_audio_metrics(chunk);
+        rms > self.sensitivity_rms || peak > self.peak_threshold
+    }
+}
+
+/// Builds the detector selected in `config` for a stream at `sample_rate`.
+pub fn create_detector(
+    app: &AppHandle,
+    config: &VadConfig,
+    sample_rate: u32,
+) -> Result<Box<dyn VoiceActivityDetector>, String> {
+    match config.engine {
+        VadEngine::Energy => Ok(Box::new(EnergyVad::new(
+            config.sensitivity_rms,
+            config.peak_threshold,
+        ))),
+        VadEngine::Silero => {
+            let model_path = silero::resolve_model_path(app, &config.silero)?;
+            let detector = SileroVad::new(&model_path, config.silero.threshold, sample_rate)?;
+            Ok(Box::new(detector))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeechRegion {
+    pub start_secs: f32,
+    pub end_secs: f32,
+}
+
+/// Runs a detector over a whole recording in `hop_size` chunks, merging
+/// consecutive speech chunks into regions. Lets engines be compared against
+/// WAV fixtures with known speech.
+pub fn speech_regions(
+    detector: &mut dyn VoiceActivityDetector,
+    samples: &[f32],
+    sample_rate: u32,
+    hop_size: usize,
+) -> Vec<SpeechRegion> {
+    let to_secs = |sample: usize| sample as f32 / sample_rate as f32;
+    let mut regions = Vec::new();
+    let mut start = None;
+
+    for (i, chunk) in samples.chunks(hop_size.max(1)).enumerate() {
+        let offset = i * hop_size.max(1);
+        match (detector.is_speech(chunk), start) {
+            (true, None) => start = Some(offset),
+            (false, Some(begin)) => {
+                regions.push(SpeechRegion {
+                    start_secs: to_secs(begin),
+                    end_secs: to_secs(offset),
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(begin) = start {
+        regions.push(SpeechRegion {
+            start_secs: to_secs(begin),
+            end_secs: to_secs(samples.len()),
+        });
+    }
+    regions
+}
+
+// Calculate RMS and peak (optimized)
+pub(crate) fn calculate_audio_metrics(chunk: &[f32]) -> (f32, f32) {
+    let mut sumsq = 0.0f32;
+    let mut peak = 0.0f32;
+
+    for &v in chunk {
+        let a = v.abs();
+        peak = peak.max(a);
+        sumsq += v * v;
+    }
+
+    let rms = (sumsq / chunk.len() as f32).sqrt();
+    (rms, peak)
+}
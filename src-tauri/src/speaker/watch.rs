@@ -0,0 +1,68 @@
+// Cloak device watching: each backend runs its device event loop on a thread of
+// its own, owned by a `WatchThread` that stops and joins it when dropped.
+use anyhow::{anyhow, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+use tracing::error;
+
+// How often a running loop checks whether it should stop
+pub(crate) const STOP_POLL: Duration = Duration::from_millis(100);
+
+pub(crate) struct WatchThread {
+    stop: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl WatchThread {
+    /// Runs `watch` on a new thread. `watch` calls `ready` once it is listening
+    /// and returns after `stop` is set. Waits for `ready`, failing with the error
+    /// `watch` returns before it; later errors are only logged.
+    pub(crate) fn spawn<F>(name: &'static str, watch: F) -> Result<Self>
+    where
+        F: FnOnce(&Arc<AtomicBool>, &dyn Fn()) -> Result<()> + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        let thread = thread::spawn({
+            let stop = stop.clone();
+            move || {
+                let ready = || {
+                    let _ = ready_tx.send(Ok(()));
+                };
+                if let Err(e) = watch(&stop, &ready) {
+                    // Nobody is waiting for the result once the watcher was ready
+                    if let Err(mpsc::SendError(Err(e))) = ready_tx.send(Err(e)) {
+                        error!("[{}] Stopped: {}", name, e);
+                    }
+                }
+            }
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok(())) => Ok(Self {
+                stop,
+                thread: Some(thread),
+            }),
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => {
+                let _ = thread.join();
+                Err(anyhow!("{} exited before it was ready", name))
+            }
+        }
+    }
+}
+
+impl Drop for WatchThread {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
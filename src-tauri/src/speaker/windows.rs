@@ -182,6 +182,11 @@ impl SpeakerStream {
         self.actual_sample_rate
     }
 
+    // The client is opened as mono; WASAPI autoconverts the mix format
+    pub fn channels(&self) -> u16 {
+        1
+    }
+
     fn capture_audio_loop(
         sample_queue: Arc<Mutex<VecDeque<f32>>>,
         waker_state: Arc<Mutex<WakerState>>,
@@ -0,0 +1,94 @@
+// AssemblyAI Universal Streaming (v3): raw PCM16 binary frames, JSON `Turn` messages
+use super::{RealtimeProtocol, StreamingSttConfig, TranscriptEvent};
+use reqwest::Url;
+use serde_json::Value;
+use tokio_tungstenite::tungstenite::Message;
+
+const DEFAULT_URL: &str = "wss://streaming.assemblyai.com/v3/ws";
+// 100 ms at 16 kHz; shorter chunks are rejected
+const SILENCE_SAMPLES: usize = 1600;
+// Longest silence AssemblyAI accepts before ending a turn by itself
+const MAX_TURN_SILENCE_MS: u32 = 10_000;
+
+pub struct AssemblyAiProtocol {
+    url: String,
+}
+
+impl AssemblyAiProtocol {
+    pub fn new(config: &StreamingSttConfig) -> Self {
+        let base = config.url_or(DEFAULT_URL.to_string());
+        let mut params = vec![
+            ("sample_rate", "16000".to_string()),
+            ("encoding", "pcm_s16le".to_string()),
+            ("format_turns", "true".to_string()),
+            // Turns end only on `ForceEndpoint`: Cloak's VAD ends utterances, and
+            // automatic turn ends would add finals no commit asked for
+            ("end_of_turn_confidence_threshold", "1".to_string()),
+            ("max_turn_silence", MAX_TURN_SILENCE_MS.to_string()),
+        ];
+        if let Some(model) = config.model.as_deref().filter(|m| !m.trim().is_empty()) {
+            params.push(("speech_model", model.trim().to_string()));
+        }
+        let url = Url::parse_with_params(&base, &params)
+            .map(|url| url.to_string())
+            .unwrap_or(base);
+        Self { url }
+    }
+}
+
+impl RealtimeProtocol for AssemblyAiProtocol {
+    fn name(&self) -> &'static str {
+        "AssemblyAI"
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    fn auth_headers(&self, api_key: &str) -> Vec<(&'static str, String)> {
+        vec![("Authorization", api_key.to_string())]
+    }
+
+    fn commit_message(&self) -> Option<Message> {
+        Some(Message::text(r#"{"type":"ForceEndpoint"}"#))
+    }
+
+    fn close_message(&self) -> Option<Message> {
+        Some(Message::text(r#"{"type":"Terminate"}"#))
+    }
+
+    // There is no keepalive message, so idle streams are kept open with silence
+    fn keepalive_message(&self) -> Option<Message> {
+        Some(self.audio_message(&[0; SILENCE_SAMPLES]))
+    }
+
+    fn parse_message(&mut self, text: &str) -> Result<Vec<TranscriptEvent>, String> {
+        let event: Value = serde_json::from_str(text)
+            .map_err(|e| format!("Failed to parse AssemblyAI message: {}", e))?;
+
+        if let Some(error) = event.get("error").and_then(|e| e.as_str()) {
+            return Err(format!("AssemblyAI error: {}", error));
+        }
+        if event.get("type").and_then(|t| t.as_str()) != Some("Turn") {
+            return Ok(vec![]);
+        }
+
+        let transcript = event
+            .get("transcript")
+            .and_then(|t| t.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string();
+
+        let flag = |key: &str| event.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+        // With `format_turns`, each turn ends with an unformatted and then a
+        // formatted message; only the formatted one is final
+        if flag("end_of_turn") && flag("turn_is_formatted") {
+            Ok(vec![TranscriptEvent::Final(transcript)])
+        } else if transcript.is_empty() {
+            Ok(vec![])
+        } else {
+            Ok(vec![TranscriptEvent::Partial(transcript)])
+        }
+    }
+}
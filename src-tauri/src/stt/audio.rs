@@ -0,0 +1,87 @@
+// Cloak STT audio helpers: WAV decoding and sample-rate conversion
+use hound::{SampleFormat, WavReader};
+use std::io::Cursor;
+
+/// Decodes a WAV file into mono f32 samples, averaging channels.
+/// Returns the samples and their sample rate.
+pub fn decode_wav(bytes: &[u8]) -> Result<(Vec<f32>, u32), String> {
+    let mut reader =
+        WavReader::new(Cursor::new(bytes)).map_err(|e| format!("Failed to read WAV: {}", e))?;
+    let spec = reader.spec();
+
+    let interleaved: Vec<f32> = match spec.sample_format {
+        SampleFormat::Float => reader
+            .samples::<f32>()
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to decode WAV samples: {}", e))?,
+        SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("Failed to decode WAV samples: {}", e))?
+        }
+    };
+
+    let channels = spec.channels.max(1) as usize;
+    let mono = if channels == 1 {
+        interleaved
+    } else {
+        interleaved
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            .collect()
+    };
+    Ok((mono, spec.sample_rate))
+}
+
+/// Converts a complete clip from `input_rate` to `output_rate`.
+pub fn resample(samples: &[f32], input_rate: u32, output_rate: u32) -> Vec<f32> {
+    LinearResampler::new(input_rate, output_rate).process(samples)
+}
+
+/// Linear interpolation between two sample rates. Carries the last sample
+/// across chunks so streamed frame boundaries stay continuous.
+pub struct LinearResampler {
+    step: f64,
+    position: f64,
+    previous: f32,
+}
+
+impl LinearResampler {
+    pub fn new(input_rate: u32, output_rate: u32) -> Self {
+        Self {
+            step: input_rate as f64 / output_rate as f64,
+            position: 1.0,
+            previous: 0.0,
+        }
+    }
+
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        if self.step == 1.0 {
+            return input.to_vec();
+        }
+
+        // Index 0 is the previous chunk's last sample, index k is input[k - 1]
+        let mut output = Vec::with_capacity((input.len() as f64 / self.step) as usize + 1);
+        while self.position < input.len() as f64 {
+            let index = self.position as usize;
+            let frac = (self.position - index as f64) as f32;
+            let a = if index == 0 {
+                self.previous
+            } else {
+                input[index - 1]
+            };
+            let b = input[index];
+            output.push(a + (b - a) * frac);
+            self.position += self.step;
+        }
+
+        self.position -= input.len() as f64;
+        if let Some(&last) = input.last() {
+            self.previous = last;
+        }
+        output
+    }
+}
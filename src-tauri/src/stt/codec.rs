@@ -0,0 +1,527 @@
+// Cloak upload codecs: speech clips as WAV, FLAC (lossless, roughly half the
+// size) or Ogg/Opus (lossy, about a tenth), and decoding them back
+use crate::stt::audio;
+use audiopus::coder::{Decoder, Encoder};
+use audiopus::packet::Packet;
+use audiopus::{Application, Bitrate, Channels, MutSignals, SampleRate};
+use hound::{WavSpec, WavWriter};
+use ogg::{PacketReader, PacketWriteEndInfo, PacketWriter};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioEncoding {
+    /// 16-bit PCM; accepted everywhere
+    #[default]
+    Wav,
+    Flac,
+    /// Opus in an Ogg container
+    Opus,
+}
+
+impl AudioEncoding {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            AudioEncoding::Wav => "audio/wav",
+            AudioEncoding::Flac => "audio/flac",
+            AudioEncoding::Opus => "audio/ogg",
+        }
+    }
+
+    /// Upload file name; providers use the extension to pick a decoder.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            AudioEncoding::Wav => "audio.wav",
+            AudioEncoding::Flac => "audio.flac",
+            AudioEncoding::Opus => "audio.ogg",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            AudioEncoding::Wav => "wav",
+            AudioEncoding::Flac => "flac",
+            AudioEncoding::Opus => "ogg",
+        }
+    }
+
+    /// Recognises an encoded clip by its magic bytes.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        match bytes.get(..4)? {
+            b"RIFF" => Some(AudioEncoding::Wav),
+            b"fLaC" => Some(AudioEncoding::Flac),
+            b"OggS" => Some(AudioEncoding::Opus),
+            _ => None,
+        }
+    }
+}
+
+/// Encodes mono samples in -1.0..1.0.
+pub fn encode(
+    encoding: AudioEncoding,
+    sample_rate: u32,
+    samples: &[f32],
+) -> Result<Vec<u8>, String> {
+    match encoding {
+        AudioEncoding::Wav => encode_wav(sample_rate, samples),
+        AudioEncoding::Flac => encode_flac(sample_rate, samples),
+        AudioEncoding::Opus => encode_opus(sample_rate, samples),
+    }
+}
+
+/// Decodes a WAV, FLAC or Ogg/Opus clip into mono samples and their rate.
+pub fn decode(bytes: &[u8]) -> Result<(Vec<f32>, u32), String> {
+    match AudioEncoding::detect(bytes) {
+        Some(AudioEncoding::Flac) => decode_flac(bytes),
+        Some(AudioEncoding::Opus) => decode_opus(bytes),
+        _ => audio::decode_wav(bytes),
+    }
+}
+
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+pub fn encode_wav(sample_rate: u32, samples: &[f32]) -> Result<Vec<u8>, String> {
+    let mut cursor = Cursor::new(Vec::new());
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut writer = WavWriter::new(&mut cursor, spec)
+        .map_err(|e| format!("Failed to create WAV writer: {}", e))?;
+    for &sample in samples {
+        writer
+            .write_sample(to_i16(sample))
+            .map_err(|e| format!("Failed to write WAV sample: {}", e))?;
+    }
+    writer
+        .finalize()
+        .map_err(|e| format!("Failed to finalize WAV: {}", e))?;
+
+    Ok(cursor.into_inner())
+}
+
+// FLAC: fixed-size blocks, each coded with the best fixed predictor (order 0-4)
+// and partitioned Rice residuals. No LPC search, but most of what a full
+// encoder gains on speech.
+const FLAC_BLOCK_SIZE: usize = 4096;
+const FLAC_MAX_RICE_PARAM: u32 = 14;
+const FLAC_MAX_PARTITION_ORDER: u32 = 6;
+
+pub fn encode_flac(sample_rate: u32, samples: &[f32]) -> Result<Vec<u8>, String> {
+    if sample_rate == 0 || sample_rate >= 1 << 20 {
+        return Err(format!("Unsupported FLAC sample rate: {}", sample_rate));
+    }
+    let pcm: Vec<i32> = samples.iter().map(|&s| to_i16(s) as i32).collect();
+
+    let mut out = BitWriter::default();
+    out.write_bytes(b"fLaC");
+
+    // STREAMINFO, the only (and so last) metadata block
+    out.write(1, 1);
+    out.write(0, 7);
+    out.write(34, 24);
+    out.write(FLAC_BLOCK_SIZE as u64, 16); // min block size
+    out.write(FLAC_BLOCK_SIZE as u64, 16); // max block size
+    out.write(0, 24); // min frame size: unknown
+    out.write(0, 24); // max frame size: unknown
+    out.write(sample_rate as u64, 20);
+    out.write(0, 3); // channels - 1
+    out.write(15, 5); // bits per sample - 1
+    out.write(pcm.len() as u64, 36);
+    out.write_bytes(&[0; 16]); // MD5 of the audio: not computed
+
+    for (index, block) in pcm.chunks(FLAC_BLOCK_SIZE).enumerate() {
+        write_flac_frame(&mut out, index as u64, block);
+    }
+    Ok(out.into_bytes())
+}
+
+fn write_flac_frame(out: &mut BitWriter, frame_number: u64, block: &[i32]) {
+    let start = out.bytes.len();
+
+    out.write(0b11_1111_1111_1110, 14); // sync code
+    out.write(0, 1);
+    out.write(0, 1); // fixed block size
+    out.write(0b0111, 4); // block size in 16 bits after the header
+    out.write(0b0000, 4); // sample rate from STREAMINFO
+    out.write(0b0000, 4); // mono
+    out.write(0b100, 3); // 16 bits per sample
+    out.write(0, 1);
+    out.write_bytes(&utf8_coded(frame_number));
+    out.write(block.len() as u64 - 1, 16);
+    let crc = crc8(&out.bytes[start..]);
+    out.write(crc as u64, 8);
+
+    write_flac_subframe(out, block);
+    out.align();
+    let crc = crc16(&out.bytes[start..]);
+    out.write(crc as u64, 16);
+}
+
+fn write_flac_subframe(out: &mut BitWriter, block: &[i32]) {
+    let (order, residuals) = (0..=4usize.min(block.len().saturating_sub(1)))
+        .map(|order| (order, fixed_residuals(block, order)))
+        .min_by_key(|(_, residuals)| {
+            residuals
+                .iter()
+                .map(|r| r.unsigned_abs() as u64)
+                .sum::<u64>()
+        })
+        .unwrap_or((0, block.to_vec()));
+
+    out.write(0, 1);
+    out.write(0b001000 | order as u64, 6); // FIXED subframe
+    out.write(0, 1); // no wasted bits
+    for &warmup in &block[..order] {
+        out.write_signed(warmup, 16);
+    }
+
+    // Residual: Rice coding with 4-bit parameters
+    let folded: Vec<u64> = residuals
+        .iter()
+        .map(|&r| ((r << 1) ^ (r >> 31)) as u32 as u64)
+        .collect();
+    let (partition_order, params) = best_rice_partitions(&folded, block.len(), order);
+    out.write(0b00, 2);
+    out.write(partition_order as u64, 4);
+
+    let partition_len = block.len() >> partition_order;
+    let mut offset = 0;
+    for (index, &param) in params.iter().enumerate() {
+        let len = if index == 0 {
+            partition_len - order
+        } else {
+            partition_len
+        };
+        out.write(param as u64, 4);
+        for &value in &folded[offset..offset + len] {
+            out.write_unary((value >> param) as u32);
+            out.write(value & ((1 << param) - 1), param);
+        }
+        offset += len;
+    }
+}
+
+// Prediction error of the fixed polynomial predictor of `order`
+fn fixed_residuals(block: &[i32], order: usize) -> Vec<i32> {
+    (order..block.len())
+        .map(|i| {
+            let s = |k: usize| block[i - k];
+            match order {
+                0 => s(0),
+                1 => s(0) - s(1),
+                2 => s(0) - 2 * s(1) + s(2),
+                3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+                _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+            }
+        })
+        .collect()
+}
+
+// Rice parameter and its cost in bits for one partition
+fn best_rice_param(folded: &[u64]) -> (u32, u64) {
+    (0..=FLAC_MAX_RICE_PARAM)
+        .map(|param| {
+            let bits = folded
+                .iter()
+                .map(|&u| (u >> param) + 1 + param as u64)
+                .sum::<u64>();
+            (param, bits)
+        })
+        .min_by_key(|&(_, bits)| bits)
+        .unwrap_or((0, 0))
+}
+
+// Splits the residual into 2^order partitions with their own Rice parameter,
+// picking the order that codes smallest. The first partition is shorter by
+// the predictor's warm-up samples.
+fn best_rice_partitions(folded: &[u64], block_len: usize, order: usize) -> (u32, Vec<u32>) {
+    (0..=FLAC_MAX_PARTITION_ORDER)
+        .take_while(|&p| block_len.is_multiple_of(1 << p) && (block_len >> p) > order)
+        .map(|p| {
+            let partition_len = block_len >> p;
+            let mut offset = 0;
+            let mut bits = 0;
+            let params = (0..1usize << p)
+                .map(|index| {
+                    let len = if index == 0 {
+                        partition_len - order
+                    } else {
+                        partition_len
+                    };
+                    let (param, cost) = best_rice_param(&folded[offset..offset + len]);
+                    offset += len;
+                    bits += cost + 4;
+                    param
+                })
+                .collect::<Vec<_>>();
+            (p, params, bits)
+        })
+        .min_by_key(|(_, _, bits)| *bits)
+        .map(|(p, params, _)| (p, params))
+        .unwrap_or((0, vec![best_rice_param(folded).0]))
+}
+
+// Frame numbers use the UTF-8 style variable-length coding
+fn utf8_coded(value: u64) -> Vec<u8> {
+    if value < 0x80 {
+        return vec![value as u8];
+    }
+    let mut continuation = Vec::new();
+    let mut rest = value;
+    let mut first_bits = 6;
+    while rest >= 1 << first_bits {
+        continuation.push(0x80 | (rest & 0x3f) as u8);
+        rest >>= 6;
+        first_bits -= 1;
+    }
+    let count = continuation.len() + 1;
+    let lead = !(0xffu8 >> count) | rest as u8;
+    let mut bytes = vec![lead];
+    bytes.extend(continuation.into_iter().rev());
+    bytes
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0u16, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    // Bits already used in the last byte (0 = byte aligned)
+    used: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u64, bits: u32) {
+        for i in (0..bits).rev() {
+            if self.used == 0 {
+                self.bytes.push(0);
+            }
+            let bit = ((value >> i) & 1) as u8;
+            *self.bytes.last_mut().unwrap() |= bit << (7 - self.used);
+            self.used = (self.used + 1) % 8;
+        }
+    }
+
+    fn write_signed(&mut self, value: i32, bits: u32) {
+        self.write(value as u64 & ((1 << bits) - 1), bits);
+    }
+
+    fn write_unary(&mut self, zeros: u32) {
+        for _ in 0..zeros {
+            self.write(0, 1);
+        }
+        self.write(1, 1);
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        if self.used == 0 {
+            self.bytes.extend_from_slice(bytes);
+        } else {
+            bytes.iter().for_each(|&b| self.write(b as u64, 8));
+        }
+    }
+
+    fn align(&mut self) {
+        self.used = 0;
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+fn decode_flac(bytes: &[u8]) -> Result<(Vec<f32>, u32), String> {
+    let mut reader = claxon::FlacReader::new(Cursor::new(bytes))
+        .map_err(|e| format!("Failed to read FLAC: {}", e))?;
+    let info = reader.streaminfo();
+    let scale = (1i64 << (info.bits_per_sample - 1)) as f32;
+    let channels = info.channels.max(1) as usize;
+
+    let interleaved: Vec<f32> = reader
+        .samples()
+        .map(|s| s.map(|s| s as f32 / scale))
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to decode FLAC samples: {}", e))?;
+    let mono = interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+    Ok((mono, info.sample_rate))
+}
+
+// Ogg/Opus: 20 ms frames of 24 kbit/s speech, granule positions at 48 kHz
+const OPUS_FRAME_MS: usize = 20;
+const OPUS_BITRATE: i32 = 24000;
+const OPUS_GRANULE_RATE: u64 = 48000;
+const OPUS_STREAM_SERIAL: u32 = 0x436c_6b01;
+
+fn opus_rate(sample_rate: u32) -> (SampleRate, u32) {
+    match sample_rate {
+        8000 => (SampleRate::Hz8000, 8000),
+        12000 => (SampleRate::Hz12000, 12000),
+        16000 => (SampleRate::Hz16000, 16000),
+        24000 => (SampleRate::Hz24000, 24000),
+        _ => (SampleRate::Hz48000, 48000),
+    }
+}
+
+pub fn encode_opus(sample_rate: u32, samples: &[f32]) -> Result<Vec<u8>, String> {
+    // Opus only runs at a few rates; anything else goes up to 48 kHz
+    let (rate, encoder_rate) = opus_rate(sample_rate);
+    let samples = if encoder_rate == sample_rate {
+        samples.to_vec()
+    } else {
+        audio::resample(samples, sample_rate, encoder_rate)
+    };
+
+    let mut encoder = Encoder::new(rate, Channels::Mono, Application::Voip)
+        .map_err(|e| format!("Failed to create Opus encoder: {}", e))?;
+    encoder
+        .set_bitrate(Bitrate::BitsPerSecond(OPUS_BITRATE))
+        .map_err(|e| format!("Failed to set Opus bitrate: {}", e))?;
+    let granule_step = OPUS_GRANULE_RATE / encoder_rate as u64;
+    let pre_skip = encoder.lookahead().unwrap_or(0) as u64 * granule_step;
+
+    let mut writer = PacketWriter::new(Vec::new());
+    let write_err = |e: std::io::Error| format!("Failed to write Ogg page: {}", e);
+
+    let mut head = b"OpusHead".to_vec();
+    head.push(1); // version
+    head.push(1); // channels
+    head.extend_from_slice(&(pre_skip as u16).to_le_bytes());
+    head.extend_from_slice(&sample_rate.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+    head.push(0); // channel mapping family
+    writer
+        .write_packet(
+            head.into_boxed_slice(),
+            OPUS_STREAM_SERIAL,
+            PacketWriteEndInfo::EndPage,
+            0,
+        )
+        .map_err(write_err)?;
+
+    let vendor = b"cloak";
+    let mut tags = b"OpusTags".to_vec();
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor);
+    tags.extend_from_slice(&0u32.to_le_bytes()); // no user comments
+    writer
+        .write_packet(
+            tags.into_boxed_slice(),
+            OPUS_STREAM_SERIAL,
+            PacketWriteEndInfo::EndPage,
+            0,
+        )
+        .map_err(write_err)?;
+
+    let frame_len = encoder_rate as usize * OPUS_FRAME_MS / 1000;
+    // Enough frames to flush the encoder's lookahead
+    let frames = (samples.len() + pre_skip as usize / granule_step as usize).div_ceil(frame_len);
+    let end_granule = pre_skip + samples.len() as u64 * granule_step;
+    let mut packet = vec![0u8; 4000];
+    let mut frame = vec![0f32; frame_len];
+
+    for index in 0..frames.max(1) {
+        let start = (index * frame_len).min(samples.len());
+        let end = (start + frame_len).min(samples.len());
+        frame.fill(0.0);
+        frame[..end - start].copy_from_slice(&samples[start..end]);
+
+        let len = encoder
+            .encode_float(&frame, &mut packet)
+            .map_err(|e| format!("Failed to encode Opus frame: {}", e))?;
+
+        let last = index + 1 >= frames.max(1);
+        let granule = ((index + 1) as u64 * frame_len as u64 * granule_step).min(end_granule);
+        let info = if last {
+            PacketWriteEndInfo::EndStream
+        } else {
+            PacketWriteEndInfo::NormalPacket
+        };
+        writer
+            .write_packet(packet[..len].into(), OPUS_STREAM_SERIAL, info, granule)
+            .map_err(write_err)?;
+    }
+
+    Ok(writer.into_inner())
+}
+
+fn decode_opus(bytes: &[u8]) -> Result<(Vec<f32>, u32), String> {
+    let mut reader = PacketReader::new(Cursor::new(bytes));
+    let read_err = |e: ogg::OggReadError| format!("Failed to read Ogg stream: {}", e);
+
+    let head = reader
+        .read_packet()
+        .map_err(read_err)?
+        .ok_or_else(|| "Ogg stream is empty".to_string())?;
+    if head.data.len() < 19 || &head.data[..8] != b"OpusHead" {
+        return Err("Ogg stream is not Opus".to_string());
+    }
+    let channels = if head.data[9] >= 2 {
+        Channels::Stereo
+    } else {
+        Channels::Mono
+    };
+    let pre_skip = u16::from_le_bytes([head.data[10], head.data[11]]) as usize;
+    // OpusTags
+    reader.read_packet().map_err(read_err)?;
+
+    let mut decoder = Decoder::new(SampleRate::Hz48000, channels)
+        .map_err(|e| format!("Failed to create Opus decoder: {}", e))?;
+    let channel_count = channels as usize;
+    // Longest Opus packet: 120 ms at 48 kHz
+    let mut buffer = vec![0f32; 5760 * channel_count];
+    let mut decoded = Vec::new();
+
+    while let Some(packet) = reader.read_packet().map_err(read_err)? {
+        let packet = Packet::try_from(packet.data.as_slice())
+            .map_err(|e| format!("Invalid Opus packet: {}", e))?;
+        let signals = MutSignals::try_from(buffer.as_mut_slice())
+            .map_err(|e| format!("Invalid Opus buffer: {}", e))?;
+        let frames = decoder
+            .decode_float(Some(packet), signals, false)
+            .map_err(|e| format!("Failed to decode Opus packet: {}", e))?;
+        decoded.extend(
+            buffer[..frames * channel_count]
+                .chunks(channel_count)
+                .map(|frame| frame.iter().sum::<f32>() / channel_count as f32),
+        );
+    }
+
+    let samples = decoded.split_off(pre_skip.min(decoded.len()));
+    Ok((samples, OPUS_GRANULE_RATE as u32))
+}
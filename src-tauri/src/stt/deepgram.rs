@@ -0,0 +1,125 @@
+// Deepgram live transcription (`/v1/listen`): raw PCM16 binary frames, JSON results
+use super::{RealtimeProtocol, StreamingSttConfig, TranscriptEvent};
+use reqwest::Url;
+use serde_json::Value;
+use tokio_tungstenite::tungstenite::Message;
+
+const DEFAULT_URL: &str = "wss://api.deepgram.com/v1/listen";
+const DEFAULT_MODEL: &str = "nova-3";
+
+pub struct DeepgramProtocol {
+    url: String,
+    // `is_final` pieces of the utterance in progress, joined once the commit's
+    // `Finalize` comes back
+    settled: Vec<String>,
+}
+
+impl DeepgramProtocol {
+    pub fn new(config: &StreamingSttConfig) -> Self {
+        let base = config.url_or(DEFAULT_URL.to_string());
+        let mut params = vec![
+            ("model", config.model_or(DEFAULT_MODEL).to_string()),
+            ("encoding", "linear16".to_string()),
+            ("sample_rate", "16000".to_string()),
+            ("channels", "1".to_string()),
+            ("interim_results", "true".to_string()),
+            ("smart_format", "true".to_string()),
+            // Cloak's VAD ends utterances; Deepgram's own endpointing would add
+            // finals no commit asked for
+            ("endpointing", "false".to_string()),
+        ];
+        if let Some(language) = config.language() {
+            params.push(("language", language.to_string()));
+        }
+        let url = Url::parse_with_params(&base, &params)
+            .map(|url| url.to_string())
+            .unwrap_or(base);
+        Self {
+            url,
+            settled: Vec::new(),
+        }
+    }
+
+    fn joined_with(&self, text: &str) -> String {
+        self.settled
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(text))
+            .filter(|piece| !piece.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl RealtimeProtocol for DeepgramProtocol {
+    fn name(&self) -> &'static str {
+        "Deepgram"
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    fn auth_headers(&self, api_key: &str) -> Vec<(&'static str, String)> {
+        vec![("Authorization", format!("Token {}", api_key))]
+    }
+
+    fn commit_message(&self) -> Option<Message> {
+        Some(Message::text(r#"{"type":"Finalize"}"#))
+    }
+
+    fn close_message(&self) -> Option<Message> {
+        Some(Message::text(r#"{"type":"CloseStream"}"#))
+    }
+
+    // Deepgram closes the stream after 10 s without audio or a KeepAlive
+    fn keepalive_message(&self) -> Option<Message> {
+        Some(Message::text(r#"{"type":"KeepAlive"}"#))
+    }
+
+    fn parse_message(&mut self, text: &str) -> Result<Vec<TranscriptEvent>, String> {
+        let event: Value = serde_json::from_str(text)
+            .map_err(|e| format!("Failed to parse Deepgram message: {}", e))?;
+
+        match event.get("type").and_then(|t| t.as_str()) {
+            Some("Results") => {}
+            Some("Error") => {
+                let message = event
+                    .get("description")
+                    .or_else(|| event.get("message"))
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("unknown error");
+                return Err(format!("Deepgram error: {}", message));
+            }
+            _ => return Ok(vec![]),
+        }
+
+        let transcript = event
+            .pointer("/channel/alternatives/0/transcript")
+            .and_then(|t| t.as_str())
+            .unwrap_or("")
+            .trim();
+        let flag = |key: &str| event.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+
+        if !flag("is_final") {
+            let text = self.joined_with(transcript);
+            return Ok(if text.is_empty() {
+                vec![]
+            } else {
+                vec![TranscriptEvent::Partial(text)]
+            });
+        }
+
+        if flag("from_finalize") {
+            let text = self.joined_with(transcript);
+            self.settled.clear();
+            return Ok(vec![TranscriptEvent::Final(text)]);
+        }
+
+        if transcript.is_empty() {
+            return Ok(vec![]);
+        }
+        self.settled.push(transcript.to_string());
+        Ok(vec![TranscriptEvent::Partial(self.joined_with(""))])
+    }
+}
@@ -0,0 +1,380 @@
+// Cloak speech-to-text: streaming over a WebSocket to realtime STT APIs, and
+// on-device Whisper for transcriptions that must not leave the machine
+use crate::speaker::AudioSource;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tokio_tungstenite::tungstenite::Message;
+
+mod assemblyai;
+pub mod audio;
+pub mod codec;
+mod deepgram;
+mod openai_realtime;
+mod session;
+mod whisper;
+
+pub use assemblyai::AssemblyAiProtocol;
+pub use deepgram::DeepgramProtocol;
+pub use openai_realtime::OpenAiRealtimeProtocol;
+pub use session::StreamingSession;
+pub use whisper::LocalWhisper;
+
+const CONFIG_FILE_NAME: &str = "streaming_stt_config.json";
+const LOCAL_CONFIG_FILE_NAME: &str = "local_whisper_config.json";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RealtimeProvider {
+    #[default]
+    Deepgram,
+    /// OpenAI Realtime API in transcription mode
+    OpenAi,
+    /// AssemblyAI Universal Streaming (v3)
+    AssemblyAi,
+}
+
+impl RealtimeProvider {
+    fn api_key_env(&self) -> &'static str {
+        match self {
+            RealtimeProvider::Deepgram => "DEEPGRAM_API_KEY",
+            RealtimeProvider::OpenAi => "OPENAI_API_KEY",
+            RealtimeProvider::AssemblyAi => "ASSEMBLYAI_API_KEY",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StreamingSttConfig {
+    /// When enabled, VAD capture streams speech to `provider` instead of
+    /// emitting WAV segments on `speech-detected`
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub provider: RealtimeProvider,
+    /// Falls back to the provider's environment variable (e.g. `DEEPGRAM_API_KEY`)
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// BCP-47 language hint, e.g. "en"
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Replaces the provider's WebSocket URL (self-hosted gateways, local mock servers)
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+impl StreamingSttConfig {
+    pub fn api_key(&self) -> String {
+        self.api_key
+            .as_deref()
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(String::from)
+            .or_else(|| env::var(self.provider.api_key_env()).ok())
+            .map(|key| key.trim().to_string())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn model_or<'a>(&'a self, default: &'a str) -> &'a str {
+        self.model
+            .as_deref()
+            .map(str::trim)
+            .filter(|model| !model.is_empty())
+            .unwrap_or(default)
+    }
+
+    pub(crate) fn language(&self) -> Option<&str> {
+        self.language
+            .as_deref()
+            .map(str::trim)
+            .filter(|language| !language.is_empty())
+    }
+
+    pub(crate) fn url_or(&self, default: String) -> String {
+        self.url
+            .as_deref()
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(String::from)
+            .unwrap_or(default)
+    }
+
+    fn protocol(&self) -> Box<dyn RealtimeProtocol> {
+        match self.provider {
+            RealtimeProvider::Deepgram => Box::new(DeepgramProtocol::new(self)),
+            RealtimeProvider::OpenAi => Box::new(OpenAiRealtimeProtocol::new(self)),
+            RealtimeProvider::AssemblyAi => Box::new(AssemblyAiProtocol::new(self)),
+        }
+    }
+}
+
+/// A transcript update parsed from a provider message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TranscriptEvent {
+    /// Current hypothesis for the utterance in progress; replaces earlier partials
+    Partial(String),
+    /// Settled text; later partials start a new utterance. Sent exactly once
+    /// per commit, empty when nothing was said
+    Final(String),
+    /// The committed utterance couldn't be transcribed; takes its final's place
+    Failed(String),
+}
+
+/// The wire protocol of one realtime STT API.
+///
+/// The session owns the socket; a protocol only builds the handshake and
+/// messages and interprets what the server sends back, so it can be driven
+/// against a local mock server by pointing `StreamingSttConfig::url` at it.
+pub trait RealtimeProtocol: Send {
+    fn name(&self) -> &'static str;
+
+    /// WebSocket URL including query parameters.
+    fn url(&self) -> String;
+
+    /// Sample rate of the PCM16 frames the API expects.
+    fn sample_rate(&self) -> u32 {
+        16_000
+    }
+
+    /// Headers used to authenticate with `api_key`.
+    fn auth_headers(&self, api_key: &str) -> Vec<(&'static str, String)>;
+
+    /// Messages sent once right after the handshake.
+    fn setup_messages(&self) -> Vec<Message> {
+        vec![]
+    }
+
+    /// Wraps mono little-endian PCM16 samples for sending.
+    fn audio_message(&self, pcm: &[i16]) -> Message {
+        Message::binary(pcm_bytes(pcm))
+    }
+
+    /// Asks the server to finalize the audio sent so far (end of utterance).
+    fn commit_message(&self) -> Option<Message>;
+
+    /// Drops buffered audio the server hasn't transcribed yet (noise, too short).
+    fn discard_message(&self) -> Option<Message> {
+        None
+    }
+
+    /// Ends the stream; the server flushes pending transcripts and closes.
+    fn close_message(&self) -> Option<Message>;
+
+    /// Sent while no speech is being streamed, for servers that close a socket
+    /// that goes quiet for a few seconds.
+    fn keepalive_message(&self) -> Option<Message> {
+        None
+    }
+
+    /// Interprets one text message. Errors reported by the server become `Err`.
+    fn parse_message(&mut self, text: &str) -> Result<Vec<TranscriptEvent>, String>;
+}
+
+pub(crate) fn pcm_bytes(pcm: &[i16]) -> Vec<u8> {
+    pcm.iter().flat_map(|s| s.to_le_bytes()).collect()
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocalWhisperConfig {
+    /// Try on-device Whisper before the configured transcription endpoints
+    #[serde(default)]
+    pub enabled: bool,
+    /// GGML model file; defaults to the first `*.bin` in `<app data>/models/whisper`
+    #[serde(default)]
+    pub model_path: Option<String>,
+    /// ISO 639-1 code; detected automatically when unset
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub threads: Option<usize>,
+    /// Never fall back to cloud endpoints, even if local transcription fails
+    #[serde(default)]
+    pub local_only: bool,
+}
+
+#[derive(Default)]
+pub struct SttState {
+    config: Mutex<Option<StreamingSttConfig>>,
+    local_config: Mutex<Option<LocalWhisperConfig>>,
+    // Loaded on first use and reloaded when the selected model changes
+    whisper: tokio::sync::Mutex<Option<Arc<LocalWhisper>>>,
+}
+
+fn get_config_path(app: &AppHandle, file_name: &str) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+
+    Ok(app_data_dir.join(file_name))
+}
+
+fn read_config<T: Default + for<'de> Deserialize<'de>>(app: &AppHandle, file_name: &str) -> T {
+    get_config_path(app, file_name)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_config<T: Serialize>(app: &AppHandle, file_name: &str, config: &T) -> Result<(), String> {
+    let path = get_config_path(app, file_name)?;
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize STT config: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write STT config: {}", e))
+}
+
+/// Current streaming STT configuration, read from disk on first use.
+pub fn get_config(app: &AppHandle) -> StreamingSttConfig {
+    let state = app.state::<SttState>();
+    let mut cached = state.config.lock().unwrap();
+    cached
+        .get_or_insert_with(|| read_config(app, CONFIG_FILE_NAME))
+        .clone()
+}
+
+/// Current local Whisper configuration, read from disk on first use.
+pub fn get_local_config(app: &AppHandle) -> LocalWhisperConfig {
+    let state = app.state::<SttState>();
+    let mut cached = state.local_config.lock().unwrap();
+    cached
+        .get_or_insert_with(|| read_config(app, LOCAL_CONFIG_FILE_NAME))
+        .clone()
+}
+
+fn resolve_model_path(app: &AppHandle, config: &LocalWhisperConfig) -> Result<PathBuf, String> {
+    if let Some(path) = config
+        .model_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+    {
+        let path = PathBuf::from(path);
+        if !path.is_file() {
+            return Err(format!("Whisper model not found at {}", path.display()));
+        }
+        return Ok(path);
+    }
+
+    let models_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("models")
+        .join("whisper");
+    whisper::find_model_in(&models_dir).ok_or_else(|| {
+        format!(
+            "No Whisper model selected. Choose a GGML model file or place one in {}",
+            models_dir.display()
+        )
+    })
+}
+
+async fn get_local_whisper(
+    app: &AppHandle,
+    config: &LocalWhisperConfig,
+) -> Result<Arc<LocalWhisper>, String> {
+    let model_path = resolve_model_path(app, config)?;
+    let state = app.state::<SttState>();
+    let mut loaded = state.whisper.lock().await;
+    if let Some(whisper) = loaded.as_ref().filter(|w| w.model_path() == model_path) {
+        return Ok(whisper.clone());
+    }
+
+    let whisper = tauri::async_runtime::spawn_blocking(move || LocalWhisper::load(&model_path))
+        .await
+        .map_err(|e| format!("Whisper model load panicked: {}", e))??;
+    let whisper = Arc::new(whisper);
+    *loaded = Some(whisper.clone());
+    Ok(whisper)
+}
+
+/// Transcribes a WAV, FLAC or Ogg/Opus clip on the CPU with the configured
+/// Whisper model.
+pub async fn transcribe_locally(app: &AppHandle, audio_bytes: &[u8]) -> Result<String, String> {
+    transcribe_locally_with(app, &get_local_config(app), audio_bytes).await
+}
+
+/// Like `transcribe_locally`, with the model and language of `config` instead
+/// of the saved settings.
+pub async fn transcribe_locally_with(
+    app: &AppHandle,
+    config: &LocalWhisperConfig,
+    audio_bytes: &[u8],
+) -> Result<String, String> {
+    let (samples, sample_rate) = codec::decode(audio_bytes)?;
+    if samples.is_empty() {
+        return Err("Empty audio buffer".to_string());
+    }
+    let samples = audio::resample(&samples, sample_rate, whisper::WHISPER_SAMPLE_RATE);
+
+    let whisper = get_local_whisper(app, config).await?;
+    let language = config
+        .language
+        .as_deref()
+        .map(str::trim)
+        .filter(|language| !language.is_empty())
+        .map(String::from);
+    let threads = config.threads.unwrap_or_else(whisper::default_threads);
+    tauri::async_runtime::spawn_blocking(move || {
+        whisper.transcribe(&samples, language.as_deref(), threads)
+    })
+    .await
+    .map_err(|e| format!("Whisper transcription panicked: {}", e))?
+}
+
+/// Opens a streaming session for audio captured at `input_rate`, or returns
+/// `None` when streaming transcription is disabled. `source` labels the
+/// session's transcripts in a dual capture.
+pub async fn start_session(
+    app: &AppHandle,
+    input_rate: u32,
+    source: Option<AudioSource>,
+) -> Result<Option<StreamingSession>, String> {
+    let config = get_config(app);
+    if !config.enabled {
+        return Ok(None);
+    }
+    StreamingSession::connect(app.clone(), &config, input_rate, source)
+        .await
+        .map(Some)
+}
+
+#[tauri::command]
+pub fn get_streaming_stt_config(app: AppHandle) -> Result<StreamingSttConfig, String> {
+    Ok(get_config(&app))
+}
+
+#[tauri::command]
+pub fn set_streaming_stt_config(app: AppHandle, config: StreamingSttConfig) -> Result<(), String> {
+    write_config(&app, CONFIG_FILE_NAME, &config)?;
+
+    let state = app.state::<SttState>();
+    *state.config.lock().unwrap() = Some(config);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_local_whisper_config(app: AppHandle) -> Result<LocalWhisperConfig, String> {
+    Ok(get_local_config(&app))
+}
+
+#[tauri::command]
+pub fn set_local_whisper_config(app: AppHandle, config: LocalWhisperConfig) -> Result<(), String> {
+    if config.enabled {
+        resolve_model_path(&app, &config)?;
+    }
+    write_config(&app, LOCAL_CONFIG_FILE_NAME, &config)?;
+
+    let state = app.state::<SttState>();
+    *state.local_config.lock().unwrap() = Some(config);
+    Ok(())
+}
@@ -0,0 +1,144 @@
+// OpenAI Realtime API in transcription mode: base64 PCM16 in JSON events
+use super::{RealtimeProtocol, StreamingSttConfig, TranscriptEvent};
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use reqwest::Url;
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio_tungstenite::tungstenite::Message;
+
+const DEFAULT_URL: &str = "wss://api.openai.com/v1/realtime";
+const DEFAULT_MODEL: &str = "gpt-4o-transcribe";
+
+pub struct OpenAiRealtimeProtocol {
+    url: String,
+    model: String,
+    language: Option<String>,
+    // Deltas received so far, per conversation item
+    partials: HashMap<String, String>,
+}
+
+impl OpenAiRealtimeProtocol {
+    pub fn new(config: &StreamingSttConfig) -> Self {
+        let base = config.url_or(DEFAULT_URL.to_string());
+        let url = Url::parse_with_params(&base, &[("intent", "transcription")])
+            .map(|url| url.to_string())
+            .unwrap_or(base);
+        Self {
+            url,
+            model: config.model_or(DEFAULT_MODEL).to_string(),
+            language: config.language().map(String::from),
+            partials: HashMap::new(),
+        }
+    }
+}
+
+fn error_message(event: &Value) -> String {
+    event
+        .pointer("/error/message")
+        .and_then(|m| m.as_str())
+        .unwrap_or("unknown error")
+        .to_string()
+}
+
+impl RealtimeProtocol for OpenAiRealtimeProtocol {
+    fn name(&self) -> &'static str {
+        "OpenAI Realtime"
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    // `pcm16` input is fixed at 24 kHz
+    fn sample_rate(&self) -> u32 {
+        24_000
+    }
+
+    fn auth_headers(&self, api_key: &str) -> Vec<(&'static str, String)> {
+        vec![
+            ("Authorization", format!("Bearer {}", api_key)),
+            ("OpenAI-Beta", "realtime=v1".to_string()),
+        ]
+    }
+
+    fn setup_messages(&self) -> Vec<Message> {
+        let mut transcription = serde_json::json!({ "model": self.model });
+        if let Some(language) = &self.language {
+            transcription["language"] = Value::String(language.clone());
+        }
+        // Turn detection stays off: Cloak's VAD decides when an utterance ends
+        let update = serde_json::json!({
+            "type": "transcription_session.update",
+            "session": {
+                "input_audio_format": "pcm16",
+                "input_audio_transcription": transcription,
+                "turn_detection": null
+            }
+        });
+        vec![Message::text(update.to_string())]
+    }
+
+    fn audio_message(&self, pcm: &[i16]) -> Message {
+        let event = serde_json::json!({
+            "type": "input_audio_buffer.append",
+            "audio": B64.encode(super::pcm_bytes(pcm))
+        });
+        Message::text(event.to_string())
+    }
+
+    fn commit_message(&self) -> Option<Message> {
+        Some(Message::text(r#"{"type":"input_audio_buffer.commit"}"#))
+    }
+
+    fn discard_message(&self) -> Option<Message> {
+        Some(Message::text(r#"{"type":"input_audio_buffer.clear"}"#))
+    }
+
+    // No end-of-stream event; the session closes the socket once pending
+    // transcripts have arrived
+    fn close_message(&self) -> Option<Message> {
+        None
+    }
+
+    fn parse_message(&mut self, text: &str) -> Result<Vec<TranscriptEvent>, String> {
+        let event: Value = serde_json::from_str(text)
+            .map_err(|e| format!("Failed to parse OpenAI Realtime message: {}", e))?;
+        let item_id = event
+            .get("item_id")
+            .and_then(|i| i.as_str())
+            .unwrap_or("")
+            .to_string();
+
+        match event.get("type").and_then(|t| t.as_str()).unwrap_or("") {
+            "conversation.item.input_audio_transcription.delta" => {
+                let delta = event.get("delta").and_then(|d| d.as_str()).unwrap_or("");
+                let partial = self.partials.entry(item_id).or_default();
+                partial.push_str(delta);
+                let text = partial.trim();
+                Ok(if text.is_empty() {
+                    vec![]
+                } else {
+                    vec![TranscriptEvent::Partial(text.to_string())]
+                })
+            }
+            "conversation.item.input_audio_transcription.completed" => {
+                let streamed = self.partials.remove(&item_id).unwrap_or_default();
+                let transcript = event
+                    .get("transcript")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or(&streamed)
+                    .trim();
+                Ok(vec![TranscriptEvent::Final(transcript.to_string())])
+            }
+            "conversation.item.input_audio_transcription.failed" => {
+                self.partials.remove(&item_id);
+                Ok(vec![TranscriptEvent::Failed(format!(
+                    "OpenAI transcription failed: {}",
+                    error_message(&event)
+                ))])
+            }
+            "error" => Err(format!("OpenAI Realtime error: {}", error_message(&event))),
+            _ => Ok(vec![]),
+        }
+    }
+}
@@ -0,0 +1,326 @@
+// Cloak streaming STT session: owns the WebSocket, forwards captured audio
+// and emits `transcript-partial` / `transcript-final` events
+use super::audio::LinearResampler;
+use super::{RealtimeProtocol, StreamingSttConfig, TranscriptEvent};
+use crate::speaker::AudioSource;
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tracing::warn;
+
+// Audio is sent in ~100 ms frames; AssemblyAI rejects chunks under 50 ms
+const FRAME_MS: u32 = 100;
+// How long to wait for the last transcripts once capture has stopped
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+// Audio only flows during speech, so quiet stretches get a keepalive this often
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(4);
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+enum SessionCommand {
+    Audio(Vec<i16>),
+    Commit(Option<u32>),
+    Discard,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptPayload {
+    pub text: String,
+    pub provider: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<AudioSource>,
+    /// Diarized speaker of the committed utterance
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<u32>,
+}
+
+/// Handle to a running session. Dropping it ends the stream; transcripts still
+/// pending on the server are emitted before the socket closes.
+pub struct StreamingSession {
+    commands: mpsc::UnboundedSender<SessionCommand>,
+    resampler: LinearResampler,
+}
+
+impl StreamingSession {
+    /// Connects to the configured provider. `input_rate` is the capture rate;
+    /// audio is converted to the provider's PCM16 rate before sending.
+    pub async fn connect(
+        app: AppHandle,
+        config: &StreamingSttConfig,
+        input_rate: u32,
+        source: Option<AudioSource>,
+    ) -> Result<Self, String> {
+        let protocol = config.protocol();
+        let mut request = protocol
+            .url()
+            .into_client_request()
+            .map_err(|e| format!("Invalid streaming STT URL: {}", e))?;
+
+        let api_key = config.api_key();
+        if !api_key.is_empty() {
+            for (name, value) in protocol.auth_headers(&api_key) {
+                let value = HeaderValue::from_str(&value)
+                    .map_err(|e| format!("Invalid streaming STT header {}: {}", name, e))?;
+                request.headers_mut().insert(name, value);
+            }
+        }
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(request)
+            .await
+            .map_err(|e| format!("Failed to connect to {}: {}", protocol.name(), e))?;
+
+        for message in protocol.setup_messages() {
+            socket
+                .send(message)
+                .await
+                .map_err(|e| format!("Failed to configure {} session: {}", protocol.name(), e))?;
+        }
+
+        let output_rate = protocol.sample_rate();
+        let frame_len = (output_rate * FRAME_MS / 1000) as usize;
+        let (commands, receiver) = mpsc::unbounded_channel();
+        tauri::async_runtime::spawn(run_session(
+            app, protocol, socket, receiver, frame_len, source,
+        ));
+
+        Ok(Self {
+            commands,
+            resampler: LinearResampler::new(input_rate, output_rate),
+        })
+    }
+
+    /// Queues mono f32 samples at the capture rate. Fails once the provider
+    /// has closed the session.
+    pub fn send_audio(&mut self, samples: &[f32]) -> Result<(), String> {
+        let pcm: Vec<i16> = self
+            .resampler
+            .process(samples)
+            .into_iter()
+            .map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+            .collect();
+        if pcm.is_empty() {
+            return Ok(());
+        }
+        self.send(SessionCommand::Audio(pcm))
+    }
+
+    /// Marks the end of an utterance so the provider finalizes it right away.
+    /// `speaker` is attached to the utterance's transcripts.
+    pub fn commit(&self, speaker: Option<u32>) -> Result<(), String> {
+        self.send(SessionCommand::Commit(speaker))
+    }
+
+    /// Drops audio of an utterance that turned out to be noise.
+    pub fn discard(&self) -> Result<(), String> {
+        self.send(SessionCommand::Discard)
+    }
+
+    // The receiver goes away when `run_session` ends
+    fn send(&self, command: SessionCommand) -> Result<(), String> {
+        self.commands
+            .send(command)
+            .map_err(|_| "Streaming session closed".to_string())
+    }
+}
+
+async fn run_session(
+    app: AppHandle,
+    mut protocol: Box<dyn RealtimeProtocol>,
+    socket: Socket,
+    mut commands: mpsc::UnboundedReceiver<SessionCommand>,
+    frame_len: usize,
+    source: Option<AudioSource>,
+) {
+    let (mut sink, mut incoming) = socket.split();
+    let mut pending: Vec<i16> = Vec::new();
+    // Audio sent since the last commit or discard
+    let mut uncommitted = false;
+    // Speakers of committed utterances whose final transcript hasn't arrived
+    let mut speakers: VecDeque<Option<u32>> = VecDeque::new();
+    let mut keepalive = tokio::time::interval(KEEPALIVE_INTERVAL);
+    keepalive.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut last_sent = Instant::now();
+
+    let result: Result<bool, String> = async {
+        loop {
+            tokio::select! {
+                command = commands.recv() => match command {
+                    Some(SessionCommand::Audio(pcm)) => {
+                        uncommitted = true;
+                        pending.extend(pcm);
+                        while pending.len() >= frame_len {
+                            let frame: Vec<i16> = pending.drain(..frame_len).collect();
+                            sink.send(protocol.audio_message(&frame))
+                                .await
+                                .map_err(|e| format!("Failed to send audio: {}", e))?;
+                            last_sent = Instant::now();
+                        }
+                    }
+                    // Without audio there is nothing to finalize, and no final would
+                    // come back to claim the speaker
+                    Some(SessionCommand::Commit(speaker)) if uncommitted => {
+                        uncommitted = false;
+                        speakers.push_back(speaker);
+                        if !pending.is_empty() {
+                            sink.send(protocol.audio_message(&pending))
+                                .await
+                                .map_err(|e| format!("Failed to send audio: {}", e))?;
+                            pending.clear();
+                        }
+                        if let Some(message) = protocol.commit_message() {
+                            sink.send(message)
+                                .await
+                                .map_err(|e| format!("Failed to commit audio: {}", e))?;
+                        }
+                        last_sent = Instant::now();
+                    }
+                    Some(SessionCommand::Commit(_)) => {}
+                    Some(SessionCommand::Discard) => {
+                        uncommitted = false;
+                        pending.clear();
+                        if let Some(message) = protocol.discard_message() {
+                            sink.send(message)
+                                .await
+                                .map_err(|e| format!("Failed to discard audio: {}", e))?;
+                        }
+                    }
+                    // Capture stopped
+                    None => return Ok(true),
+                },
+                _ = keepalive.tick() => {
+                    if last_sent.elapsed() >= KEEPALIVE_INTERVAL {
+                        if let Some(message) = protocol.keepalive_message() {
+                            sink.send(message)
+                                .await
+                                .map_err(|e| format!("Failed to send keepalive: {}", e))?;
+                        }
+                        last_sent = Instant::now();
+                    }
+                }
+                message = incoming.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        handle_message(
+                            &app,
+                            protocol.as_mut(),
+                            text.as_str(),
+                            source,
+                            &mut speakers,
+                        )
+                    }
+                    Some(Ok(Message::Close(_))) | None => return Ok(false),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        return Err(format!("{} connection failed: {}", protocol.name(), e))
+                    }
+                }
+            }
+        }
+    }
+    .await;
+
+    match result {
+        Ok(true) => {
+            // Flush the tail of the utterance and let the server finish transcribing
+            if !pending.is_empty() {
+                let _ = sink.send(protocol.audio_message(&pending)).await;
+            }
+            // Commit the tail so its final comes back like any other
+            if uncommitted {
+                speakers.push_back(None);
+                if let Some(message) = protocol.commit_message() {
+                    let _ = sink.send(message).await;
+                }
+            }
+            if let Some(message) = protocol.close_message() {
+                let _ = sink.send(message).await;
+            }
+
+            let _ = tokio::time::timeout(CLOSE_TIMEOUT, async {
+                while let Some(Ok(message)) = incoming.next().await {
+                    match message {
+                        Message::Text(text) => handle_message(
+                            &app,
+                            protocol.as_mut(),
+                            text.as_str(),
+                            source,
+                            &mut speakers,
+                        ),
+                        Message::Close(_) => break,
+                        _ => {}
+                    }
+                }
+            })
+            .await;
+            let _ = sink.send(Message::Close(None)).await;
+        }
+        Ok(false) => {
+            warn!("{} closed the streaming session", protocol.name());
+            let _ = app.emit(
+                "streaming-stt-error",
+                format!("{} closed the connection", protocol.name()),
+            );
+        }
+        Err(e) => {
+            warn!("{}", e);
+            let _ = app.emit("streaming-stt-error", e);
+        }
+    }
+}
+
+fn handle_message(
+    app: &AppHandle,
+    protocol: &mut dyn RealtimeProtocol,
+    text: &str,
+    source: Option<AudioSource>,
+    speakers: &mut VecDeque<Option<u32>>,
+) {
+    match protocol.parse_message(text) {
+        Ok(events) => {
+            for event in events {
+                // Each commit gets exactly one final (or failure), in commit order;
+                // partials belong to the oldest pending one
+                let (name, text, speaker) = match event {
+                    TranscriptEvent::Partial(text) => (
+                        "transcript-partial",
+                        text,
+                        speakers.front().copied().flatten(),
+                    ),
+                    TranscriptEvent::Final(text) => {
+                        let speaker = speakers.pop_front().flatten();
+                        if text.is_empty() {
+                            continue;
+                        }
+                        ("transcript-final", text, speaker)
+                    }
+                    TranscriptEvent::Failed(e) => {
+                        speakers.pop_front();
+                        warn!("{}", e);
+                        let _ = app.emit("streaming-stt-error", e);
+                        continue;
+                    }
+                };
+                let _ = app.emit(
+                    name,
+                    TranscriptPayload {
+                        text,
+                        provider: protocol.name(),
+                        source,
+                        speaker,
+                    },
+                );
+            }
+        }
+        Err(e) => {
+            warn!("{}", e);
+            let _ = app.emit("streaming-stt-error", e);
+        }
+    }
+}
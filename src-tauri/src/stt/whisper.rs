@@ -0,0 +1,91 @@
+// Cloak on-device transcription with whisper.cpp and a user-selected GGML model
+use std::path::{Path, PathBuf};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+/// Sample rate whisper.cpp expects
+pub const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+pub struct LocalWhisper {
+    model_path: PathBuf,
+    context: WhisperContext,
+}
+
+impl LocalWhisper {
+    /// Loads a GGML model (e.g. `ggml-base.en.bin`). Takes a few seconds for larger models.
+    pub fn load(model_path: &Path) -> Result<Self, String> {
+        let path = model_path
+            .to_str()
+            .ok_or_else(|| "Whisper model path is not valid UTF-8".to_string())?;
+        let context = WhisperContext::new_with_params(path, WhisperContextParameters::default())
+            .map_err(|e| format!("Failed to load Whisper model {}: {}", path, e))?;
+        Ok(Self {
+            model_path: model_path.to_path_buf(),
+            context,
+        })
+    }
+
+    pub fn model_path(&self) -> &Path {
+        &self.model_path
+    }
+
+    /// Transcribes mono 16 kHz samples. `language` is an ISO 639-1 code; `None`
+    /// lets Whisper detect it.
+    pub fn transcribe(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+        threads: usize,
+    ) -> Result<String, String> {
+        let mut state = self
+            .context
+            .create_state()
+            .map_err(|e| format!("Failed to create Whisper state: {}", e))?;
+
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_n_threads(threads as i32);
+        params.set_language(Some(language.unwrap_or("auto")));
+        params.set_translate(false);
+        // Segments are independent utterances, so earlier text isn't useful context
+        params.set_no_context(true);
+        params.set_suppress_blank(true);
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+
+        state
+            .full(params, samples)
+            .map_err(|e| format!("Whisper transcription failed: {}", e))?;
+
+        let segments = state
+            .full_n_segments()
+            .map_err(|e| format!("Failed to read Whisper segments: {}", e))?;
+        let mut text = String::new();
+        for segment in 0..segments {
+            let segment_text = state
+                .full_get_segment_text(segment)
+                .map_err(|e| format!("Failed to read Whisper segment: {}", e))?;
+            text.push_str(&segment_text);
+        }
+        Ok(text.trim().to_string())
+    }
+}
+
+/// Default thread count: leave a core for capture and the UI.
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get().saturating_sub(1))
+        .unwrap_or(4)
+        .clamp(1, 8)
+}
+
+/// First GGML model (`*.bin`) in `dir`, by file name.
+pub fn find_model_in(dir: &Path) -> Option<PathBuf> {
+    let mut models: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("bin"))
+        .collect();
+    models.sort();
+    models.into_iter().next()
+}
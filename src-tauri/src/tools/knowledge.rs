@@ -0,0 +1,80 @@
+// search_knowledge_base: hybrid search over saved conversations, transcriptions and pages
+use super::ToolOutput;
+use crate::knowledge::{self, SearchFilters};
+use crate::providers::ToolSpec;
+use serde_json::Value;
+use tauri::AppHandle;
+
+pub const NAME: &str = "search_knowledge_base";
+
+const DEFAULT_LIMIT: usize = 5;
+const MAX_LIMIT: usize = 20;
+
+pub fn definition() -> ToolSpec {
+    ToolSpec {
+        name: NAME.to_string(),
+        description: "Search the user's knowledge base of past conversations, meeting transcriptions and pages. Returns the most relevant passages with the item they come from.".to_string(),
+        parameters: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "What to look for, in natural language or keywords"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum number of results (default 5, max 20)"
+                },
+                "item_type": {
+                    "type": "string",
+                    "enum": ["conversation", "transcription", "page"],
+                    "description": "Only search items of this type"
+                }
+            },
+            "required": ["query"]
+        }),
+    }
+}
+
+pub async fn run(app: &AppHandle, arguments: &Value) -> Result<ToolOutput, String> {
+    let query = arguments
+        .get("query")
+        .and_then(|q| q.as_str())
+        .unwrap_or("")
+        .trim();
+    if query.is_empty() {
+        return Err("query must not be empty".to_string());
+    }
+    let limit = arguments
+        .get("limit")
+        .and_then(|l| l.as_u64())
+        .map_or(DEFAULT_LIMIT, |l| l as usize)
+        .clamp(1, MAX_LIMIT);
+    let filters = SearchFilters {
+        item_types: arguments
+            .get("item_type")
+            .and_then(|t| t.as_str())
+            .map(|t| vec![t.to_string()])
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    let results = knowledge::search(app, query, limit, &filters).await?;
+    if results.is_empty() {
+        return Ok(ToolOutput::text(format!(
+            "No knowledge base items match \"{}\".",
+            query
+        )));
+    }
+
+    let results: Vec<String> = results
+        .into_iter()
+        .map(|r| {
+            format!(
+                "[{}] {} ({})\n{}",
+                r.item_id, r.title, r.item_type, r.chunk_text
+            )
+        })
+        .collect();
+    Ok(ToolOutput::text(results.join("\n\n")))
+}
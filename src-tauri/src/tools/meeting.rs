@@ -0,0 +1,69 @@
+// get_meeting_transcript: transcript of the meeting being recorded, or the latest one
+use super::ToolOutput;
+use crate::db;
+use crate::providers::ToolSpec;
+use serde_json::Value;
+use tauri::AppHandle;
+
+pub const NAME: &str = "get_meeting_transcript";
+
+const DEFAULT_MAX_CHARS: usize = 20_000;
+
+pub fn definition() -> ToolSpec {
+    ToolSpec {
+        name: NAME.to_string(),
+        description: "Get the transcript of the meeting currently being recorded, or of the most recent meeting if none is in progress.".to_string(),
+        parameters: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "max_chars": {
+                    "type": "integer",
+                    "description": "Only return the last N characters of the transcript (default 20000)"
+                }
+            }
+        }),
+    }
+}
+
+pub async fn run(app: &AppHandle, arguments: &Value) -> Result<ToolOutput, String> {
+    let max_chars = arguments
+        .get("max_chars")
+        .and_then(|m| m.as_u64())
+        .map_or(DEFAULT_MAX_CHARS, |m| m as usize)
+        .max(1);
+
+    let pool = db::get_pool(app).await?;
+    let meeting: Option<(String, String, String)> = sqlx::query_as(
+        "SELECT title, status, transcript FROM meetings
+         ORDER BY status = 'recording' DESC, created_at DESC
+         LIMIT 1",
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("Failed to load meeting: {}", e))?;
+
+    let (title, status, transcript) = match meeting {
+        Some(meeting) => meeting,
+        None => return Ok(ToolOutput::text("No meetings have been recorded yet.")),
+    };
+    if transcript.trim().is_empty() {
+        return Ok(ToolOutput::text(format!(
+            "Meeting \"{}\" ({}) has no transcript yet.",
+            title, status
+        )));
+    }
+
+    // Keep the end of long transcripts; the latest discussion is usually what matters
+    let char_count = transcript.chars().count();
+    let transcript = if char_count > max_chars {
+        let tail: String = transcript.chars().skip(char_count - max_chars).collect();
+        format!("[...earlier transcript omitted...]\n{}", tail)
+    } else {
+        transcript
+    };
+
+    Ok(ToolOutput::text(format!(
+        "Meeting \"{}\" ({})\n\n{}",
+        title, status, transcript
+    )))
+}
@@ -0,0 +1,47 @@
+// Cloak built-in tools the model can call during a chat stream
+use crate::providers::{ToolCall, ToolSpec};
+use serde_json::Value;
+use tauri::AppHandle;
+
+mod knowledge;
+mod meeting;
+mod screenshot;
+
+/// Result of running a tool. `images` are base64 PNGs that are sent back to the
+/// model alongside the text result.
+#[derive(Debug, Default)]
+pub struct ToolOutput {
+    pub content: String,
+    pub images: Vec<String>,
+}
+
+impl ToolOutput {
+    pub fn text(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            images: Vec::new(),
+        }
+    }
+}
+
+/// Every tool offered to the model when tool calling is enabled.
+pub fn definitions() -> Vec<ToolSpec> {
+    vec![
+        knowledge::definition(),
+        screenshot::definition(),
+        meeting::definition(),
+    ]
+}
+
+/// Runs the tool named in `call` with its JSON arguments.
+pub async fn execute(app: &AppHandle, call: &ToolCall) -> Result<ToolOutput, String> {
+    let arguments: Value = serde_json::from_str(&call.arguments)
+        .map_err(|e| format!("Invalid arguments for {}: {}", call.name, e))?;
+
+    match call.name.as_str() {
+        knowledge::NAME => knowledge::run(app, &arguments).await,
+        screenshot::NAME => screenshot::run(app).await,
+        meeting::NAME => meeting::run(app, &arguments).await,
+        other => Err(format!("Unknown tool: {}", other)),
+    }
+}
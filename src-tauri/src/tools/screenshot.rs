@@ -0,0 +1,32 @@
+// take_screenshot: captures the monitor the Cloak window is on
+use super::ToolOutput;
+use crate::capture;
+use crate::providers::ToolSpec;
+use tauri::{AppHandle, Manager};
+
+pub const NAME: &str = "take_screenshot";
+
+pub fn definition() -> ToolSpec {
+    ToolSpec {
+        name: NAME.to_string(),
+        description:
+            "Take a screenshot of the user's current screen to see what they are looking at."
+                .to_string(),
+        parameters: serde_json::json!({
+            "type": "object",
+            "properties": {}
+        }),
+    }
+}
+
+pub async fn run(app: &AppHandle) -> Result<ToolOutput, String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| "Main window not found".to_string())?;
+    let image = capture::capture_to_base64(window).await?;
+
+    Ok(ToolOutput {
+        content: "Screenshot captured; the image is attached in the next message.".to_string(),
+        images: vec![image],
+    })
+}
@@ -79,6 +79,8 @@ export const AppProvider = ({ children }: { children: ReactNode }) => {
   const [selectedAudioDevices, setSelectedAudioDevices] = useState<{
     input: { id: string; name: string };
     output: { id: string; name: string };
+    // Application captured on its own instead of the whole output (Linux)
+    application?: string | null;
   }>(() => {
     const savedDevices = safeLocalStorage.getItem(
       STORAGE_KEYS.SELECTED_AUDIO_DEVICES
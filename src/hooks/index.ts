@@ -16,3 +16,4 @@ export * from "./useApp";
 export * from "./useMenuItems";
 export * from "./useGoogleCalendar";
 export * from "./useMeetings";
+export * from "./useMeetingCapture";
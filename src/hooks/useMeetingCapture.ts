@@ -0,0 +1,177 @@
+import { useState, useCallback, useEffect, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { useApp } from "@/contexts";
+import {
+  fetchSTT,
+  releaseSpeechSegment,
+  AudioSource,
+} from "@/lib/functions";
+
+/** Returned by `start_meeting` and `get_active_meeting` */
+interface ActiveMeeting {
+  meeting_id: string;
+  started_at: number;
+}
+
+/** Payload of `meeting-transcript-line` */
+interface MeetingLineEvent {
+  meeting_id: string;
+  line: string;
+}
+
+/** Payload of `meeting-status` */
+interface MeetingStatusEvent {
+  meeting_id: string;
+  status: "recording" | "processing" | "completed";
+}
+
+/** Payload of `meeting-segment-untranscribed`: Rust STT is not set up */
+interface UntranscribedSegmentEvent {
+  meeting_id: string;
+  segment_id: string;
+  mime_type: string;
+  source: AudioSource;
+  speaker?: number;
+  error: string;
+}
+
+/**
+ * Controls the Rust meeting recorder, which captures the microphone ("me")
+ * and system audio ("them"), transcribes each utterance and saves it with its
+ * speaker label ("Me", or "Speaker N" for diarized remote voices). Recording
+ * carries on across webview reloads; `onLine` receives every saved line and
+ * `onCompleted` fires once the summary has been written.
+ */
+export function useMeetingCapture(
+  meetingId: string | undefined,
+  onLine?: (line: string) => void,
+  onCompleted?: () => void
+) {
+  const { selectedSttProvider, allSttProviders, selectedAudioDevices } =
+    useApp();
+  const [recording, setRecording] = useState(false);
+  const [processing, setProcessing] = useState(false);
+  const [error, setError] = useState("");
+  const onLineRef = useRef(onLine);
+  onLineRef.current = onLine;
+  const onCompletedRef = useRef(onCompleted);
+  onCompletedRef.current = onCompleted;
+
+  // Pick up a meeting that was already recording before a reload
+  useEffect(() => {
+    if (!meetingId) return;
+    invoke<ActiveMeeting | null>("get_active_meeting")
+      .then((active) => setRecording(active?.meeting_id === meetingId))
+      .catch(console.error);
+  }, [meetingId]);
+
+  useEffect(() => {
+    const unlisteners: Array<() => void> = [];
+
+    const setupListeners = async () => {
+      unlisteners.push(
+        await listen<MeetingLineEvent>("meeting-transcript-line", (event) => {
+          if (event.payload.meeting_id !== meetingId) return;
+          onLineRef.current?.(event.payload.line);
+        })
+      );
+      unlisteners.push(
+        await listen<MeetingStatusEvent>("meeting-status", (event) => {
+          const { meeting_id, status } = event.payload;
+          if (meeting_id !== meetingId) return;
+          setRecording(status === "recording");
+          setProcessing(status === "processing");
+          if (status === "completed") onCompletedRef.current?.();
+        })
+      );
+    };
+
+    setupListeners();
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten());
+    };
+  }, [meetingId]);
+
+  // Rust could not transcribe: use the selected provider and save the line
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlisten = await listen<UntranscribedSegmentEvent>(
+        "meeting-segment-untranscribed",
+        async (event) => {
+          const { meeting_id, segment_id, mime_type, source, speaker } =
+            event.payload;
+          if (meeting_id !== meetingId) return;
+          const providerConfig = allSttProviders.find(
+            (p) => p.id === selectedSttProvider.provider
+          );
+          try {
+            const transcription = await fetchSTT({
+              provider: providerConfig,
+              selectedProvider: selectedSttProvider,
+              audio: { segmentId: segment_id, mimeType: mime_type },
+            });
+            if (!transcription.trim()) return;
+            const line = await invoke<string>("append_meeting_transcript", {
+              meetingId,
+              source,
+              speaker: speaker ?? null,
+              text: transcription,
+            });
+            onLineRef.current?.(line);
+            await invoke("copilot_observe_transcript", {
+              source,
+              speaker: speaker ?? null,
+              text: transcription,
+            });
+          } catch (err: any) {
+            console.error("STT Error:", err);
+            setError(err.message || "Failed to transcribe audio");
+          } finally {
+            releaseSpeechSegment(segment_id);
+          }
+        }
+      );
+    };
+
+    setupListener();
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [meetingId, selectedSttProvider, allSttProviders]);
+
+  const start = useCallback(async () => {
+    if (!meetingId) return;
+    setError("");
+    try {
+      await invoke<ActiveMeeting>("start_meeting", {
+        meetingId,
+        title: null,
+        microphone: selectedAudioDevices.input.name || null,
+        deviceId:
+          selectedAudioDevices.output.id !== "default"
+            ? selectedAudioDevices.output.id || null
+            : null,
+        application: selectedAudioDevices.application || null,
+      });
+      setRecording(true);
+    } catch (err) {
+      console.error("Failed to start meeting capture:", err);
+      setError(`Failed to start recording: ${err}`);
+    }
+  }, [meetingId, selectedAudioDevices]);
+
+  const stop = useCallback(async () => {
+    try {
+      await invoke<string>("stop_meeting");
+      setProcessing(true);
+    } catch (err) {
+      console.error("Failed to stop meeting capture:", err);
+    }
+    setRecording(false);
+  }, []);
+
+  return { recording, processing, error, start, stop };
+}
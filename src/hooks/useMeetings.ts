@@ -1,11 +1,10 @@
 import { useState, useCallback, useEffect } from "react";
-import { invoke } from "@tauri-apps/api/core";
-import { listen } from "@tauri-apps/api/event";
 import {
   createMeeting,
   getAllMeetings,
   getMeetingById,
   updateMeeting,
+  summarizeMeeting,
   deleteMeeting as dbDeleteMeeting,
 } from "@/lib/database/meetings.action";
 import type { Meeting } from "@/types/meetings";
@@ -14,27 +13,6 @@ function generateId(): string {
   return `meeting-${Date.now()}-${Math.random().toString(36).slice(2, 9)}`;
 }
 
-const SUMMARY_SYSTEM_PROMPT = `You are a meeting summarizer. Given a transcript of a meeting or call, create a structured summary. Use this EXACT format with markdown:
-
-## Current Situation
-- Key context points as bullet points
-
-## Key Points Discussed
-- Main topics and decisions as bullet points
-
-## Action Items
-- Concrete next steps with owners if mentioned
-
-## Key Takeaways
-- Important conclusions or insights
-
-Rules:
-- Be concise but capture all important information
-- Use bullet points, not paragraphs
-- If the transcript is short or unclear, summarize what you can
-- Do NOT add information that isn't in the transcript
-- Keep each bullet point to 1-2 sentences max`;
-
 export function useMeetings() {
   const [meetings, setMeetings] = useState<Meeting[]>([]);
   const [currentMeeting, setCurrentMeeting] = useState<Meeting | null>(null);
@@ -101,38 +79,10 @@ export function useMeetings() {
     async (meetingId: string): Promise<string | null> => {
       setGeneratingSummary(true);
       try {
+        // Map-reduce summary in Rust; it saves the markdown and the notes
+        await summarizeMeeting(meetingId);
         const meeting = await getMeetingById(meetingId);
-        if (!meeting?.transcript) return null;
-
-        const chunks: string[] = [];
-        let streamComplete = false;
-
-        const unlisten = await listen(
-          "chat_stream_chunk",
-          (event: { payload: string }) => {
-            chunks.push(event.payload);
-          }
-        );
-        const unlistenComplete = await listen("chat_stream_complete", () => {
-          streamComplete = true;
-        });
-
-        await invoke("chat_stream_response", {
-          userMessage: `Here is the meeting transcript to summarize:\n\n${meeting.transcript}`,
-          systemPrompt: SUMMARY_SYSTEM_PROMPT,
-          imageBase64: null,
-          history: null,
-        });
-
-        while (!streamComplete) {
-          await new Promise((r) => setTimeout(r, 50));
-        }
-        unlisten();
-        unlistenComplete();
-
-        const summary = chunks.join("");
-
-        await updateMeeting(meetingId, { summary, status: "completed" });
+        const summary = meeting?.summary ?? null;
 
         // Update local state
         setMeetings((prev) =>
@@ -149,7 +99,6 @@ export function useMeetings() {
         return summary;
       } catch (err) {
         console.error("Failed to generate summary:", err);
-        await updateMeeting(meetingId, { status: "completed" });
         return null;
       } finally {
         setGeneratingSummary(false);
@@ -3,7 +3,13 @@ import { useWindowResize, useGlobalShortcuts } from ".";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { useApp } from "@/contexts";
-import { fetchSTT, fetchAIResponse } from "@/lib/functions";
+import {
+  fetchSTT,
+  releaseSpeechSegment,
+  fetchAIResponse,
+  SpeechSegmentEvent,
+  StreamingTranscriptEvent,
+} from "@/lib/functions";
 import {
   DEFAULT_QUICK_ACTIONS,
   DEFAULT_SYSTEM_PROMPT,
@@ -19,6 +25,37 @@ import {
 } from "@/lib";
 import { Message } from "@/types/completion";
 
+export type VadEngine = "energy" | "silero";
+
+// Format of the clips sent for transcription
+export type AudioEncoding = "wav" | "flac" | "opus";
+
+export interface SileroVadConfig {
+  model_path: string | null;
+  threshold: number;
+}
+
+// Removes system audio the microphone picks up (dual capture on speakers)
+export interface EchoCancellationConfig {
+  enabled: boolean;
+  // 0-1: how hard leftover echo is suppressed
+  strength: number;
+}
+
+// Spectral noise suppression ahead of VAD, per source
+export interface NoiseSuppressionConfig {
+  microphone: boolean;
+  system_audio: boolean;
+}
+
+// `audio-signal-level` payload; *_before is the level ahead of noise suppression
+export interface SignalLevel {
+  rms: number;
+  peak: number;
+  rms_before?: number;
+  peak_before?: number;
+}
+
 // VAD Configuration interface matching Rust
 export interface VadConfig {
   enabled: boolean;
@@ -30,6 +67,42 @@ export interface VadConfig {
   pre_speech_chunks: number;
   noise_gate_threshold: number;
   max_recording_duration_secs: number;
+  engine?: VadEngine;
+  silero?: SileroVadConfig;
+  // Capture is resampled to this rate (16 kHz by default); null keeps the device rate
+  target_sample_rate?: number | null;
+  encoding?: AudioEncoding;
+  echo_cancellation?: EchoCancellationConfig;
+  noise_suppression?: NoiseSuppressionConfig;
+  // Move a running capture to the new default device when it changes
+  follow_default_device?: boolean;
+}
+
+// `audio-devices-changed` payload
+export interface AudioDevicesChanged {
+  default_input_changed: boolean;
+  default_output_changed: boolean;
+  // A running capture moved to the new default device
+  capture_switched: boolean;
+}
+
+export type QuestionDetector = "heuristic" | "llm";
+
+// Answer copilot settings, matching Rust `CopilotConfig`
+export interface CopilotConfig {
+  enabled: boolean;
+  detector: QuestionDetector;
+  // Silence that ends the other person's turn
+  debounce_ms: number;
+  // Minimum time between two suggestions
+  min_interval_secs: number;
+  context_lines: number;
+}
+
+// Payload of `copilot-suggestion`, sent before the answer starts streaming
+interface CopilotSuggestionEvent {
+  stream_id: string;
+  question: string;
 }
 
 // OPTIMIZED VAD defaults - matches backend exactly for perfect performance
@@ -72,6 +145,8 @@ export function useSystemAudio() {
   const [isProcessing, setIsProcessing] = useState(false);
   const [isAIProcessing, setIsAIProcessing] = useState(false);
   const [lastTranscription, setLastTranscription] = useState<string>("");
+  // Live hypothesis from streaming transcription, cleared once it is final
+  const [partialTranscription, setPartialTranscription] = useState<string>("");
   const [lastAIResponse, setLastAIResponse] = useState<string>("");
   const [error, setError] = useState<string>("");
   const [setupRequired, setSetupRequired] = useState<boolean>(false);
@@ -84,7 +159,10 @@ export function useSystemAudio() {
   const [isContinuousMode, setIsContinuousMode] = useState<boolean>(false);
   const [isRecordingInContinuousMode, setIsRecordingInContinuousMode] =
     useState<boolean>(false);
-  const [signalLevel, setSignalLevel] = useState({ rms: 0, peak: 0 });
+  const [signalLevel, setSignalLevel] = useState<SignalLevel>({ rms: 0, peak: 0 });
+  const [copilotConfig, setCopilotConfig] = useState<CopilotConfig | null>(
+    null
+  );
 
   const [conversation, setConversation] = useState<ChatConversation>({
     id: "",
@@ -110,6 +188,9 @@ export function useSystemAudio() {
   const saveTimeoutRef = useRef<NodeJS.Timeout | null>(null);
   const isSavingRef = useRef<boolean>(false);
   const scrollAreaRef = useRef<HTMLDivElement>(null);
+  // With the copilot on, Rust decides which transcripts get an answer
+  const copilotEnabledRef = useRef(false);
+  copilotEnabledRef.current = copilotConfig?.enabled ?? false;
 
   // Load context settings and VAD config from localStorage on mount
   useEffect(() => {
@@ -222,7 +303,7 @@ export function useSystemAudio() {
 
     const setupListener = async () => {
       unlisten = await listen("audio-signal-level", (event) => {
-        const payload = event.payload as { rms: number; peak: number };
+        const payload = event.payload as SignalLevel;
         setSignalLevel(payload);
       });
     };
@@ -239,76 +320,82 @@ export function useSystemAudio() {
 
     const setupEventListener = async () => {
       try {
-        speechUnlisten = await listen("speech-detected", async (event) => {
-          try {
-            if (!capturing) return;
-
-            const base64Audio = event.payload as string;
-            // Convert to blob
-            const binaryString = atob(base64Audio);
-            const bytes = new Uint8Array(binaryString.length);
-            for (let i = 0; i < binaryString.length; i++) {
-              bytes[i] = binaryString.charCodeAt(i);
-            }
-            const audioBlob = new Blob([bytes], { type: "audio/wav" });
+        speechUnlisten = await listen<SpeechSegmentEvent>(
+          "speech-detected",
+          async (event) => {
+            try {
+              // Labelled segments belong to a meeting's dual capture
+              if (!capturing || event.payload.source) return;
 
-            const providerConfig = allSttProviders.find(
-              (p) => p.id === selectedSttProvider.provider
-            );
+              const { id, mime_type } = event.payload;
 
-            setIsProcessing(true);
+              const providerConfig = allSttProviders.find(
+                (p) => p.id === selectedSttProvider.provider
+              );
 
-            // Add timeout wrapper for STT request (30 seconds)
-            const sttPromise = fetchSTT({
-              provider: providerConfig,
-              selectedProvider: selectedSttProvider,
-              audio: audioBlob,
-            });
+              setIsProcessing(true);
 
-            const timeoutPromise = new Promise<string>((_, reject) => {
-              setTimeout(
-                () => reject(new Error("Speech transcription timed out (30s)")),
-                30000
-              );
-            });
+              // Add timeout wrapper for STT request (30 seconds)
+              const sttPromise = fetchSTT({
+                provider: providerConfig,
+                selectedProvider: selectedSttProvider,
+                audio: { segmentId: id, mimeType: mime_type },
+              }).finally(() => releaseSpeechSegment(id));
 
-            try {
-              const transcription = await Promise.race([
-                sttPromise,
-                timeoutPromise,
-              ]);
-
-              if (transcription.trim()) {
-                setLastTranscription(transcription);
-                setError("");
-
-                const effectiveSystemPrompt = useSystemPrompt
-                  ? systemPrompt || DEFAULT_SYSTEM_PROMPT
-                  : contextContent || DEFAULT_SYSTEM_PROMPT;
-
-                const previousMessages = conversation.messages.map((msg) => {
-                  return { role: msg.role, content: msg.content };
-                });
-
-                await processWithAI(
-                  transcription,
-                  effectiveSystemPrompt,
-                  previousMessages
+              const timeoutPromise = new Promise<string>((_, reject) => {
+                setTimeout(
+                  () => reject(new Error("Speech transcription timed out (30s)")),
+                  30000
                 );
-              } else {
-                setError("Received empty transcription");
+              });
+
+              try {
+                const transcription = await Promise.race([
+                  sttPromise,
+                  timeoutPromise,
+                ]);
+
+                if (transcription.trim()) {
+                  setLastTranscription(transcription);
+                  setError("");
+
+                  if (copilotEnabledRef.current) {
+                    await invoke("copilot_observe_transcript", {
+                      source: null,
+                      speaker: null,
+                      text: transcription,
+                    });
+                    return;
+                  }
+
+                  const effectiveSystemPrompt = useSystemPrompt
+                    ? systemPrompt || DEFAULT_SYSTEM_PROMPT
+                    : contextContent || DEFAULT_SYSTEM_PROMPT;
+
+                  const previousMessages = conversation.messages.map((msg) => {
+                    return { role: msg.role, content: msg.content };
+                  });
+
+                  await processWithAI(
+                    transcription,
+                    effectiveSystemPrompt,
+                    previousMessages
+                  );
+                } else {
+                  setError("Received empty transcription");
+                }
+              } catch (sttError: any) {
+                console.error("STT Error:", sttError);
+                setError(sttError.message || "Failed to transcribe audio");
+                setIsPopoverOpen(true);
               }
-            } catch (sttError: any) {
-              console.error("STT Error:", sttError);
-              setError(sttError.message || "Failed to transcribe audio");
-              setIsPopoverOpen(true);
+            } catch (err) {
+              setError("Failed to process speech");
+            } finally {
+              setIsProcessing(false);
             }
-          } catch (err) {
-            setError("Failed to process speech");
-          } finally {
-            setIsProcessing(false);
           }
-        });
+        );
       } catch (err) {
         setError("Failed to setup speech listener");
       }
@@ -326,6 +413,70 @@ export function useSystemAudio() {
     conversation.messages.length,
   ]);
 
+  // Handle streaming transcription (Rust forwards speech to a realtime STT API)
+  useEffect(() => {
+    let partialUnlisten: (() => void) | undefined;
+    let finalUnlisten: (() => void) | undefined;
+    let errorUnlisten: (() => void) | undefined;
+
+    const setupStreamingListeners = async () => {
+      try {
+        partialUnlisten = await listen<StreamingTranscriptEvent>(
+          "transcript-partial",
+          (event) => {
+            // Labelled transcripts belong to a meeting's dual capture
+            if (!capturing || event.payload.source) return;
+            setPartialTranscription(event.payload.text);
+          }
+        );
+
+        finalUnlisten = await listen<StreamingTranscriptEvent>(
+          "transcript-final",
+          async (event) => {
+            if (!capturing || event.payload.source) return;
+            const transcription = event.payload.text.trim();
+            setPartialTranscription("");
+            if (!transcription) return;
+
+            setLastTranscription(transcription);
+            setError("");
+            // Rust hears streamed finals itself
+            if (copilotEnabledRef.current) return;
+
+            const effectiveSystemPrompt = useSystemPrompt
+              ? systemPrompt || DEFAULT_SYSTEM_PROMPT
+              : contextContent || DEFAULT_SYSTEM_PROMPT;
+
+            const previousMessages = conversation.messages.map((msg) => {
+              return { role: msg.role, content: msg.content };
+            });
+
+            await processWithAI(
+              transcription,
+              effectiveSystemPrompt,
+              previousMessages
+            );
+          }
+        );
+
+        errorUnlisten = await listen<string>("streaming-stt-error", (event) => {
+          console.error("Streaming transcription error:", event.payload);
+          setError(`Live transcription: ${event.payload}`);
+        });
+      } catch (err) {
+        setError("Failed to setup streaming transcription listener");
+      }
+    };
+
+    setupStreamingListeners();
+
+    return () => {
+      if (partialUnlisten) partialUnlisten();
+      if (finalUnlisten) finalUnlisten();
+      if (errorUnlisten) errorUnlisten();
+    };
+  }, [capturing, conversation.messages.length]);
+
   // Context management functions
   const saveContextSettings = useCallback(
     (usePrompt: boolean, content: string) => {
@@ -448,12 +599,17 @@ export function useSystemAudio() {
       await invoke<string>("start_system_audio_capture", {
         vadConfig: vadConfig,
         deviceId: deviceId,
+        application: selectedAudioDevices.application || null,
       });
     } catch (err) {
       console.error("Failed to start continuous recording:", err);
       setError(`Failed to start recording: ${err}`);
     }
-  }, [vadConfig, selectedAudioDevices.output.id]);
+  }, [
+    vadConfig,
+    selectedAudioDevices.output.id,
+    selectedAudioDevices.application,
+  ]);
 
   // Ignore current recording (stop without transcription)
   const ignoreContinuousRecording = useCallback(async () => {
@@ -592,13 +748,18 @@ export function useSystemAudio() {
       await invoke<string>("start_system_audio_capture", {
         vadConfig: vadConfig,
         deviceId: deviceId,
+        application: selectedAudioDevices.application || null,
       });
     } catch (err) {
       const errorMessage = err instanceof Error ? err.message : String(err);
       setError(errorMessage);
       setIsPopoverOpen(true);
     }
-  }, [vadConfig, selectedAudioDevices.output.id]);
+  }, [
+    vadConfig,
+    selectedAudioDevices.output.id,
+    selectedAudioDevices.application,
+  ]);
 
   const stopCapture = useCallback(async () => {
     try {
@@ -619,6 +780,7 @@ export function useSystemAudio() {
       setIsRecordingInContinuousMode(false);
       setRecordingProgress(0);
       setLastTranscription("");
+      setPartialTranscription("");
       setLastAIResponse("");
       setError("");
       setIsPopoverOpen(false);
@@ -776,6 +938,128 @@ export function useSystemAudio() {
     setUseSystemPrompt(true);
   }, []);
 
+  // Load the answer copilot settings from Rust
+  useEffect(() => {
+    invoke<CopilotConfig>("get_copilot_config")
+      .then(setCopilotConfig)
+      .catch(console.error);
+  }, []);
+
+  const updateCopilotConfiguration = useCallback(
+    async (config: CopilotConfig) => {
+      try {
+        await invoke("set_copilot_config", { config });
+        setCopilotConfig(config);
+      } catch (error) {
+        console.error("Failed to update copilot config:", error);
+        setError(`Failed to update answer suggestions: ${error}`);
+      }
+    },
+    []
+  );
+
+  // Answers are written with the same prompt as manual ones
+  useEffect(() => {
+    if (!copilotConfig?.enabled) return;
+    const effectiveSystemPrompt = useSystemPrompt
+      ? systemPrompt || DEFAULT_SYSTEM_PROMPT
+      : contextContent || DEFAULT_SYSTEM_PROMPT;
+    invoke("set_copilot_context", { context: effectiveSystemPrompt }).catch(
+      console.error
+    );
+  }, [copilotConfig?.enabled, useSystemPrompt, systemPrompt, contextContent]);
+
+  // Stream copilot answers into the overlay
+  useEffect(() => {
+    const unlisteners: Array<() => void> = [];
+    let current: { streamId: string; question: string; answer: string } | null =
+      null;
+
+    const setupListeners = async () => {
+      unlisteners.push(
+        await listen<CopilotSuggestionEvent>("copilot-suggestion", (event) => {
+          // Registered synchronously, before any chunk of the stream arrives
+          current = {
+            streamId: event.payload.stream_id,
+            question: event.payload.question,
+            answer: "",
+          };
+          if (abortControllerRef.current) {
+            abortControllerRef.current.abort();
+          }
+          setLastTranscription(event.payload.question);
+          setLastAIResponse("");
+          setError("");
+          setIsAIProcessing(true);
+          setIsPopoverOpen(true);
+        })
+      );
+      unlisteners.push(
+        await listen<{ stream_id: string; chunk: string }>(
+          "chat_stream_chunk",
+          (event) => {
+            if (event.payload.stream_id !== current?.streamId) return;
+            current.answer += event.payload.chunk;
+            setLastAIResponse((prev) => prev + event.payload.chunk);
+          }
+        )
+      );
+      unlisteners.push(
+        await listen<{ stream_id: string }>("chat_stream_complete", (event) => {
+          if (event.payload.stream_id !== current?.streamId) return;
+          const { question, answer } = current;
+          current = null;
+          setIsAIProcessing(false);
+          if (!answer) return;
+          const timestamp = Date.now();
+          setConversation((prev) => ({
+            ...prev,
+            messages: [
+              {
+                id: generateMessageId("user", timestamp),
+                role: "user" as const,
+                content: question,
+                timestamp,
+              },
+              {
+                id: generateMessageId("assistant", timestamp + 1),
+                role: "assistant" as const,
+                content: answer,
+                timestamp: timestamp + 1,
+              },
+              ...prev.messages,
+            ],
+            updatedAt: timestamp,
+            title: prev.title || generateConversationTitle(question),
+          }));
+        })
+      );
+      unlisteners.push(
+        await listen<{ stream_id: string; error: string }>(
+          "chat_stream_error",
+          (event) => {
+            if (event.payload.stream_id !== current?.streamId) return;
+            current = null;
+            setIsAIProcessing(false);
+            setError(event.payload.error || "Failed to get AI response");
+          }
+        )
+      );
+      unlisteners.push(
+        await listen<{ stream_id: string }>("chat_stream_cancelled", (event) => {
+          if (event.payload.stream_id !== current?.streamId) return;
+          current = null;
+          setIsAIProcessing(false);
+        })
+      );
+    };
+
+    setupListeners();
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten());
+    };
+  }, []);
+
   // Update VAD configuration
   const updateVadConfiguration = useCallback(async (config: VadConfig) => {
     try {
@@ -878,6 +1162,7 @@ export function useSystemAudio() {
     isProcessing,
     isAIProcessing,
     lastTranscription,
+    partialTranscription,
     lastAIResponse,
     error,
     setupRequired,
@@ -910,6 +1195,9 @@ export function useSystemAudio() {
     // VAD configuration
     vadConfig,
     updateVadConfiguration,
+    // Answer copilot
+    copilotConfig,
+    updateCopilotConfiguration,
     // Continuous recording
     isContinuousMode,
     isRecordingInContinuousMode,
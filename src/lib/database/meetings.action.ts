@@ -1,5 +1,13 @@
+import { invoke } from "@tauri-apps/api/core";
 import { getDatabase } from "./config";
-import type { Meeting, MeetingInput } from "@/types/meetings";
+import type {
+  Meeting,
+  FollowUpEmail,
+  MeetingArchiveConfig,
+  MeetingInput,
+  MeetingNotes,
+  MeetingSegment,
+} from "@/types/meetings";
 
 interface DbMeeting {
   id: string;
@@ -11,6 +19,7 @@ interface DbMeeting {
   participants: string | null;
   calendar_event_id: string | null;
   status: string;
+  audio_path: string | null;
   created_at: number;
   updated_at: number;
 }
@@ -26,6 +35,7 @@ function dbToMeeting(row: DbMeeting): Meeting {
     participants: row.participants,
     calendarEventId: row.calendar_event_id,
     status: row.status as Meeting["status"],
+    audioPath: row.audio_path ?? null,
     createdAt: row.created_at,
     updatedAt: row.updated_at,
   };
@@ -63,6 +73,7 @@ export async function createMeeting(input: MeetingInput): Promise<Meeting> {
     participants: input.participants ?? null,
     calendarEventId: input.calendarEventId ?? null,
     status: input.status ?? "completed",
+    audioPath: null,
     createdAt: now,
     updatedAt: now,
   };
@@ -134,7 +145,67 @@ export async function updateMeeting(
   );
 }
 
+/** Speaker-attributed lines, written by Rust during a recording */
+export async function getMeetingSegments(
+  meetingId: string
+): Promise<MeetingSegment[]> {
+  return invoke<MeetingSegment[]>("get_meeting_segments", { meetingId });
+}
+
+/**
+ * Summarizes the transcript into structured notes (also saved as the
+ * markdown summary) and marks the meeting completed.
+ */
+export async function summarizeMeeting(
+  meetingId: string
+): Promise<MeetingNotes | null> {
+  return invoke<MeetingNotes | null>("summarize_meeting", { meetingId });
+}
+
+export async function getMeetingNotes(
+  meetingId: string
+): Promise<MeetingNotes | null> {
+  return invoke<MeetingNotes | null>("get_meeting_notes", { meetingId });
+}
+
+export async function draftFollowUpEmail(
+  meetingId: string
+): Promise<FollowUpEmail> {
+  return invoke<FollowUpEmail>("draft_follow_up_email", { meetingId });
+}
+
+export async function getMeetingArchiveConfig(): Promise<MeetingArchiveConfig> {
+  return invoke<MeetingArchiveConfig>("get_meeting_archive_config");
+}
+
+export async function setMeetingArchiveConfig(
+  config: MeetingArchiveConfig
+): Promise<void> {
+  await invoke("set_meeting_archive_config", { config });
+}
+
+/**
+ * Transcribes a meeting again from its archived audio, with the given
+ * on-device Whisper model or the usual providers. Returns the line count.
+ */
+export async function retranscribeMeeting(
+  meetingId: string,
+  modelPath?: string | null,
+  language?: string | null
+): Promise<number> {
+  return invoke<number>("retranscribe_meeting", {
+    meetingId,
+    modelPath: modelPath ?? null,
+    language: language ?? null,
+  });
+}
+
 export async function deleteMeeting(id: string): Promise<void> {
+  await invoke("delete_meeting_audio", { meetingId: id }).catch(console.error);
   const db = await getDatabase();
+  await db.execute(`DELETE FROM meeting_audio_chunks WHERE meeting_id = $1`, [
+    id,
+  ]);
+  await db.execute(`DELETE FROM meeting_segments WHERE meeting_id = $1`, [id]);
   await db.execute(`DELETE FROM meetings WHERE id = $1`, [id]);
 }
@@ -8,10 +8,14 @@ import {
 import { Message, TYPE_PROVIDER } from "@/types";
 import { fetch as tauriFetch } from "@tauri-apps/plugin-http";
 import { invoke } from "@tauri-apps/api/core";
-import { listen } from "@tauri-apps/api/event";
 import curl2Json from "@bany/curl-to-json";
 import { shouldUseCloakAPI } from "./cloak.api";
-import { CHUNK_POLL_INTERVAL_MS } from "../chat-constants";
+import { CHUNK_POLL_INTERVAL_MS, generateRequestId } from "../chat-constants";
+import {
+  cancelChatStream,
+  ChatStreamSource,
+  listenToChatStream,
+} from "./chat-stream.function";
 import { getResponseSettings, RESPONSE_LENGTHS, LANGUAGES } from "@/lib";
 import { MARKDOWN_FORMATTING_INSTRUCTIONS } from "@/config/constants";
 
@@ -50,6 +54,8 @@ async function* fetchCloakAIResponse(params: {
   imagesBase64?: string[];
   history?: Message[];
   signal?: AbortSignal;
+  useKnowledge?: boolean;
+  onSources?: (sources: ChatStreamSource[]) => void;
 }): AsyncIterable<string> {
   try {
     const {
@@ -58,6 +64,8 @@ async function* fetchCloakAIResponse(params: {
       imagesBase64 = [],
       history = [],
       signal,
+      useKnowledge = false,
+      onSources,
     } = params;
 
     // Check if already aborted before starting
@@ -82,42 +90,37 @@ async function* fetchCloakAIResponse(params: {
       imageBase64 = imagesBase64.length === 1 ? imagesBase64[0] : imagesBase64;
     }
 
-    // Set up streaming event listener
-    let streamComplete = false;
-    const streamChunks: string[] = [];
-
-    const unlisten = await listen("chat_stream_chunk", (event) => {
-      const chunk = event.payload as string;
-      streamChunks.push(chunk);
-    });
-
-    const unlistenComplete = await listen("chat_stream_complete", () => {
-      streamComplete = true;
-    });
+    // Set up streaming event listener for this request only
+    const streamId = generateRequestId();
+    const stream = await listenToChatStream(streamId);
 
     try {
       // Check if aborted before starting invoke
       if (signal?.aborted) {
-        unlisten();
-        unlistenComplete();
         return;
       }
 
-      // Start the streaming request using the new API response endpoint
+      // Start the streaming request; resolves once the response starts streaming
       await invoke("chat_stream_response", {
         userMessage,
         systemPrompt,
         imageBase64,
         history: historyString,
+        streamId,
+        options: {
+          // Let the model search the knowledge base, look at the screen, etc.
+          enableTools: true,
+          useKnowledge,
+        },
       });
 
       // Yield chunks as they come in
       let lastIndex = 0;
-      while (!streamComplete) {
+      let sourcesReported = false;
+      while (!stream.isDone()) {
         // Check if aborted during streaming
         if (signal?.aborted) {
-          unlisten();
-          unlistenComplete();
+          await cancelChatStream(streamId);
           return;
         }
 
@@ -128,32 +131,38 @@ async function* fetchCloakAIResponse(params: {
 
         // Check again after timeout
         if (signal?.aborted) {
-          unlisten();
-          unlistenComplete();
+          await cancelChatStream(streamId);
           return;
         }
 
+        if (!sourcesReported && stream.sources.length > 0) {
+          sourcesReported = true;
+          onSources?.(stream.sources);
+        }
+
         // Yield any new chunks
-        for (let i = lastIndex; i < streamChunks.length; i++) {
-          yield streamChunks[i];
+        for (let i = lastIndex; i < stream.chunks.length; i++) {
+          yield stream.chunks[i];
         }
-        lastIndex = streamChunks.length;
+        lastIndex = stream.chunks.length;
       }
 
       // Final abort check before yielding remaining chunks
       if (signal?.aborted) {
-        unlisten();
-        unlistenComplete();
         return;
       }
 
       // Yield any remaining chunks
-      for (let i = lastIndex; i < streamChunks.length; i++) {
-        yield streamChunks[i];
+      for (let i = lastIndex; i < stream.chunks.length; i++) {
+        yield stream.chunks[i];
+      }
+
+      const streamError = stream.getError();
+      if (streamError) {
+        throw new Error(streamError);
       }
     } finally {
-      unlisten();
-      unlistenComplete();
+      stream.unlisten();
     }
   } catch (error) {
     const errorMessage = error instanceof Error ? error.message : String(error);
@@ -172,6 +181,9 @@ export async function* fetchAIResponse(params: {
   userMessage: string;
   imagesBase64?: string[];
   signal?: AbortSignal;
+  /** Answer with cited knowledge base context (Cloak API path only) */
+  useKnowledge?: boolean;
+  onSources?: (sources: ChatStreamSource[]) => void;
 }): AsyncIterable<string> {
   try {
     const {
@@ -198,6 +210,8 @@ export async function* fetchAIResponse(params: {
         imagesBase64,
         history,
         signal,
+        useKnowledge: params.useKnowledge,
+        onSources: params.onSources,
       });
       return;
     }
@@ -0,0 +1,128 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+interface ChatStreamEvent {
+  stream_id: string;
+}
+
+interface ChatStreamChunkEvent extends ChatStreamEvent {
+  chunk: string;
+}
+
+interface ChatStreamErrorEvent extends ChatStreamEvent {
+  error: string;
+}
+
+export interface ChatToolCallEvent extends ChatStreamEvent {
+  id: string;
+  name: string;
+  /** JSON-encoded arguments chosen by the model */
+  arguments: string;
+}
+
+export interface ChatToolResultEvent extends ChatStreamEvent {
+  id: string;
+  name: string;
+  content: string;
+  is_error: boolean;
+}
+
+/** A knowledge item cited as `[citation]` in a knowledge-augmented answer */
+export interface ChatStreamSource {
+  citation: number;
+  item_id: string;
+  item_type: string;
+  title: string;
+  chunk_indexes: number[];
+}
+
+interface ChatStreamSourcesEvent extends ChatStreamEvent {
+  sources: ChatStreamSource[];
+}
+
+export type ChatToolEvent =
+  | { type: "call"; call: ChatToolCallEvent }
+  | { type: "result"; result: ChatToolResultEvent };
+
+export interface ChatStreamListener {
+  /** Chunks received so far, in arrival order */
+  chunks: string[];
+  /** Knowledge items the answer may cite (only with `useKnowledge`) */
+  sources: ChatStreamSource[];
+  /** Tool calls and their results, in arrival order */
+  toolEvents: ChatToolEvent[];
+  /** True once the stream has completed, failed or been cancelled */
+  isDone: () => boolean;
+  /** Error reported after the stream started, if any */
+  getError: () => string | null;
+  unlisten: () => void;
+}
+
+/**
+ * Subscribe to the events of a single `chat_stream_response` call.
+ * Call this before invoking the command so no chunk is missed.
+ */
+export async function listenToChatStream(
+  streamId: string
+): Promise<ChatStreamListener> {
+  const chunks: string[] = [];
+  const sources: ChatStreamSource[] = [];
+  const toolEvents: ChatToolEvent[] = [];
+  let done = false;
+  let error: string | null = null;
+
+  const unlisteners = await Promise.all([
+    listen<ChatStreamChunkEvent>("chat_stream_chunk", (event) => {
+      if (event.payload.stream_id === streamId) {
+        chunks.push(event.payload.chunk);
+      }
+    }),
+    listen<ChatStreamSourcesEvent>("chat_stream_sources", (event) => {
+      if (event.payload.stream_id === streamId) {
+        sources.push(...event.payload.sources);
+      }
+    }),
+    listen<ChatToolCallEvent>("chat_tool_call", (event) => {
+      if (event.payload.stream_id === streamId) {
+        toolEvents.push({ type: "call", call: event.payload });
+      }
+    }),
+    listen<ChatToolResultEvent>("chat_tool_result", (event) => {
+      if (event.payload.stream_id === streamId) {
+        toolEvents.push({ type: "result", result: event.payload });
+      }
+    }),
+    listen<ChatStreamEvent>("chat_stream_complete", (event) => {
+      if (event.payload.stream_id === streamId) {
+        done = true;
+      }
+    }),
+    listen<ChatStreamErrorEvent>("chat_stream_error", (event) => {
+      if (event.payload.stream_id === streamId) {
+        error = event.payload.error;
+        done = true;
+      }
+    }),
+    listen<ChatStreamEvent>("chat_stream_cancelled", (event) => {
+      if (event.payload.stream_id === streamId) {
+        done = true;
+      }
+    }),
+  ]);
+
+  return {
+    chunks,
+    sources,
+    toolEvents,
+    isDone: () => done,
+    getError: () => error,
+    unlisten: () => unlisteners.forEach((unlisten) => unlisten()),
+  };
+}
+
+/**
+ * Abort a running chat stream. Unknown or finished IDs are ignored.
+ */
+export async function cancelChatStream(streamId: string): Promise<void> {
+  await invoke("cancel_chat_stream", { streamId }).catch(() => {});
+}
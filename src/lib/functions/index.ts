@@ -1,4 +1,5 @@
 export * from "./ai-response.function";
+export * from "./chat-stream.function";
 export * from "./stt.function";
 export * from "./common.function";
 export * from "./cloak.api";
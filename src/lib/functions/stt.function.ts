@@ -10,20 +10,67 @@ import { TYPE_PROVIDER } from "@/types";
 import curl2Json from "@bany/curl-to-json";
 import { shouldUseCloakAPI } from "./cloak.api";
 
+// File name whose extension matches the blob's encoding; providers pick
+// their decoder from it
+function audioFileName(audio: File | Blob): string {
+  const type = audio.type.split(";")[0];
+  if (type === "audio/flac") return "audio.flac";
+  if (type === "audio/ogg") return "audio.ogg";
+  if (type === "audio/webm") return "audio.webm";
+  return "audio.wav";
+}
+
+/** A `speech-detected` utterance still held in Rust, referenced by ID */
+export interface SpeechSegmentRef {
+  segmentId: string;
+  mimeType: string;
+}
+
+type STTAudio = File | Blob | SpeechSegmentRef;
+
+function isSegmentRef(audio: STTAudio): audio is SpeechSegmentRef {
+  return "segmentId" in audio;
+}
+
+/** Loads a segment's encoded bytes from Rust as a raw ArrayBuffer */
+export async function loadSpeechSegment(
+  segment: SpeechSegmentRef
+): Promise<Blob> {
+  const bytes = await invoke<ArrayBuffer>("get_speech_segment", {
+    id: segment.segmentId,
+  });
+  return new Blob([bytes], { type: segment.mimeType });
+}
+
+/** Frees a segment in Rust once it has been transcribed */
+export async function releaseSpeechSegment(segmentId: string): Promise<void> {
+  await invoke("release_speech_segment", { id: segmentId }).catch(
+    console.error
+  );
+}
+
+// Rust transcription; segments are transcribed in place without the audio
+// crossing the bridge
+async function invokeTranscription(audio: STTAudio) {
+  type AudioResponse = {
+    success: boolean;
+    transcription?: string;
+    error?: string;
+  };
+  if (isSegmentRef(audio)) {
+    return invoke<AudioResponse>("transcribe_segment", {
+      segmentId: audio.segmentId,
+    });
+  }
+  const audioBase64 = await blobToBase64(audio);
+  return invoke<AudioResponse>("transcribe_audio", { audioBase64 });
+}
+
 // Cloak STT function
-async function fetchCloakSTT(audio: File | Blob): Promise<string> {
+async function fetchCloakSTT(audio: STTAudio): Promise<string> {
   try {
-    // Convert audio to base64
-    const audioBase64 = await blobToBase64(audio);
-
     // Call Tauri command
-    const response = await invoke<{
-      success: boolean;
-      transcription?: string;
-      error?: string;
-    }>("transcribe_audio", {
-      audioBase64,
-    });
+    const response = await invokeTranscription(audio);
 
     if (response.success && response.transcription) {
       return response.transcription;
@@ -36,13 +83,24 @@ async function fetchCloakSTT(audio: File | Blob): Promise<string> {
   }
 }
 
+// Local-first transcription; throws instead of returning the error text
+async function transcribeInRust(audio: STTAudio): Promise<string> {
+  const response = await invokeTranscription(audio);
+
+  if (!response.success || response.transcription === undefined) {
+    throw new Error(response.error || "Transcription failed");
+  }
+  return response.transcription;
+}
+
 export interface STTParams {
   provider: TYPE_PROVIDER | undefined;
   selectedProvider: {
     provider: string;
     variables: Record<string, string>;
   };
-  audio: File | Blob;
+  /** A recorded blob, or a segment from `speech-detected` */
+  audio: STTAudio;
 }
 
 /**
@@ -52,7 +110,7 @@ export async function fetchSTT(params: STTParams): Promise<string> {
   let warnings: string[] = [];
 
   try {
-    const { selectedProvider, audio } = params;
+    const { selectedProvider } = params;
 
     // Default to OpenAI Whisper if no provider is passed
     let provider = params.provider;
@@ -61,15 +119,30 @@ export async function fetchSTT(params: STTParams): Promise<string> {
       provider = SPEECH_TO_TEXT_PROVIDERS.find(p => p.id === "openai-whisper");
     }
 
+    // On-device Whisper first; Rust then tries its configured endpoints
+    const localWhisper = await getLocalWhisperConfig().catch(() => null);
+    if (localWhisper?.enabled) {
+      try {
+        return await transcribeInRust(params.audio);
+      } catch (error) {
+        if (localWhisper.local_only) throw error;
+        console.warn("Local transcription failed, using provider:", error);
+      }
+    }
+
     // Check if we should use Cloak API instead
     const useCloakAPI = await shouldUseCloakAPI();
     if (useCloakAPI) {
-      return await fetchCloakSTT(audio);
+      return await fetchCloakSTT(params.audio);
     }
 
     if (!provider) throw new Error("Speech-to-Text Provider configuration missing");
     if (!selectedProvider) throw new Error("Selected provider not provided");
-    if (!audio) throw new Error("Audio file is required");
+    if (!params.audio) throw new Error("Audio file is required");
+    // Custom providers need the bytes themselves
+    const audio = isSegmentRef(params.audio)
+      ? await loadSpeechSegment(params.audio)
+      : params.audio;
 
     let curlJson: any;
     try {
@@ -145,7 +218,7 @@ export async function fetchSTT(params: STTParams): Promise<string> {
       const freshBlob = new Blob([await audio.arrayBuffer()], {
         type: audio.type,
       });
-      form.append("file", freshBlob, "audio.wav");
+      form.append("file", freshBlob, audioFileName(audio));
       const headerKeys = Object.keys(headers).map((k) =>
         k.toUpperCase().replace(/[-_]/g, "")
       );
@@ -190,10 +263,16 @@ export async function fetchSTT(params: STTParams): Promise<string> {
       delete finalHeaders["Content-Type"];
       body = form;
     } else if (isBinaryUpload) {
-      // Deepgram-style: raw binary body
+      // Deepgram-style: raw binary body, typed with the actual encoding
       body = new Blob([await audio.arrayBuffer()], {
         type: audio.type,
       });
+      if (audio.type) {
+        for (const key of Object.keys(finalHeaders)) {
+          if (key.toLowerCase() === "content-type") delete finalHeaders[key];
+        }
+        finalHeaders["Content-Type"] = audio.type;
+      }
     } else {
       // Google-style: JSON payload with base64
       allVariables.AUDIO = await blobToBase64(audio);
@@ -254,3 +333,75 @@ export async function fetchSTT(params: STTParams): Promise<string> {
     throw new Error(msg);
   }
 }
+
+export type RealtimeSttProvider = "deepgram" | "openai" | "assemblyai";
+
+/** Streaming transcription settings, mirrored from `StreamingSttConfig` in Rust */
+export interface StreamingSttConfig {
+  enabled: boolean;
+  provider: RealtimeSttProvider;
+  api_key?: string | null;
+  model?: string | null;
+  language?: string | null;
+  /** Replaces the provider's WebSocket URL */
+  url?: string | null;
+}
+
+/** Side of a dual capture: the microphone is "me", system audio is "them" */
+export type AudioSource = "me" | "them";
+
+/** Payload of `transcript-partial` and `transcript-final` events */
+export interface StreamingTranscriptEvent {
+  text: string;
+  provider: string;
+  /** Set when the transcript comes from a dual capture */
+  source?: AudioSource;
+  /** Diarized speaker number of system audio (Speaker 1, 2, ...) */
+  speaker?: number;
+}
+
+/**
+ * Payload of `speech-detected`: an utterance kept in Rust. Pass
+ * `{ segmentId: id, mimeType: mime_type }` to `fetchSTT`, then release it.
+ */
+export interface SpeechSegmentEvent {
+  id: string;
+  /** Encoding of the segment (audio/wav, audio/flac or audio/ogg) */
+  mime_type: string;
+  duration_secs: number;
+  /** Set when the utterance comes from a dual capture */
+  source?: AudioSource;
+  /** Diarized speaker number of system audio (Speaker 1, 2, ...) */
+  speaker?: number;
+}
+
+export async function getStreamingSttConfig(): Promise<StreamingSttConfig> {
+  return invoke<StreamingSttConfig>("get_streaming_stt_config");
+}
+
+export async function setStreamingSttConfig(
+  config: StreamingSttConfig
+): Promise<void> {
+  await invoke("set_streaming_stt_config", { config });
+}
+
+/** On-device Whisper settings, mirrored from `LocalWhisperConfig` in Rust */
+export interface LocalWhisperConfig {
+  enabled: boolean;
+  /** GGML model file; defaults to the first `*.bin` in `<app data>/models/whisper` */
+  model_path?: string | null;
+  language?: string | null;
+  threads?: number | null;
+  /** Never fall back to cloud transcription */
+  local_only: boolean;
+}
+
+export async function getLocalWhisperConfig(): Promise<LocalWhisperConfig> {
+  return invoke<LocalWhisperConfig>("get_local_whisper_config");
+}
+
+export async function setLocalWhisperConfig(
+  config: LocalWhisperConfig
+): Promise<void> {
+  await invoke("set_local_whisper_config", { config });
+}
@@ -46,14 +46,36 @@ export function chunkText(text: string): string[] {
   return chunks.filter((c) => c.length >= MIN_CHUNK_CHARS || chunks.length === 1);
 }
 
+export type EmbeddingBackend = "local" | "cloak" | "openai";
+
+export interface EmbeddingConfig {
+  backend: EmbeddingBackend;
+  /** Base URL of an OpenAI-compatible server (openai backend only) */
+  base_url?: string | null;
+  api_key?: string | null;
+  model?: string | null;
+}
+
 /**
- * Generate embedding for one text via Cloak API (Tauri command).
+ * Generate embedding for one text with the configured backend (Tauri command).
  */
 export async function generateEmbeddingForText(text: string): Promise<number[]> {
   const result = await invoke<number[]>("generate_embedding", { text });
   return result;
 }
 
+export async function getEmbeddingConfig(): Promise<EmbeddingConfig> {
+  return invoke<EmbeddingConfig>("get_embedding_config");
+}
+
+/**
+ * Switch embedding backend. Vectors from different backends are not comparable,
+ * so existing items should be re-embedded afterwards.
+ */
+export async function setEmbeddingConfig(config: EmbeddingConfig): Promise<void> {
+  await invoke("set_embedding_config", { config });
+}
+
 /**
  * Generate embeddings for all chunks of an item's content and persist them.
  * Deletes existing embeddings for the item first.
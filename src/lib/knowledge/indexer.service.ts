@@ -1,5 +1,6 @@
 import { invoke } from "@tauri-apps/api/core";
-import { listen } from "@tauri-apps/api/event";
+import { generateRequestId } from "@/lib/chat-constants";
+import { listenToChatStream } from "@/lib/functions/chat-stream.function";
 import { getAllConversations } from "@/lib/database/chat-history.action";
 import {
   getKnowledgeItemBySourceId,
@@ -9,7 +10,14 @@ import {
   getTagByName,
   addTagToItem,
 } from "@/lib/database/knowledge.action";
-import { embedKnowledgeItem } from "./embedding.service";
+
+/**
+ * Wake the Rust background indexer, which chunks and embeds new or changed
+ * items. With `force`, every item is re-embedded (e.g. after switching backend).
+ */
+export async function reindexKnowledge(force = false): Promise<void> {
+  await invoke("reindex_knowledge", { force });
+}
 
 const CHUNK_POLL_INTERVAL_MS = 50;
 
@@ -20,15 +28,8 @@ async function getCloakCompletion(
   systemPrompt: string,
   userMessage: string
 ): Promise<string> {
-  let streamComplete = false;
-  const streamChunks: string[] = [];
-
-  const unlisten = await listen("chat_stream_chunk", (event: { payload: string }) => {
-    streamChunks.push(event.payload);
-  });
-  const unlistenComplete = await listen("chat_stream_complete", () => {
-    streamComplete = true;
-  });
+  const streamId = generateRequestId();
+  const stream = await listenToChatStream(streamId);
 
   try {
     await invoke("chat_stream_response", {
@@ -36,15 +37,20 @@ async function getCloakCompletion(
       systemPrompt,
       imageBase64: null,
       history: null,
+      streamId,
     });
 
-    while (!streamComplete) {
+    while (!stream.isDone()) {
       await new Promise((r) => setTimeout(r, CHUNK_POLL_INTERVAL_MS));
     }
-    return streamChunks.join("");
+
+    const streamError = stream.getError();
+    if (streamError) {
+      throw new Error(streamError);
+    }
+    return stream.chunks.join("");
   } finally {
-    unlisten();
-    unlistenComplete();
+    stream.unlisten();
   }
 }
 
@@ -53,7 +59,8 @@ function generateId(prefix: string): string {
 }
 
 /**
- * Index one conversation into the knowledge base: create item, summary, embeddings, tags.
+ * Index one conversation into the knowledge base: create item, summary and tags.
+ * Chunking and embeddings are handled by the background indexer.
  */
 export async function indexConversation(conversationId: string): Promise<{
   success: boolean;
@@ -61,8 +68,9 @@ export async function indexConversation(conversationId: string): Promise<{
   error?: string;
   created?: boolean;
 }> {
+  // The background indexer may already have created the item without a summary
   const existing = await getKnowledgeItemBySourceId(conversationId);
-  if (existing) {
+  if (existing?.summary) {
     return { success: true, itemId: existing.id, created: false };
   }
 
@@ -88,20 +96,28 @@ export async function indexConversation(conversationId: string): Promise<{
     summary = "";
   }
 
-  const itemId = generateId("ki");
+  const itemId = existing?.id ?? generateId("ki");
   const now = Date.now();
 
   try {
-    await createKnowledgeItem({
-      id: itemId,
-      type: "conversation",
-      title,
-      content,
-      summary: summary || null,
-      sourceId: conversationId,
-      createdAt: now,
-      updatedAt: now,
-    });
+    if (existing) {
+      // Keep updatedAt so a summary alone doesn't trigger re-embedding
+      await updateKnowledgeItem(itemId, {
+        summary: summary || null,
+        updatedAt: existing.updatedAt,
+      });
+    } else {
+      await createKnowledgeItem({
+        id: itemId,
+        type: "conversation",
+        title,
+        content,
+        summary: summary || null,
+        sourceId: conversationId,
+        createdAt: now,
+        updatedAt: now,
+      });
+    }
   } catch (e) {
     return {
       success: false,
@@ -110,16 +126,7 @@ export async function indexConversation(conversationId: string): Promise<{
     };
   }
 
-  const embedResult = await embedKnowledgeItem(itemId, content);
-  if (embedResult.error) {
-    await updateKnowledgeItem(itemId, { summary: summary || null });
-    return {
-      success: true,
-      itemId,
-      error: embedResult.error,
-      created: true,
-    };
-  }
+  await reindexKnowledge().catch(() => {});
 
   let tagNames: string[] = [];
   try {
@@ -1,8 +1,10 @@
 import { invoke } from "@tauri-apps/api/core";
-import { getAllEmbeddings, knowledgeFtsSearch } from "@/lib/database/knowledge.action";
+import { knowledgeFtsSearch } from "@/lib/database/knowledge.action";
 import type { KnowledgeItem } from "@/types";
-import type { SearchResultChunk } from "@/types/knowledge.type";
-import { getKnowledgeItemById } from "@/lib/database/knowledge.action";
+import type {
+  KnowledgeItemType,
+  SearchResultChunk,
+} from "@/types/knowledge.type";
 
 /**
  * Cosine similarity between two vectors (same length).
@@ -21,61 +23,73 @@ export function cosineSimilarity(a: number[], b: number[]): number {
   return denom === 0 ? 0 : dot / denom;
 }
 
+export interface KnowledgeSearchFilters {
+  /** Tag IDs or names; items with any of them match */
+  tags?: string[];
+  /** Project IDs */
+  projects?: string[];
+  itemTypes?: KnowledgeItemType[];
+  itemIds?: string[];
+}
+
+export interface KnowledgeSearchResult {
+  itemId: string;
+  itemType: KnowledgeItemType;
+  title: string;
+  summary: string | null;
+  chunkIndex: number | null;
+  chunkText: string;
+  score: number;
+  vectorScore: number | null;
+  keywordScore: number | null;
+  updatedAt: number;
+}
+
 /**
- * Semantic search: embed query, compare to all stored embeddings, return ranked chunks.
+ * Hybrid search in Rust: cosine top-k over chunk embeddings fused with FTS5 BM25.
+ */
+export async function searchKnowledge(
+  query: string,
+  k = 10,
+  filters?: KnowledgeSearchFilters
+): Promise<KnowledgeSearchResult[]> {
+  const trimmed = query.trim();
+  if (!trimmed) return [];
+  return invoke<KnowledgeSearchResult[]>("search_knowledge", {
+    query: trimmed,
+    k,
+    filters,
+  });
+}
+
+/**
+ * Semantic search: ranked chunks for the query, optionally within one item.
  */
 export async function semanticSearch(
   query: string,
   limit = 10,
   itemIdFilter?: string
 ): Promise<SearchResultChunk[]> {
-  const trimmed = query.trim();
-  if (!trimmed) return [];
-
-  let queryEmbedding: number[];
+  let results: KnowledgeSearchResult[];
   try {
-    queryEmbedding = await invoke<number[]>("generate_embedding", { text: trimmed });
+    results = await searchKnowledge(
+      query,
+      limit,
+      itemIdFilter ? { itemIds: [itemIdFilter] } : undefined
+    );
   } catch (err) {
-    console.error("Embedding generation failed:", err);
+    console.error("Knowledge search failed:", err);
     return [];
   }
 
-  const all = await getAllEmbeddings();
-  const scored: { itemId: string; chunkIndex: number; chunkText: string; score: number }[] = [];
-
-  for (const row of all) {
-    if (itemIdFilter && row.itemId !== itemIdFilter) continue;
-    let vec: number[];
-    try {
-      vec = JSON.parse(row.embedding) as number[];
-    } catch {
-      continue;
-    }
-    const score = cosineSimilarity(queryEmbedding, vec);
-    scored.push({
-      itemId: row.itemId,
-      chunkIndex: row.chunkIndex,
-      chunkText: row.chunkText,
-      score,
-    });
-  }
-
-  scored.sort((a, b) => b.score - a.score);
-  const top = scored.slice(0, limit);
-
-  const results: SearchResultChunk[] = [];
-  for (const s of top) {
-    const item = await getKnowledgeItemById(s.itemId);
-    results.push({
-      itemId: s.itemId,
-      chunkIndex: s.chunkIndex,
-      chunkText: s.chunkText,
-      score: s.score,
-      title: item?.title,
-      summary: item?.summary ?? undefined,
-    });
-  }
-  return results;
+  return results.map((r) => ({
+    itemId: r.itemId,
+    chunkIndex: r.chunkIndex ?? 0,
+    chunkText: r.chunkText,
+    score: r.score,
+    title: r.title,
+    summary: r.summary ?? undefined,
+  }));
 }
 
 /**
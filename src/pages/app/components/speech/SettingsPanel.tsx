@@ -20,7 +20,13 @@ import {
   RotateCcwIcon,
   ChevronUpIcon,
 } from "lucide-react";
-import { VadConfig } from "@/hooks/useSystemAudio";
+import {
+  AudioEncoding,
+  CopilotConfig,
+  QuestionDetector,
+  VadConfig,
+  VadEngine,
+} from "@/hooks/useSystemAudio";
 import {
   PROMPT_TEMPLATES,
   getPromptTemplateById,
@@ -55,6 +61,9 @@ interface SettingsPanelProps {
   // VAD Config
   vadConfig: VadConfig;
   onUpdateVadConfig: (config: VadConfig) => void;
+  // Answer copilot; null until loaded
+  copilotConfig: CopilotConfig | null;
+  onUpdateCopilotConfig: (config: CopilotConfig) => void;
   // Context settings
   useSystemPrompt: boolean;
   setUseSystemPrompt: (value: boolean) => void;
@@ -65,6 +74,8 @@ interface SettingsPanelProps {
 export const SettingsPanel = ({
   vadConfig,
   onUpdateVadConfig,
+  copilotConfig,
+  onUpdateCopilotConfig,
   useSystemPrompt,
   setUseSystemPrompt,
   contextContent,
@@ -118,6 +129,15 @@ export const SettingsPanel = ({
       pre_speech_chunks: 12,
       noise_gate_threshold: 0.003,
       max_recording_duration_secs: 180,
+      engine: vadConfig.engine, // Keep current engine and model
+      silero: vadConfig.silero && { ...vadConfig.silero, threshold: 0.5 },
+      encoding: vadConfig.encoding, // Keep upload format
+      echo_cancellation: {
+        enabled: vadConfig.echo_cancellation?.enabled ?? true,
+        strength: 0.6,
+      },
+      noise_suppression: { microphone: true, system_audio: false },
+      follow_default_device: false,
     };
     onUpdateVadConfig(defaultConfig);
   };
@@ -214,6 +234,109 @@ export const SettingsPanel = ({
             )}
           </div>
 
+          {/* Answer Suggestions Section */}
+          {copilotConfig && (
+            <div className="space-y-3 pt-3 border-t border-border/50">
+              <h4 className="text-xs font-semibold text-muted-foreground uppercase tracking-wide">
+                Answer Suggestions
+              </h4>
+
+              <div className="flex items-center justify-between gap-4">
+                <div className="flex-1">
+                  <Label className="text-xs font-medium">
+                    Suggest Answers Automatically
+                  </Label>
+                  <p className="text-[10px] text-muted-foreground mt-0.5">
+                    {copilotConfig.enabled
+                      ? "Answers only when the other person asks a question"
+                      : "Every transcript gets an answer"}
+                  </p>
+                </div>
+                <Switch
+                  checked={copilotConfig.enabled}
+                  onCheckedChange={(enabled) =>
+                    onUpdateCopilotConfig({ ...copilotConfig, enabled })
+                  }
+                />
+              </div>
+
+              {copilotConfig.enabled && (
+                <>
+                  <div className="space-y-2">
+                    <Label className="text-xs font-medium">
+                      Question Detection
+                    </Label>
+                    <Select
+                      value={copilotConfig.detector}
+                      onValueChange={(value) =>
+                        onUpdateCopilotConfig({
+                          ...copilotConfig,
+                          detector: value as QuestionDetector,
+                        })
+                      }
+                    >
+                      <SelectTrigger className="w-full h-8 text-xs">
+                        <SelectValue />
+                      </SelectTrigger>
+                      <SelectContent>
+                        <SelectItem value="heuristic" className="text-xs">
+                          Fast (question marks and phrasing)
+                        </SelectItem>
+                        <SelectItem value="llm" className="text-xs">
+                          AI (more accurate, uses your model)
+                        </SelectItem>
+                      </SelectContent>
+                    </Select>
+                  </div>
+
+                  <div className="space-y-2">
+                    <Label className="text-xs font-medium flex items-center justify-between">
+                      <span>Wait After Speech</span>
+                      <span className="text-muted-foreground font-normal">
+                        {(copilotConfig.debounce_ms / 1000).toFixed(1)}s
+                      </span>
+                    </Label>
+                    <Slider
+                      value={[copilotConfig.debounce_ms]}
+                      onValueChange={([value]) =>
+                        onUpdateCopilotConfig({
+                          ...copilotConfig,
+                          debounce_ms: value,
+                        })
+                      }
+                      min={400}
+                      max={4000}
+                      step={100}
+                      className="w-full"
+                    />
+                  </div>
+
+                  <div className="space-y-2">
+                    <Label className="text-xs font-medium flex items-center justify-between">
+                      <span>Minimum Time Between Answers</span>
+                      <span className="text-muted-foreground font-normal">
+                        {copilotConfig.min_interval_secs}s
+                      </span>
+                    </Label>
+                    <Slider
+                      value={[copilotConfig.min_interval_secs]}
+                      onValueChange={([value]) =>
+                        onUpdateCopilotConfig({
+                          ...copilotConfig,
+                          min_interval_secs: value,
+                        })
+                      }
+                      min={0}
+                      max={60}
+                      step={5}
+                      className="w-full"
+                    />
+                  </div>
+                </>
+              )}
+            </div>
+          )}
+
           {/* Context Section */}
           <div className="space-y-3 pt-3 border-t border-border/50">
             <h4 className="text-xs font-semibold text-muted-foreground uppercase tracking-wide">
@@ -292,9 +415,221 @@ export const SettingsPanel = ({
 
             {showAdvanced && (
               <div className="mt-3 space-y-3">
+                <div className="space-y-2">
+                  <Label className="text-xs font-medium">Upload Format</Label>
+                  <Select
+                    value={vadConfig.encoding ?? "wav"}
+                    onValueChange={(value) =>
+                      onUpdateVadConfig({
+                        ...vadConfig,
+                        encoding: value as AudioEncoding,
+                      })
+                    }
+                  >
+                    <SelectTrigger className="w-full h-8 text-xs">
+                      <SelectValue />
+                    </SelectTrigger>
+                    <SelectContent>
+                      <SelectItem value="wav" className="text-xs">
+                        WAV (uncompressed)
+                      </SelectItem>
+                      <SelectItem value="flac" className="text-xs">
+                        FLAC (lossless, smaller)
+                      </SelectItem>
+                      <SelectItem value="opus" className="text-xs">
+                        Opus (smallest, for slow connections)
+                      </SelectItem>
+                    </SelectContent>
+                  </Select>
+                  <p className="text-[10px] text-muted-foreground">
+                    Check that your speech provider accepts the format before
+                    switching from WAV
+                  </p>
+                </div>
+
+                <div className="space-y-2">
+                  <div className="flex items-center justify-between gap-4">
+                    <div className="flex-1">
+                      <Label className="text-xs font-medium">
+                        Echo Cancellation
+                      </Label>
+                      <p className="text-[10px] text-muted-foreground mt-0.5">
+                        Removes the other side of the call from your microphone
+                        in meetings when you use speakers
+                      </p>
+                    </div>
+                    <Switch
+                      checked={vadConfig.echo_cancellation?.enabled ?? true}
+                      onCheckedChange={(enabled) =>
+                        onUpdateVadConfig({
+                          ...vadConfig,
+                          echo_cancellation: {
+                            strength:
+                              vadConfig.echo_cancellation?.strength ?? 0.6,
+                            enabled,
+                          },
+                        })
+                      }
+                    />
+                  </div>
+                  {(vadConfig.echo_cancellation?.enabled ?? true) && (
+                    <>
+                      <Label className="text-xs font-medium flex items-center justify-between">
+                        <span>Suppression Strength</span>
+                        <span className="text-muted-foreground font-normal">
+                          {Math.round(
+                            (vadConfig.echo_cancellation?.strength ?? 0.6) * 100
+                          )}
+                          %
+                        </span>
+                      </Label>
+                      <Slider
+                        value={[vadConfig.echo_cancellation?.strength ?? 0.6]}
+                        onValueChange={([strength]) =>
+                          onUpdateVadConfig({
+                            ...vadConfig,
+                            echo_cancellation: { enabled: true, strength },
+                          })
+                        }
+                        min={0}
+                        max={1}
+                        step={0.05}
+                        className="w-full"
+                      />
+                      <p className="text-[10px] text-muted-foreground">
+                        Higher removes more echo but can soften your voice when
+                        you both talk at once
+                      </p>
+                    </>
+                  )}
+                </div>
+
+                <div className="space-y-2">
+                  <div>
+                    <Label className="text-xs font-medium">
+                      Noise Suppression
+                    </Label>
+                    <p className="text-[10px] text-muted-foreground mt-0.5">
+                      Removes steady background noise like fans and hum before
+                      speech detection
+                    </p>
+                  </div>
+                  <div className="flex items-center justify-between gap-4">
+                    <Label className="text-xs">Microphone</Label>
+                    <Switch
+                      checked={vadConfig.noise_suppression?.microphone ?? true}
+                      onCheckedChange={(microphone) =>
+                        onUpdateVadConfig({
+                          ...vadConfig,
+                          noise_suppression: {
+                            system_audio:
+                              vadConfig.noise_suppression?.system_audio ?? false,
+                            microphone,
+                          },
+                        })
+                      }
+                    />
+                  </div>
+                  <div className="flex items-center justify-between gap-4">
+                    <Label className="text-xs">System Audio</Label>
+                    <Switch
+                      checked={
+                        vadConfig.noise_suppression?.system_audio ?? false
+                      }
+                      onCheckedChange={(system_audio) =>
+                        onUpdateVadConfig({
+                          ...vadConfig,
+                          noise_suppression: {
+                            microphone:
+                              vadConfig.noise_suppression?.microphone ?? true,
+                            system_audio,
+                          },
+                        })
+                      }
+                    />
+                  </div>
+                </div>
+
+                <div className="flex items-center justify-between gap-4">
+                  <div>
+                    <Label className="text-xs font-medium">
+                      Follow Default Device
+                    </Label>
+                    <p className="text-[10px] text-muted-foreground mt-0.5">
+                      Keeps capturing when you switch to headphones or a new
+                      default device mid-session
+                    </p>
+                  </div>
+                  <Switch
+                    checked={vadConfig.follow_default_device ?? false}
+                    onCheckedChange={(follow_default_device) =>
+                      onUpdateVadConfig({ ...vadConfig, follow_default_device })
+                    }
+                  />
+                </div>
+
                 {/* VAD-specific advanced settings */}
                 {vadConfig.enabled && (
                   <>
+                    <div className="space-y-2">
+                      <Label className="text-xs font-medium">
+                        Detection Engine
+                      </Label>
+                      <Select
+                        value={vadConfig.engine ?? "energy"}
+                        onValueChange={(value) =>
+                          onUpdateVadConfig({
+                            ...vadConfig,
+                            engine: value as VadEngine,
+                          })
+                        }
+                      >
+                        <SelectTrigger className="w-full h-8 text-xs">
+                          <SelectValue />
+                        </SelectTrigger>
+                        <SelectContent>
+                          <SelectItem value="energy" className="text-xs">
+                            Energy (volume based)
+                          </SelectItem>
+                          <SelectItem value="silero" className="text-xs">
+                            Silero (neural, on-device)
+                          </SelectItem>
+                        </SelectContent>
+                      </Select>
+                      <p className="text-[10px] text-muted-foreground">
+                        {vadConfig.engine === "silero"
+                          ? "Ignores music and noise better. Needs a Silero ONNX model in the app's models/vad folder"
+                          : "Fast and model-free, but loud noise can count as speech"}
+                      </p>
+                    </div>
+
+                    {vadConfig.engine === "silero" && (
+                      <div className="space-y-2">
+                        <Label className="text-xs font-medium flex items-center justify-between">
+                          <span>Speech Probability Threshold</span>
+                          <span className="text-muted-foreground font-normal">
+                            {(vadConfig.silero?.threshold ?? 0.5).toFixed(2)}
+                          </span>
+                        </Label>
+                        <Slider
+                          value={[vadConfig.silero?.threshold ?? 0.5]}
+                          onValueChange={([value]) =>
+                            onUpdateVadConfig({
+                              ...vadConfig,
+                              silero: {
+                                model_path: vadConfig.silero?.model_path ?? null,
+                                threshold: value,
+                              },
+                            })
+                          }
+                          min={0.2}
+                          max={0.9}
+                          step={0.05}
+                          className="w-full"
+                        />
+                      </div>
+                    )}
+
                     <div className="space-y-2">
                       <Label className="text-xs font-medium flex items-center justify-between">
                         <span>Speech Sensitivity (Raw)</span>
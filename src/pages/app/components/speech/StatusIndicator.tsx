@@ -1,5 +1,6 @@
 import { AlertCircleIcon, LoaderIcon } from "lucide-react";
 import { cn } from "@/lib/utils";
+import type { SignalLevel } from "@/hooks/useSystemAudio";
 
 type Props = {
   setupRequired: boolean;
@@ -7,7 +8,7 @@ type Props = {
   isProcessing: boolean;
   isAIProcessing: boolean;
   capturing: boolean;
-  signalLevel?: { rms: number; peak: number };
+  signalLevel?: SignalLevel;
 };
 
 export const StatusIndicator = ({
@@ -52,6 +53,9 @@ export const StatusIndicator = ({
                   "w-[2px] rounded-full transition-all duration-150",
                   (signalLevel?.rms || 0) * 100 > i * 1.5
                     ? "bg-green-500 h-[100%]"
+                    : // Level that noise suppression removed
+                    (signalLevel?.rms_before || 0) * 100 > i * 1.5
+                    ? "bg-green-500/30 h-[100%]"
                     : "bg-muted-foreground/30 h-1"
                 )}
               />
@@ -57,6 +57,8 @@ export const SystemAudio = (props: useSystemAudioType) => {
     handleQuickActionClick,
     vadConfig,
     updateVadConfiguration,
+    copilotConfig,
+    updateCopilotConfiguration,
     isRecordingInContinuousMode,
     recordingProgress,
     manualStopAndSend,
@@ -361,6 +363,8 @@ export const SystemAudio = (props: useSystemAudioType) => {
                     <SettingsPanel
                       vadConfig={vadConfig}
                       onUpdateVadConfig={updateVadConfiguration}
+                      copilotConfig={copilotConfig}
+                      onUpdateCopilotConfig={updateCopilotConfiguration}
                       useSystemPrompt={useSystemPrompt}
                       setUseSystemPrompt={setUseSystemPrompt}
                       contextContent={contextContent}
@@ -6,12 +6,19 @@ import {
   Header,
   Button,
 } from "@/components";
-import { MicIcon, RefreshCwIcon, HeadphonesIcon } from "lucide-react";
+import {
+  MicIcon,
+  RefreshCwIcon,
+  HeadphonesIcon,
+  AppWindowIcon,
+} from "lucide-react";
 import { useState, useEffect } from "react";
 import { useApp } from "@/contexts";
 import { STORAGE_KEYS } from "@/config/constants";
 import { safeLocalStorage } from "@/lib/storage";
+import { isLinux } from "@/lib/platform";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 
 export const AudioSelection = () => {
   const { selectedAudioDevices, setSelectedAudioDevices } = useApp();
@@ -31,6 +38,10 @@ export const AudioSelection = () => {
     input: [],
     output: [],
   });
+  // Applications playing audio, capturable on their own (Linux only)
+  const [applications, setApplications] = useState<
+    { id: string; name: string; media: string | null }[]
+  >([]);
 
   // Save devices to localStorage
   const saveToStorage = (newDevices: typeof selectedAudioDevices) => {
@@ -53,6 +64,18 @@ export const AudioSelection = () => {
         ),
       ]);
 
+      if (isLinux()) {
+        const playing = await invoke<
+          { id: string; name: string; media: string | null }[]
+        >("get_audio_applications").catch(() => []);
+        // One entry per application; all of its streams are captured
+        setApplications(
+          playing.filter(
+            (app, i) => playing.findIndex((a) => a.name === app.name) === i
+          )
+        );
+      }
+
       setDevices({
         input:
           inputDevices.map((input) => ({
@@ -81,6 +104,7 @@ export const AudioSelection = () => {
         const defaultOutput = outputDevices?.find((d) => d?.is_default);
 
         const newDevices = {
+          ...selectedAudioDevices,
           input: currentInputExists
             ? selectedAudioDevices.input
             : {
@@ -109,6 +133,16 @@ export const AudioSelection = () => {
     loadAudioDevices();
   }, []);
 
+  // Refresh when a device is plugged in or removed
+  useEffect(() => {
+    const unlisten = listen("audio-devices-changed", () => {
+      loadAudioDevices();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [selectedAudioDevices]);
+
   // Handle device selection changes
   const handleDeviceChange = (type: "input" | "output", deviceId: string) => {
     const deviceList = type === "input" ? devices.input : devices.output;
@@ -130,6 +164,15 @@ export const AudioSelection = () => {
     }, 3000);
   };
 
+  const handleApplicationChange = (value: string) => {
+    const newDevices = {
+      ...selectedAudioDevices,
+      application: value === "all" ? null : value,
+    };
+    setSelectedAudioDevices(newDevices);
+    saveToStorage(newDevices);
+  };
+
   return (
     <div id="audio" className="space-y-1 flex flex-col gap-4">
       {/* Microphone Input Section */}
@@ -295,6 +338,72 @@ export const AudioSelection = () => {
             </div>
           </div>
 
+          {/* Application Selection (Linux) */}
+          {isLinux() && (
+            <div className="space-y-2">
+              <Select
+                value={selectedAudioDevices.application || "all"}
+                onValueChange={handleApplicationChange}
+                disabled={isLoadingDevices}
+              >
+                <SelectTrigger className="w-full h-11 border-1 border-input/50 focus:border-primary/50 transition-colors">
+                  <div className="flex items-center gap-2">
+                    <AppWindowIcon className="size-4" />
+                    <div className="text-sm font-medium truncate">
+                      {selectedAudioDevices.application ||
+                        "All system audio"}
+                    </div>
+                  </div>
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="all">
+                    <div className="flex items-center gap-2">
+                      <AppWindowIcon className="size-4" />
+                      <div className="font-medium truncate">
+                        All system audio
+                      </div>
+                    </div>
+                  </SelectItem>
+                  {/* Keep a saved choice selectable while it is not playing */}
+                  {selectedAudioDevices.application &&
+                    !applications.some(
+                      (app) => app.name === selectedAudioDevices.application
+                    ) && (
+                      <SelectItem value={selectedAudioDevices.application}>
+                        <div className="flex items-center gap-2">
+                          <AppWindowIcon className="size-4" />
+                          <div className="font-medium truncate">
+                            {selectedAudioDevices.application}
+                          </div>
+                        </div>
+                      </SelectItem>
+                    )}
+                  {applications.map((app) => (
+                    <SelectItem key={app.id} value={app.name}>
+                      <div className="flex items-center gap-2">
+                        <AppWindowIcon className="size-4" />
+                        <div className="font-medium truncate">
+                          {app.name}
+                          {app.media && (
+                            <span className="text-muted-foreground font-normal">
+                              {" "}
+                              — {app.media}
+                            </span>
+                          )}
+                        </div>
+                      </div>
+                    </SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
+              <p className="text-xs text-muted-foreground">
+                Capture only one application, such as your call, so music and
+                notifications are left out. It has to be playing when capture
+                starts; refresh to see new applications.
+              </p>
+            </div>
+          )}
+
           {/* Success message */}
           {showSuccess.output && (
             <div className="text-xs text-green-500 bg-green-500/10 p-3 rounded-md">
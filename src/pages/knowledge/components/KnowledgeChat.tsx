@@ -1,8 +1,7 @@
 import { useState, useRef, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { listen } from "@tauri-apps/api/event";
 import { Button, ScrollArea, Markdown } from "@/components";
-import { semanticSearch } from "@/lib";
+import { generateRequestId, listenToChatStream, semanticSearch } from "@/lib";
 import { Loader2, SendIcon } from "lucide-react";
 
 const RAG_SYSTEM_PREFIX = `You are a helpful assistant with access to the user's knowledge base. Use the following context when relevant to answer the question. If the context doesn't contain enough information, say so.`;
@@ -38,31 +37,33 @@ export const KnowledgeChat = ({
         ? `${RAG_SYSTEM_PREFIX}\n\n## Context\n\n${context}`
         : RAG_SYSTEM_PREFIX + "\n\n(No relevant context found in the knowledge base.)";
 
-      let streamComplete = false;
-      const streamChunks: string[] = [];
+      const streamId = generateRequestId();
+      const stream = await listenToChatStream(streamId);
 
-      const unlisten = await listen("chat_stream_chunk", (event: { payload: string }) => {
-        streamChunks.push(event.payload);
-      });
-      const unlistenComplete = await listen("chat_stream_complete", () => {
-        streamComplete = true;
-      });
+      let fullResponse = "";
+      try {
+        await invoke("chat_stream_response", {
+          userMessage: text,
+          systemPrompt,
+          imageBase64: null,
+          history: null,
+          streamId,
+        });
 
-      await invoke("chat_stream_response", {
-        userMessage: text,
-        systemPrompt,
-        imageBase64: null,
-        history: null,
-      });
+        while (!stream.isDone()) {
+          await new Promise((r) => setTimeout(r, 50));
+          setStreamBuffer(stream.chunks.join(""));
+        }
 
-      while (!streamComplete) {
-        await new Promise((r) => setTimeout(r, 50));
-        setStreamBuffer(streamChunks.join(""));
+        const streamError = stream.getError();
+        if (streamError) {
+          throw new Error(streamError);
+        }
+        fullResponse = stream.chunks.join("");
+      } finally {
+        stream.unlisten();
       }
-      unlisten();
-      unlistenComplete();
 
-      const fullResponse = streamChunks.join("");
       setMessages((prev) => [...prev, { role: "assistant", content: fullResponse }]);
     } catch (err) {
       const msg = err instanceof Error ? err.message : String(err);
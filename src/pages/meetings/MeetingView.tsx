@@ -1,8 +1,15 @@
 import { useState, useEffect, useRef, useCallback } from "react";
 import { useParams, useNavigate } from "react-router-dom";
+import { listen } from "@tauri-apps/api/event";
 import { PageLayout } from "@/layouts";
 import { Button, Markdown } from "@/components";
 import { useMeetings } from "@/hooks/useMeetings";
+import { useMeetingCapture } from "@/hooks/useMeetingCapture";
+import {
+  draftFollowUpEmail,
+  retranscribeMeeting,
+} from "@/lib/database/meetings.action";
+import type { FollowUpEmail } from "@/types/meetings";
 import {
   ArrowLeft,
   Loader2,
@@ -10,6 +17,10 @@ import {
   Copy,
   Check,
   Pencil,
+  Mic,
+  Square,
+  RotateCcw,
+  Mail,
 } from "lucide-react";
 
 type Tab = "summary" | "notes" | "transcript";
@@ -30,8 +41,34 @@ const MeetingView = () => {
     updateNotes,
     updateTitle,
     generatingSummary,
+    setCurrentMeeting,
   } = useMeetings();
 
+  const appendTranscriptLine = useCallback(
+    (line: string) => {
+      setCurrentMeeting((prev) =>
+        prev
+          ? {
+              ...prev,
+              transcript: prev.transcript ? `${prev.transcript}\n${line}` : line,
+            }
+          : prev
+      );
+    },
+    [setCurrentMeeting]
+  );
+  // The recorder saved the summary, duration and participants
+  const reloadMeeting = useCallback(() => {
+    if (id) loadMeeting(id);
+  }, [id, loadMeeting]);
+  const {
+    recording,
+    processing,
+    error: captureError,
+    start: startRecording,
+    stop: stopRecording,
+  } = useMeetingCapture(id, appendTranscriptLine, reloadMeeting);
+
   const [activeTab, setActiveTab] = useState<Tab>("summary");
   const [notesValue, setNotesValue] = useState("");
   const [editingTitle, setEditingTitle] = useState(false);
@@ -44,12 +81,40 @@ const MeetingView = () => {
     if (id) loadMeeting(id);
   }, [id, loadMeeting]);
 
+  // Progress of a re-transcription from the archived audio, as "done/total"
+  const [retranscribing, setRetranscribing] = useState<string | null>(null);
+  const [retranscribeError, setRetranscribeError] = useState("");
+
+  const handleRetranscribe = useCallback(async () => {
+    if (!id) return;
+    setRetranscribing("0");
+    setRetranscribeError("");
+    const unlisten = await listen<{
+      meeting_id: string;
+      done: number;
+      total: number;
+    }>("meeting-retranscribe-progress", (event) => {
+      const { meeting_id, done, total } = event.payload;
+      if (meeting_id === id) setRetranscribing(`${done}/${total}`);
+    });
+    try {
+      await retranscribeMeeting(id);
+      await loadMeeting(id);
+    } catch (err) {
+      setRetranscribeError(`Failed to re-transcribe: ${err}`);
+    } finally {
+      unlisten();
+      setRetranscribing(null);
+    }
+  }, [id, loadMeeting]);
+
+  // Only on load: live transcript lines must not reset notes being typed
   useEffect(() => {
     if (currentMeeting) {
       setNotesValue(currentMeeting.notes);
       setTitleValue(currentMeeting.title);
     }
-  }, [currentMeeting]);
+  }, [currentMeeting?.id]);
 
   useEffect(() => {
     if (editingTitle) titleInputRef.current?.focus();
@@ -89,6 +154,33 @@ const MeetingView = () => {
     if (id) generateSummary(id);
   }, [id, generateSummary]);
 
+  const [emailDraft, setEmailDraft] = useState<FollowUpEmail | null>(null);
+  const [draftingEmail, setDraftingEmail] = useState(false);
+  const [emailError, setEmailError] = useState("");
+  const [emailCopied, setEmailCopied] = useState(false);
+
+  const handleDraftEmail = useCallback(async () => {
+    if (!id) return;
+    setDraftingEmail(true);
+    setEmailError("");
+    try {
+      setEmailDraft(await draftFollowUpEmail(id));
+    } catch (err) {
+      setEmailError(`Failed to draft email: ${err}`);
+    } finally {
+      setDraftingEmail(false);
+    }
+  }, [id]);
+
+  const handleCopyEmail = useCallback(() => {
+    if (!emailDraft) return;
+    navigator.clipboard.writeText(
+      `Subject: ${emailDraft.subject}\n\n${emailDraft.body}`
+    );
+    setEmailCopied(true);
+    setTimeout(() => setEmailCopied(false), 2000);
+  }, [emailDraft]);
+
   if (!currentMeeting) {
     return (
       <PageLayout title="Meeting" description="View meeting details">
@@ -116,9 +208,50 @@ const MeetingView = () => {
       title={currentMeeting.title}
       description="Summary, notes, and transcript"
       rightSlot={
-        <Button variant="ghost" size="sm" onClick={() => navigate("/meetings")}>
-          <ArrowLeft className="h-4 w-4 mr-1" /> Back
-        </Button>
+        <div className="flex items-center gap-2">
+          <Button
+            variant={recording ? "destructive" : "outline"}
+            size="sm"
+            onClick={recording ? stopRecording : startRecording}
+            disabled={processing}
+          >
+            {processing ? (
+              <>
+                <Loader2 className="h-4 w-4 mr-1 animate-spin" /> Summarizing
+              </>
+            ) : recording ? (
+              <>
+                <Square className="h-4 w-4 mr-1" /> Stop
+              </>
+            ) : (
+              <>
+                <Mic className="h-4 w-4 mr-1" /> Record
+              </>
+            )}
+          </Button>
+          {currentMeeting.audioPath && !recording && !processing && (
+            <Button
+              variant="outline"
+              size="sm"
+              onClick={handleRetranscribe}
+              disabled={retranscribing !== null}
+            >
+              {retranscribing !== null ? (
+                <>
+                  <Loader2 className="h-4 w-4 mr-1 animate-spin" />{" "}
+                  {retranscribing}
+                </>
+              ) : (
+                <>
+                  <RotateCcw className="h-4 w-4 mr-1" /> Re-transcribe
+                </>
+              )}
+            </Button>
+          )}
+          <Button variant="ghost" size="sm" onClick={() => navigate("/meetings")}>
+            <ArrowLeft className="h-4 w-4 mr-1" /> Back
+          </Button>
+        </div>
       }
     >
       <div className="max-w-3xl mx-auto space-y-6">
@@ -148,6 +281,12 @@ const MeetingView = () => {
               <Pencil className="h-4 w-4 opacity-0 group-hover:opacity-50 transition-opacity" />
             </h1>
           )}
+          {retranscribeError && (
+            <p className="text-xs text-destructive mt-1">{retranscribeError}</p>
+          )}
+          {captureError && (
+            <p className="text-xs text-destructive mt-1">{captureError}</p>
+          )}
           <p className="text-sm text-muted-foreground mt-1">
             {dateStr} at {timeStr}
             {currentMeeting.durationSeconds > 0 && (
@@ -253,6 +392,52 @@ const MeetingView = () => {
                   )}
                 </Button>
               )}
+              {currentMeeting.summary && currentMeeting.transcript && (
+                <Button
+                  variant="outline"
+                  size="sm"
+                  onClick={handleDraftEmail}
+                  disabled={draftingEmail || generatingSummary}
+                  className="mt-4 ml-2"
+                >
+                  {draftingEmail ? (
+                    <>
+                      <Loader2 className="h-4 w-4 animate-spin mr-2" />
+                      Drafting...
+                    </>
+                  ) : (
+                    <>
+                      <Mail className="h-4 w-4 mr-2" />
+                      Follow-up Email
+                    </>
+                  )}
+                </Button>
+              )}
+              {emailError && (
+                <p className="text-xs text-destructive">{emailError}</p>
+              )}
+              {emailDraft && (
+                <div className="rounded-lg border bg-card p-4 space-y-2">
+                  <div className="flex items-start justify-between gap-2">
+                    <p className="text-sm font-medium">{emailDraft.subject}</p>
+                    <Button
+                      variant="ghost"
+                      size="sm"
+                      onClick={handleCopyEmail}
+                    >
+                      {emailCopied ? (
+                        <Check className="h-3 w-3 mr-1" />
+                      ) : (
+                        <Copy className="h-3 w-3 mr-1" />
+                      )}
+                      {emailCopied ? "Copied" : "Copy"}
+                    </Button>
+                  </div>
+                  <p className="text-sm whitespace-pre-wrap text-muted-foreground">
+                    {emailDraft.body}
+                  </p>
+                </div>
+              )}
             </div>
           )}
 
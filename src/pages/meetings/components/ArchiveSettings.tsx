@@ -0,0 +1,81 @@
+import { useEffect, useState } from "react";
+import { Input, Label, Switch } from "@/components";
+import {
+  getMeetingArchiveConfig,
+  setMeetingArchiveConfig,
+} from "@/lib/database/meetings.action";
+import type { MeetingArchiveConfig } from "@/types/meetings";
+
+/** Parses a positive whole number; empty input means "no limit" */
+function parseLimit(value: string): number | null {
+  const n = parseInt(value, 10);
+  return Number.isFinite(n) && n > 0 ? n : null;
+}
+
+export const ArchiveSettings = () => {
+  const [config, setConfig] = useState<MeetingArchiveConfig | null>(null);
+  const [error, setError] = useState("");
+
+  useEffect(() => {
+    getMeetingArchiveConfig().then(setConfig).catch(console.error);
+  }, []);
+
+  const save = async (next: MeetingArchiveConfig) => {
+    setConfig(next);
+    setError("");
+    try {
+      await setMeetingArchiveConfig(next);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  if (!config) return null;
+
+  return (
+    <section className="border rounded-xl bg-card p-4 space-y-3">
+      <div className="flex items-center justify-between gap-4">
+        <div>
+          <h3 className="text-sm font-medium">Keep meeting audio</h3>
+          <p className="text-xs text-muted-foreground">
+            Save each utterance on disk so a meeting can be re-transcribed
+            later with a better model.
+          </p>
+        </div>
+        <Switch
+          checked={config.enabled}
+          onCheckedChange={(enabled) => save({ ...config, enabled })}
+        />
+      </div>
+      {config.enabled && (
+        <div className="grid gap-3 sm:grid-cols-2">
+          <div className="space-y-1">
+            <Label className="text-xs">Delete after (days)</Label>
+            <Input
+              type="number"
+              min={1}
+              placeholder="Keep forever"
+              defaultValue={config.retention_days ?? ""}
+              onBlur={(e) =>
+                save({ ...config, retention_days: parseLimit(e.target.value) })
+              }
+            />
+          </div>
+          <div className="space-y-1">
+            <Label className="text-xs">Max disk usage (MB)</Label>
+            <Input
+              type="number"
+              min={1}
+              placeholder="No limit"
+              defaultValue={config.max_size_mb ?? ""}
+              onBlur={(e) =>
+                save({ ...config, max_size_mb: parseLimit(e.target.value) })
+              }
+            />
+          </div>
+        </div>
+      )}
+      {error && <p className="text-xs text-red-500">{error}</p>}
+    </section>
+  );
+};
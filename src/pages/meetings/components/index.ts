@@ -1 +1,2 @@
 export * from "./MeetingCard";
+export * from "./ArchiveSettings";
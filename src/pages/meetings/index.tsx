@@ -2,7 +2,7 @@ import { useCallback, useState } from "react";
 import { useNavigate } from "react-router-dom";
 import { PageLayout } from "@/layouts";
 import { Button } from "@/components";
-import { MeetingCard } from "./components";
+import { ArchiveSettings, MeetingCard } from "./components";
 import { useMeetings } from "@/hooks/useMeetings";
 import { useGoogleCalendar } from "@/hooks/useGoogleCalendar";
 import { Loader2, CalendarDays, Plus, ExternalLink } from "lucide-react";
@@ -171,6 +171,8 @@ const Meetings = () => {
           )}
         </section>
 
+        <ArchiveSettings />
+
         {/* Calendar connection prompt */}
         {!calConnected && (
           <section className="border rounded-xl bg-card p-6 text-center">
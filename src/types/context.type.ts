@@ -48,11 +48,13 @@ export type IContextType = {
   selectedAudioDevices: {
     input: { id: string; name: string };
     output: { id: string; name: string };
+    application?: string | null;
   };
   setSelectedAudioDevices: Dispatch<
     SetStateAction<{
       input: { id: string; name: string };
       output: { id: string; name: string };
+      application?: string | null;
     }>
   >;
   setCursorType: (type: CursorType) => void;
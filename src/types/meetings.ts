@@ -8,10 +8,48 @@ export interface Meeting {
   participants: string | null;
   calendarEventId: string | null;
   status: "recording" | "processing" | "completed";
+  /** Directory of the archived audio; null when none is kept */
+  audioPath: string | null;
   createdAt: number;
   updatedAt: number;
 }
 
+/** Mirrors `MeetingArchiveConfig` in Rust */
+export interface MeetingArchiveConfig {
+  enabled: boolean;
+  /** Archives older than this are deleted; null keeps them */
+  retention_days: number | null;
+  /** Oldest archives are deleted past this total; null means no limit */
+  max_size_mb: number | null;
+  encoding: "wav" | "flac" | "opus";
+}
+
+/** Structured meeting notes, from `get_meeting_notes` */
+export interface MeetingNotes {
+  summary: string;
+  decisions: string[];
+  action_items: { task: string; owner: string | null; due: string | null }[];
+  open_questions: string[];
+}
+
+/** Draft from `draft_follow_up_email` */
+export interface FollowUpEmail {
+  subject: string;
+  body: string;
+}
+
+/** Speaker-attributed transcript line, from `get_meeting_segments` */
+export interface MeetingSegment {
+  id: number;
+  source: "me" | "them";
+  /** Diarized remote speaker number; null when unknown */
+  speaker: number | null;
+  /** "Me", "Speaker N" or "Them" */
+  label: string;
+  text: string;
+  created_at: number;
+}
+
 export interface MeetingInput {
   id: string;
   title: string;